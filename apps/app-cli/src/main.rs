@@ -0,0 +1,862 @@
+//! Headless command line frontend for theseus
+//!
+//! Every command prints its result to stdout as JSON, so the binary can be
+//! driven from scripts and CI. Errors are printed to stderr as
+//! `{"error": "..."}` and the process exits with a non-zero status.
+
+use std::path::PathBuf;
+
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use theseus::logs::{LogEventFilter, LogLevel, LogType};
+use theseus::pack::install_curseforge::install_zipped_curseforge_pack;
+use theseus::pack::install_from::{CreatePackLocation, get_profile_from_pack};
+use theseus::pack::install_mrpack::install_zipped_mrpack;
+use theseus::prelude::*;
+use theseus::profile::QuickPlayType;
+use theseus::profile::export::ServerPackFormat;
+use uuid::Uuid;
+
+#[derive(Parser)]
+#[command(name = "oxide", version, about = "Headless Oxide Launcher")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage profiles (instances)
+    #[command(subcommand)]
+    Profile(ProfileCommand),
+    /// Install modpacks
+    #[command(subcommand)]
+    Pack(PackCommand),
+    /// Manage Java installations
+    #[command(subcommand)]
+    Java(JavaCommand),
+    /// Read game logs and crash reports
+    #[command(subcommand)]
+    Logs(LogsCommand),
+    /// Manage launcher caches
+    #[command(subcommand)]
+    Cache(CacheCommand),
+    /// Set the options.txt entries written to every profile before launch,
+    /// or with --defaults, the ones written to new profiles. Prints the
+    /// resulting entries
+    Options {
+        #[arg(long)]
+        defaults: bool,
+        #[command(flatten)]
+        edit: GameOptionsArgs,
+    },
+}
+
+#[derive(Args)]
+struct GameOptionsArgs {
+    /// Entries to set, in options.txt syntax (key:value)
+    #[arg(value_parser = parse_game_option)]
+    set: Vec<(String, String)>,
+    /// Keys of entries to remove
+    #[arg(long)]
+    remove: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// List all profiles
+    List,
+    /// Create a new profile
+    Create(CreateArgs),
+    /// Install (or repair) the game files of a profile
+    Install {
+        /// Profile path, relative to the profiles directory
+        path: String,
+        /// Reinstall even if the profile is already installed
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check the game files of a profile and download missing or corrupted
+    /// ones again
+    Verify { path: String },
+    /// Launch a profile with its preferred account, or else the default one
+    Run {
+        path: String,
+        /// Block until the game exits
+        #[arg(long)]
+        wait: bool,
+        /// Launch without network access, with the cached account name
+        /// and UUID and already downloaded game files
+        #[arg(long)]
+        offline: bool,
+        /// UUID of the account to launch with instead
+        #[arg(long, conflicts_with = "offline")]
+        account: Option<Uuid>,
+    },
+    /// Set the account a profile is launched with, or clear it to use the
+    /// default account
+    PreferAccount {
+        path: String,
+        /// Account UUID. Omit to clear the preferred account
+        account: Option<Uuid>,
+    },
+    /// Allow or disallow launching extra copies of a profile while it is
+    /// already running. Extra copies log to logs/copy-<n>/
+    Concurrent {
+        path: String,
+        #[arg(action = ArgAction::Set)]
+        enabled: bool,
+    },
+    /// Override the memory settings of a profile, starting from the global
+    /// ones. Prints the resulting settings
+    Memory {
+        path: String,
+        /// Maximum heap size in MB
+        #[arg(long)]
+        max: Option<u32>,
+        /// Initial heap size in MB
+        #[arg(long)]
+        min: Option<u32>,
+        /// Garbage collector (g1, zgc, shenandoah)
+        #[arg(long, value_parser = parse_garbage_collector)]
+        gc: Option<GarbageCollector>,
+        /// JVM flag preset (aikar)
+        #[arg(long, value_parser = parse_jvm_preset)]
+        preset: Option<JvmPreset>,
+        /// Size the heap from the number of mods at launch
+        #[arg(long, action = ArgAction::Set)]
+        auto: Option<bool>,
+        /// Remove the override and use the global settings again
+        #[arg(long, conflicts_with_all = ["max", "min", "gc", "preset", "auto"])]
+        clear: bool,
+    },
+    /// Set the options.txt entries written to a profile before launch, on
+    /// top of the global ones. Prints the resulting entries
+    Options {
+        path: String,
+        #[command(flatten)]
+        edit: GameOptionsArgs,
+    },
+    /// List the game files that are missing to launch a profile offline
+    OfflineCheck { path: String },
+    /// Kill all running processes of a profile
+    Kill { path: String },
+    /// Ask the running processes of a profile to quit cleanly, killing the
+    /// ones still running after the timeout. Prints how each was stopped
+    Stop {
+        path: String,
+        /// Seconds to wait for the game to quit
+        #[arg(long, default_value_t = 30)]
+        timeout: u64,
+    },
+    /// Show what the running processes of a profile report about the game,
+    /// such as the world or server being played
+    Status { path: String },
+    /// List the newest finished game sessions of a profile, or of all
+    /// profiles
+    Sessions {
+        path: Option<String>,
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+    },
+    /// Show session totals per profile, or per day over the last days with
+    /// --days
+    Stats {
+        path: Option<String>,
+        #[arg(long)]
+        days: Option<u32>,
+    },
+    /// Add a Modrinth version and its required dependencies to a profile.
+    /// Prints the resolved plan without installing unless --yes is passed
+    Add {
+        path: String,
+        version_id: String,
+        /// Install the plan, even if it has conflicts or unresolved
+        /// dependencies
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Export a profile to a modpack or instance archive
+    Export(ExportArgs),
+    /// Export a profile as a dedicated server, without client-only mods
+    ServerPack {
+        path: String,
+        /// Destination directory, or zip file with --zip
+        output: PathBuf,
+        /// Write a zip instead of a server directory
+        #[arg(long)]
+        zip: bool,
+        /// Files and folders to include. Defaults to everything in the
+        /// profile directory
+        #[arg(long = "include")]
+        included: Vec<String>,
+    },
+    /// List the snapshots taken before updates and modpack installs, newest
+    /// first
+    Snapshots { path: String },
+    /// Restore the files and content settings of a profile to a snapshot
+    Rollback { path: String, snapshot_id: String },
+}
+
+#[derive(Args)]
+struct CreateArgs {
+    name: String,
+    game_version: String,
+    /// vanilla, forge, fabric, quilt or neoforge
+    #[arg(long, default_value = "vanilla")]
+    loader: String,
+    /// Loader version ID, "latest" or "stable"
+    #[arg(long)]
+    loader_version: Option<String>,
+    #[arg(long)]
+    icon: Option<String>,
+    /// Only register the profile, do not download the game
+    #[arg(long)]
+    skip_install: bool,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    path: String,
+    /// Destination file
+    output: PathBuf,
+    #[arg(long, value_enum, default_value_t = ExportFormat::Mrpack)]
+    format: ExportFormat,
+    /// Files and folders to include. Defaults to everything in the profile
+    /// directory. Ignored by the zip format
+    #[arg(long = "include")]
+    included: Vec<String>,
+    #[arg(long)]
+    version_id: Option<String>,
+    /// Only used by the mrpack format
+    #[arg(long)]
+    description: Option<String>,
+    /// Only used by the mrpack format
+    #[arg(long)]
+    name: Option<String>,
+    /// Only used by the curseforge format
+    #[arg(long)]
+    author: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// Modrinth modpack
+    Mrpack,
+    /// CurseForge modpack zip
+    Curseforge,
+    /// MultiMC/Prism Launcher instance zip
+    Mmc,
+    /// Zip of the whole profile directory
+    Zip,
+}
+
+#[derive(Subcommand)]
+enum PackCommand {
+    /// Create a new profile from a .mrpack file
+    Install {
+        file: PathBuf,
+        /// Install into an existing profile instead of creating one
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Create a new profile from a CurseForge modpack zip, or from a
+    /// modpack project and file ID. Lists the files that have to be
+    /// downloaded manually
+    Curseforge {
+        /// Path of the modpack zip
+        #[arg(
+            required_unless_present_all = ["project", "file_id"],
+            conflicts_with_all = ["project", "file_id"]
+        )]
+        file: Option<PathBuf>,
+        /// CurseForge project ID of the modpack
+        #[arg(long, requires = "file_id")]
+        project: Option<i32>,
+        /// CurseForge file ID of the modpack version
+        #[arg(long, requires = "project")]
+        file_id: Option<i32>,
+        /// Install into an existing profile instead of creating one
+        #[arg(long)]
+        profile: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum JavaCommand {
+    /// Download a Java runtime for the given major version
+    Install { version: u32 },
+    /// List the Java versions configured in the launcher
+    List,
+}
+
+#[derive(Subcommand)]
+enum LogsCommand {
+    /// List the logs and crash reports of a profile
+    List { path: String },
+    /// Print the contents of a log file
+    Get {
+        path: String,
+        filename: String,
+        /// Read from the crash reports folder instead of the logs folder
+        #[arg(long)]
+        crash_report: bool,
+    },
+    /// Print the structured log events of the latest launch of a profile
+    Events {
+        path: String,
+        /// Read the events of an extra copy of the profile, by the
+        /// concurrent index of its process
+        #[arg(long, default_value_t = 0)]
+        copy: u32,
+        /// Only events at this level (trace, debug, info, warn, error, fatal)
+        /// or a more severe one
+        #[arg(long, value_parser = parse_log_level)]
+        level: Option<LogLevel>,
+        /// Only events of this logger or of loggers nested in it
+        #[arg(long)]
+        logger: Option<String>,
+        #[arg(long)]
+        thread: Option<String>,
+    },
+    /// Analyze a crash report or JVM fatal error log for suspected mods and
+    /// known causes
+    Crash {
+        path: String,
+        /// Path of the crash file relative to the profile. Defaults to the
+        /// newest one
+        file: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Remove project files from the shared content store that no profile
+    /// uses anymore
+    Gc,
+}
+
+#[derive(Serialize)]
+struct ProfilePathOutput {
+    path: String,
+}
+
+#[derive(Serialize)]
+struct ProcessStatusOutput {
+    #[serde(flatten)]
+    process: ProcessMetadata,
+    game: Option<GameStatus>,
+}
+
+#[derive(Serialize)]
+struct JavaInstallOutput {
+    version: u32,
+    path: PathBuf,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    start_logger();
+
+    let result = async {
+        theseus::EventState::init().await?;
+        State::init().await?;
+
+        execute(cli.command).await
+    }
+    .await;
+
+    match result {
+        Ok(output) => println!("{output}"),
+        Err(err) => {
+            eprintln!("{}", serde_json::json!({ "error": err.to_string() }));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Logs to stderr, as stdout only carries the JSON output. `RUST_LOG`
+/// overrides the default of warnings and errors from theseus
+fn start_logger() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("theseus=warn"));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+async fn execute(command: Command) -> theseus::Result<String> {
+    match command {
+        Command::Profile(command) => execute_profile(command).await,
+        Command::Pack(command) => execute_pack(command).await,
+        Command::Java(command) => execute_java(command).await,
+        Command::Logs(command) => execute_logs(command).await,
+        Command::Cache(command) => execute_cache(command).await,
+        Command::Options { defaults, edit } => {
+            let mut settings = settings::get().await?;
+            let options = if defaults {
+                &mut settings.default_game_options
+            } else {
+                &mut settings.game_options
+            };
+            edit.apply(options);
+            let output = to_json(options)?;
+            settings::set(settings).await?;
+
+            Ok(output)
+        }
+    }
+}
+
+impl GameOptionsArgs {
+    fn apply(&self, options: &mut Vec<(String, String)>) {
+        options.retain(|(key, _)| !self.remove.contains(key));
+        for (key, value) in &self.set {
+            match options.iter_mut().find(|(k, _)| k == key) {
+                Some((_, v)) => v.clone_from(value),
+                None => options.push((key.clone(), value.clone())),
+            }
+        }
+    }
+}
+
+fn parse_game_option(option: &str) -> Result<(String, String), String> {
+    option
+        .split_once(':')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected key:value, got {option}"))
+}
+
+async fn execute_profile(command: ProfileCommand) -> theseus::Result<String> {
+    match command {
+        ProfileCommand::List => to_json(&profile::list().await?),
+        ProfileCommand::Create(args) => {
+            let path = profile::create::profile_create(
+                args.name,
+                args.game_version,
+                ModLoader::from_string(&args.loader.to_lowercase()),
+                args.loader_version,
+                args.icon,
+                None,
+                Some(args.skip_install),
+            )
+            .await?;
+
+            to_json(&ProfilePathOutput { path })
+        }
+        ProfileCommand::Install { path, force } => {
+            profile::install(&path, force).await?;
+            to_json(&ProfilePathOutput { path })
+        }
+        ProfileCommand::Verify { path } => {
+            to_json(&profile::verify_profile(&path).await?)
+        }
+        ProfileCommand::Run {
+            path,
+            wait,
+            offline,
+            account,
+        } => {
+            let process = if offline {
+                profile::run_offline(&path, QuickPlayType::None).await?
+            } else if let Some(account) = account {
+                profile::run_with_account(&path, account, QuickPlayType::None)
+                    .await?
+            } else {
+                profile::run(&path, QuickPlayType::None).await?
+            };
+            if wait {
+                process::wait_for(process.uuid).await?;
+            }
+
+            to_json(&process)
+        }
+        ProfileCommand::PreferAccount { path, account } => {
+            profile::edit(&path, |profile| {
+                profile.preferred_account = account;
+                async { Ok(()) }
+            })
+            .await?;
+
+            to_json(&ProfilePathOutput { path })
+        }
+        ProfileCommand::Concurrent { path, enabled } => {
+            profile::edit(&path, |profile| {
+                profile.allow_concurrent_instances = enabled;
+                async { Ok(()) }
+            })
+            .await?;
+
+            to_json(&ProfilePathOutput { path })
+        }
+        ProfileCommand::Memory {
+            path,
+            max,
+            min,
+            gc,
+            preset,
+            auto,
+            clear,
+        } => {
+            let settings = settings::get().await?;
+            profile::edit(&path, |profile| {
+                profile.memory = (!clear).then(|| {
+                    let mut memory = profile.memory.unwrap_or(settings.memory);
+                    if let Some(max) = max {
+                        memory.maximum = max;
+                    }
+                    memory.minimum = min.or(memory.minimum);
+                    memory.garbage_collector = gc.or(memory.garbage_collector);
+                    memory.preset = preset.or(memory.preset);
+                    memory.automatic = auto.unwrap_or(memory.automatic);
+                    memory
+                });
+                async { Ok(()) }
+            })
+            .await?;
+
+            let memory = profile::get(&path)
+                .await?
+                .and_then(|profile| profile.memory);
+            to_json(&memory.unwrap_or(settings.memory))
+        }
+        ProfileCommand::Options { path, edit } => {
+            profile::edit(&path, |profile| {
+                edit.apply(&mut profile.game_options);
+                async { Ok(()) }
+            })
+            .await?;
+
+            let options = profile::get(&path)
+                .await?
+                .map(|profile| profile.game_options)
+                .unwrap_or_default();
+            to_json(&options)
+        }
+        ProfileCommand::OfflineCheck { path } => {
+            to_json(&profile::get_offline_missing_files(&path).await?)
+        }
+        ProfileCommand::Kill { path } => {
+            profile::kill(&path).await?;
+            to_json(&ProfilePathOutput { path })
+        }
+        ProfileCommand::Stop { path, timeout } => to_json(
+            &profile::stop(
+                &path,
+                Some(std::time::Duration::from_secs(timeout)),
+            )
+            .await?,
+        ),
+        ProfileCommand::Status { path } => {
+            let mut statuses = Vec::new();
+            for process in process::get_by_profile_path(&path).await? {
+                statuses.push(ProcessStatusOutput {
+                    game: process::get_game_status(process.uuid).await?,
+                    process,
+                });
+            }
+            to_json(&statuses)
+        }
+        ProfileCommand::Sessions { path, limit } => {
+            to_json(&process::get_sessions(path.as_deref(), limit).await?)
+        }
+        ProfileCommand::Stats { path, days } => match days {
+            Some(days) => {
+                let since =
+                    chrono::Utc::now() - chrono::Duration::days(days.into());
+                to_json(
+                    &process::get_daily_session_stats(path.as_deref(), since)
+                        .await?,
+                )
+            }
+            None => {
+                let mut stats = process::get_profile_session_stats().await?;
+                if let Some(path) = path {
+                    stats.retain(|x| x.profile_path == path);
+                }
+                to_json(&stats)
+            }
+        },
+        ProfileCommand::Add {
+            path,
+            version_id,
+            yes,
+        } => {
+            let plan =
+                profile::dependencies::resolve_dependencies(&path, &version_id)
+                    .await?;
+            if !yes {
+                return to_json(&plan);
+            }
+
+            to_json(&profile::dependencies::install_plan(&plan).await?)
+        }
+        ProfileCommand::Export(args) => {
+            let included =
+                included_or_all_candidates(&args.path, args.included).await?;
+
+            match args.format {
+                ExportFormat::Mrpack => {
+                    profile::export_mrpack(
+                        &args.path,
+                        args.output.clone(),
+                        included,
+                        args.version_id,
+                        args.description,
+                        args.name,
+                    )
+                    .await?
+                }
+                ExportFormat::Curseforge => {
+                    profile::export::export_curseforge(
+                        &args.path,
+                        args.output.clone(),
+                        included,
+                        args.version_id,
+                        args.author,
+                    )
+                    .await?
+                }
+                ExportFormat::Mmc => {
+                    profile::export::export_mmc(
+                        &args.path,
+                        args.output.clone(),
+                        included,
+                    )
+                    .await?
+                }
+                ExportFormat::Zip => {
+                    profile::export::export_instance_zip(
+                        &args.path,
+                        args.output.clone(),
+                    )
+                    .await?
+                }
+            }
+
+            to_json(&serde_json::json!({ "path": args.output }))
+        }
+        ProfileCommand::ServerPack {
+            path,
+            output,
+            zip,
+            included,
+        } => {
+            let included = included_or_all_candidates(&path, included).await?;
+            let format = if zip {
+                ServerPackFormat::Zip
+            } else {
+                ServerPackFormat::Directory
+            };
+            let excluded = profile::export::export_server_pack(
+                &path,
+                output.clone(),
+                included,
+                format,
+            )
+            .await?;
+
+            to_json(&serde_json::json!({
+                "path": output,
+                "excluded": excluded,
+            }))
+        }
+        ProfileCommand::Snapshots { path } => {
+            to_json(&profile::rollback::list_rollback_snapshots(&path).await?)
+        }
+        ProfileCommand::Rollback { path, snapshot_id } => {
+            profile::rollback::rollback_profile(&path, &snapshot_id).await?;
+            to_json(&ProfilePathOutput { path })
+        }
+    }
+}
+
+async fn included_or_all_candidates(
+    path: &str,
+    included: Vec<String>,
+) -> theseus::Result<Vec<String>> {
+    if !included.is_empty() {
+        return Ok(included);
+    }
+
+    Ok(profile::get_pack_export_candidates(path)
+        .await?
+        .into_iter()
+        .map(|x| x.to_string())
+        .collect())
+}
+
+async fn execute_pack(command: PackCommand) -> theseus::Result<String> {
+    match command {
+        PackCommand::Install { file, profile } => {
+            let location = CreatePackLocation::FromFile { path: file };
+
+            let profile_path = match profile {
+                Some(profile_path) => profile_path,
+                None => {
+                    let creator = get_profile_from_pack(location.clone());
+                    profile::create::profile_create(
+                        creator.name,
+                        creator.game_version,
+                        creator.modloader,
+                        creator.loader_version,
+                        None,
+                        None,
+                        Some(true),
+                    )
+                    .await?
+                }
+            };
+
+            let path = install_zipped_mrpack(location, profile_path).await?;
+            to_json(&ProfilePathOutput { path })
+        }
+        PackCommand::Curseforge {
+            file,
+            project,
+            file_id,
+            profile,
+        } => {
+            let location = match (file, project, file_id) {
+                (Some(path), _, _) => {
+                    CreatePackLocation::FromCurseForgeZip { path }
+                }
+                (None, Some(project_id), Some(file_id)) => {
+                    CreatePackLocation::FromCurseForgeId {
+                        project_id,
+                        file_id,
+                        title: format!("CurseForge modpack {project_id}"),
+                        icon_url: None,
+                    }
+                }
+                _ => {
+                    return Err(theseus::ErrorKind::InputError(
+                        "Specify a modpack zip or a project and file ID"
+                            .to_string(),
+                    )
+                    .into());
+                }
+            };
+
+            let profile_path = match profile {
+                Some(profile_path) => profile_path,
+                None => {
+                    let creator = get_profile_from_pack(location.clone());
+                    profile::create::profile_create(
+                        creator.name,
+                        creator.game_version,
+                        creator.modloader,
+                        creator.loader_version,
+                        None,
+                        None,
+                        Some(true),
+                    )
+                    .await?
+                }
+            };
+
+            to_json(
+                &install_zipped_curseforge_pack(location, profile_path).await?,
+            )
+        }
+    }
+}
+
+async fn execute_java(command: JavaCommand) -> theseus::Result<String> {
+    match command {
+        JavaCommand::Install { version } => {
+            let path = jre::auto_install_java(version).await?;
+            jre::set_java_version(jre::check_jre(path.clone()).await?).await?;
+
+            to_json(&JavaInstallOutput { version, path })
+        }
+        JavaCommand::List => {
+            let versions = jre::get_java_versions()
+                .await?
+                .into_iter()
+                .map(|(_, version)| version)
+                .collect::<Vec<_>>();
+
+            to_json(&versions)
+        }
+    }
+}
+
+async fn execute_logs(command: LogsCommand) -> theseus::Result<String> {
+    match command {
+        LogsCommand::List { path } => {
+            to_json(&theseus::logs::get_logs(&path, Some(true)).await?)
+        }
+        LogsCommand::Get {
+            path,
+            filename,
+            crash_report,
+        } => {
+            let log_type = if crash_report {
+                LogType::CrashReport
+            } else {
+                LogType::InfoLog
+            };
+            let output = theseus::logs::get_output_by_filename(
+                &path, log_type, &filename,
+            )
+            .await?;
+
+            to_json(&serde_json::json!({
+                "filename": filename,
+                "output": output,
+            }))
+        }
+        LogsCommand::Events {
+            path,
+            copy,
+            level,
+            logger,
+            thread,
+        } => {
+            let filter = LogEventFilter {
+                min_level: level,
+                logger,
+                thread,
+            };
+            let events =
+                theseus::logs::get_log_events_cursor(&path, copy, 0, filter)
+                    .await?
+                    .events;
+
+            to_json(&events)
+        }
+        LogsCommand::Crash { path, file } => match file {
+            Some(file) => to_json(&crash::analyze_crash(&path, &file).await?),
+            None => to_json(&crash::analyze_latest_crash(&path).await?),
+        },
+    }
+}
+
+fn parse_garbage_collector(gc: &str) -> Result<GarbageCollector, String> {
+    GarbageCollector::from_string(gc)
+        .ok_or_else(|| format!("unknown garbage collector {gc}"))
+}
+
+fn parse_jvm_preset(preset: &str) -> Result<JvmPreset, String> {
+    JvmPreset::from_string(preset)
+        .ok_or_else(|| format!("unknown preset {preset}"))
+}
+
+fn parse_log_level(level: &str) -> Result<LogLevel, String> {
+    LogLevel::from_string(level)
+        .ok_or_else(|| format!("unknown log level {level}"))
+}
+
+async fn execute_cache(command: CacheCommand) -> theseus::Result<String> {
+    match command {
+        CacheCommand::Gc => to_json(&theseus::cache::gc_content_store().await?),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> theseus::Result<String> {
+    Ok(serde_json::to_string_pretty(value)?)
+}
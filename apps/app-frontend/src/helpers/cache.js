@@ -51,3 +51,7 @@ export async function get_search_results_many(ids, cacheBehaviour) {
 export async function purge_cache_types(cacheTypes) {
 	return await invoke('plugin:cache|purge_cache_types', { cacheTypes })
 }
+
+export async function gc_content_store() {
+	return await invoke('plugin:cache|gc_content_store')
+}
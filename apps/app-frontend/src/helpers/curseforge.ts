@@ -190,6 +190,7 @@ export type CacheBehaviour =
 	| 'stale_while_revalidate'
 	| 'must_revalidate'
 	| 'bypass'
+	| 'cache_only'
 
 export interface CfCachedProject {
 	id: number
@@ -49,7 +49,9 @@ export async function loading_listener(callback) {
     ProcessPayload {
         uuid: unique identification of the process in the state (currently identified by PID, but that will change)
         pid: process ID
-        event: event type ("Launched", "Finished")
+        event: event type ("launched", "finished", "world_left", or an object for events
+            reported by the game: { world_joined: { world } }, { server_joined: { host, port } },
+            { fps_updated: { fps } }, { mods_loaded: { mods } })
         message: message to display to the user
     }
 */
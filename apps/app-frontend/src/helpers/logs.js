@@ -63,3 +63,52 @@ export async function delete_logs(profilePath) {
 export async function get_latest_log_cursor(profilePath, cursor) {
 	return await invoke('plugin:logs|logs_get_latest_log_cursor', { profilePath, cursor })
 }
+
+// From the launcher log of an extra copy of a running profile, by the concurrent_index of its process
+export async function get_concurrent_log_cursor(profilePath, concurrentIndex, cursor) {
+	return await invoke('plugin:logs|logs_get_concurrent_log_cursor', {
+		profilePath,
+		concurrentIndex,
+		cursor,
+	})
+}
+
+/// Get the structured log events of a running profile, from the sidecar of its launcher log
+/// filter: { min_level: 'TRACE' | 'DEBUG' | 'INFO' | 'WARN' | 'ERROR' | 'FATAL' | null, logger: String | null, thread: String | null }
+/// Returns:
+/*
+  {
+    cursor: u64
+    events: [{ timestamp, logger, level, thread, message, throwable }]
+    new_file: bool
+  }
+*/
+export async function get_log_events_cursor(profilePath, concurrentIndex, cursor, filter) {
+	return await invoke('plugin:logs|logs_get_log_events_cursor', {
+		profilePath,
+		concurrentIndex,
+		cursor,
+		filter,
+	})
+}
+
+/// Get the crash reports and JVM fatal error logs (hs_err_pid*.log) of a profile, newest first
+/// Returns [{ path, file_type: 'crash_report' | 'jvm_fatal_error', age }]
+export async function get_crash_files(profilePath) {
+	return await invoke('plugin:logs|logs_get_crash_files', { profilePath })
+}
+
+/// Analyze a crash file by its path from get_crash_files, or the newest one if crashPath is null
+/// Returns null if the profile has no crash files, otherwise:
+/*
+  {
+    file: { path, file_type, age }
+    description: String | null
+    error: String | null
+    suspected_mods: [{ mod_id, path, project_id, reasons: [{ type: 'reported_by_loader' | 'stack_frame' | 'mixin', ... }] }]
+    known_causes: [{ type: 'wrong_java_version' | 'out_of_memory' | 'missing_dependency' | 'duplicate_mod', fix, ... }]
+  }
+*/
+export async function analyze_crash(profilePath, crashPath) {
+	return await invoke('plugin:logs|logs_analyze_crash', { profilePath, crashPath })
+}
@@ -63,3 +63,51 @@ export async function create_profile_and_install_from_file(path) {
 	)
 	return await invoke('plugin:pack|pack_install', { location, profile })
 }
+
+// Installs a CurseForge modpack from a project and file ID
+// Returns the profile path and the files the pack author only allows to be downloaded manually
+export async function create_profile_and_install_from_curseforge(
+	projectId,
+	fileId,
+	packTitle,
+	iconUrl,
+	createInstanceCallback = () => {},
+) {
+	const location = {
+		type: 'fromCurseForgeId',
+		project_id: projectId,
+		file_id: fileId,
+		title: packTitle,
+		icon_url: iconUrl,
+	}
+	const profile_creator = await invoke('plugin:pack|pack_get_profile_from_pack', { location })
+	const profile = await create(
+		profile_creator.name,
+		profile_creator.gameVersion,
+		profile_creator.modloader,
+		profile_creator.loaderVersion,
+		null,
+		true,
+	)
+	createInstanceCallback(profile)
+
+	return await invoke('plugin:pack|pack_install_curseforge', { location, profile })
+}
+
+// Installs a CurseForge modpack zip from a path
+export async function create_profile_and_install_from_curseforge_file(path) {
+	const location = {
+		type: 'fromCurseForgeZip',
+		path: path,
+	}
+	const profile_creator = await invoke('plugin:pack|pack_get_profile_from_pack', { location })
+	const profile = await create(
+		profile_creator.name,
+		profile_creator.gameVersion,
+		profile_creator.modloader,
+		profile_creator.loaderVersion,
+		null,
+		true,
+	)
+	return await invoke('plugin:pack|pack_install_curseforge', { location, profile })
+}
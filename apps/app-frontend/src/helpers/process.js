@@ -17,6 +17,36 @@ export async function get_all() {
 	return await invoke('plugin:process|process_get_all')
 }
 
+/// Gets what the game running in a process last reported through the launcher RPC
+/// Returns { world, server: { host, port }, fps, mods: [{ id, name, version }] } or null
+export async function get_game_status(uuid) {
+	return await invoke('plugin:process|process_get_game_status', { uuid })
+}
+
+/// Gets the newest finished sessions of a profile, or of all profiles if profilePath is null
+/// Returns [{ id, profile_path, start_time, end_time, exit_code, account, worlds, servers, crashed }]
+export async function get_sessions(profilePath, limit) {
+	return await invoke('plugin:process|process_get_sessions', { profilePath, limit })
+}
+
+/// Gets per-day session totals since an ISO timestamp, for a profile or for all profiles
+/// Returns [{ date, sessions, play_time, crashes }]
+export async function get_daily_session_stats(profilePath, since) {
+	return await invoke('plugin:process|process_get_daily_session_stats', { profilePath, since })
+}
+
+/// Gets the session totals of every profile, the ones that crash most first
+/// Returns [{ profile_path, sessions, play_time, average_length, longest_session, crashes, last_session }]
+export async function get_profile_session_stats() {
+	return await invoke('plugin:process|process_get_profile_session_stats')
+}
+
+/// Asks a process to quit cleanly, and kills it if it is still running after timeoutSecs (30 by default)
+/// Returns 'rpc', 'stdin' or 'killed'
+export async function stop(uuid, timeoutSecs) {
+	return await invoke('plugin:process|process_stop', { uuid, timeoutSecs })
+}
+
 /// Kills a process by UUID
 export async function kill(uuid) {
 	return await invoke('plugin:process|process_kill', { uuid })
@@ -90,22 +90,69 @@ export async function install(path, force) {
 	return await invoke('plugin:profile|profile_install', { path, force })
 }
 
+// Verifies the game files of an installed profile and downloads missing or corrupted ones again
+// Returns { checked_files, missing_files, corrupted_files, natives_extracted }
+export async function verify(path) {
+	return await invoke('plugin:profile|profile_verify', { path })
+}
+
 // Updates all of a profile's projects
 export async function update_all(path) {
 	return await invoke('plugin:profile|profile_update_all', { path })
 }
 
+// Lists the updates update_all would apply, as old and new versions
+export async function preview_update_all(path) {
+	return await invoke('plugin:profile|profile_preview_update_all', { path })
+}
+
 // Updates a specified project
 export async function update_project(path, projectPath) {
 	return await invoke('plugin:profile|profile_update_project', { path, projectPath })
 }
 
+// Lists the snapshots taken before updates and modpack installs, newest first
+export async function list_rollback_snapshots(path) {
+	return await invoke('plugin:profile|profile_list_rollback_snapshots', { path })
+}
+
+// Restores the files and content settings of a profile to a snapshot
+export async function rollback(path, snapshotId) {
+	return await invoke('plugin:profile|profile_rollback', { path, snapshotId })
+}
+
+// Deletes a rollback snapshot, returning the bytes freed
+export async function delete_rollback_snapshot(path, snapshotId) {
+	return await invoke('plugin:profile|profile_delete_rollback_snapshot', { path, snapshotId })
+}
+
+// Sets how a project is kept up to date, or resets it if policy is null
+export async function edit_update_policy(path, projectPath, policy) {
+	return await invoke('plugin:profile|profile_edit_update_policy', {
+		path,
+		projectPath,
+		policy,
+	})
+}
+
 // Add a project to a profile from a version
 // Returns a path to the new project file
 export async function add_project_from_version(path, versionId) {
 	return await invoke('plugin:profile|profile_add_project_from_version', { path, versionId })
 }
 
+// Resolve the required dependencies and incompatibilities of a version, without downloading anything
+// Returns a plan to be confirmed with install_plan
+export async function resolve_dependencies(path, versionId) {
+	return await invoke('plugin:profile|profile_resolve_dependencies', { path, versionId })
+}
+
+// Install the versions of a plan returned by resolve_dependencies
+// Returns the paths to the new project files
+export async function install_plan(plan) {
+	return await invoke('plugin:profile|profile_install_plan', { plan })
+}
+
 // Add a project to a profile from a path + project_type
 // Returns a path to the new project file
 export async function add_project_from_path(path, projectPath, projectType) {
@@ -139,6 +186,21 @@ export async function update_repair_modrinth(path) {
 	return await invoke('plugin:profile|profile_repair_managed_modrinth', { path })
 }
 
+// Update a managed CurseForge profile to a specific file
+// Returns the files that must be downloaded manually
+export async function update_managed_curseforge_version(path, fileId) {
+	return await invoke('plugin:profile|profile_update_managed_curseforge_version', {
+		path,
+		fileId,
+	})
+}
+
+// Repair a managed CurseForge profile
+// Returns the files that must be downloaded manually
+export async function update_repair_curseforge(path) {
+	return await invoke('plugin:profile|profile_repair_managed_curseforge', { path })
+}
+
 // Export a profile to .mrpack
 /// included_overrides is an array of paths to override folders to include (ie: 'mods', 'resource_packs')
 // Version id is optional (ie: 1.1.5)
@@ -160,6 +222,50 @@ export async function export_profile_mrpack(
 	})
 }
 
+// Export a profile to a CurseForge modpack zip
+// Files CurseForge knows are referenced by ID, everything else is added as an override
+export async function export_profile_curseforge(
+	path,
+	exportLocation,
+	includedOverrides,
+	versionId,
+	author,
+) {
+	return await invoke('plugin:profile|profile_export_curseforge', {
+		path,
+		exportLocation,
+		includedOverrides,
+		versionId,
+		author,
+	})
+}
+
+// Export a profile to a MultiMC/Prism Launcher instance zip
+export async function export_profile_mmc(path, exportLocation, includedOverrides) {
+	return await invoke('plugin:profile|profile_export_mmc', {
+		path,
+		exportLocation,
+		includedOverrides,
+	})
+}
+
+// Export every file of a profile to a zip
+export async function export_profile_instance_zip(path, exportLocation) {
+	return await invoke('plugin:profile|profile_export_instance_zip', { path, exportLocation })
+}
+
+// Export a profile as a dedicated server, leaving out client-only mods
+// format is 'directory' or 'zip'
+// Returns the paths of the client-only files that were left out
+export async function export_profile_server_pack(path, exportLocation, includedOverrides, format) {
+	return await invoke('plugin:profile|profile_export_server_pack', {
+		path,
+		exportLocation,
+		includedOverrides,
+		format,
+	})
+}
+
 // Given a folder path, populate an array of all the subfolders
 // Intended to be used for finding potential override folders
 // profile
@@ -178,10 +284,33 @@ export async function run(path) {
 	return await invoke('plugin:profile|profile_run', { path })
 }
 
+// Run Minecraft without network access, using the cached name and UUID of the preferred or default account
+// Fails listing the missing files if the game is not fully downloaded
+export async function run_offline(path) {
+	return await invoke('plugin:profile|profile_run_offline', { path })
+}
+
+// Run Minecraft using the given account instead of the default or preferred one
+// Returns PID of child
+export async function run_with_account(path, accountUuid) {
+	return await invoke('plugin:profile|profile_run_with_account', { path, accountUuid })
+}
+
+// Lists the game files that are missing to launch a profile offline
+export async function get_offline_missing_files(path) {
+	return await invoke('plugin:profile|profile_get_offline_missing_files', { path })
+}
+
 export async function kill(path) {
 	return await invoke('plugin:profile|profile_kill', { path })
 }
 
+// Asks the running game to quit cleanly, and kills it if it is still running after timeoutSecs (30 by default)
+// Returns ['rpc' | 'stdin' | 'killed'], one per process
+export async function stop(path, timeoutSecs) {
+	return await invoke('plugin:profile|profile_stop', { path, timeoutSecs })
+}
+
 // Edits a profile
 export async function edit(path, editProfile) {
 	return await invoke('plugin:profile|profile_edit', { path, editProfile })
@@ -195,6 +324,10 @@ export async function edit_icon(path, iconPath) {
 export async function finish_install(instance) {
 	if (instance.install_stage !== 'pack_installed') {
 		let linkedData = instance.linked_data
+		if (linkedData.provider === 'curseforge') {
+			await update_repair_curseforge(instance.path)
+			return
+		}
 		await install_to_existing_profile(
 			linkedData.project_id,
 			linkedData.version_id,
@@ -53,6 +53,8 @@ export type AppSettings = {
 
 	extra_launch_args: string[]
 	custom_env_vars: [string, string][]
+	game_options: [string, string][]
+	default_game_options: [string, string][]
 	memory: MemorySettings
 	force_fullscreen: boolean
 	game_resolution: WindowSize
@@ -25,11 +25,17 @@ type GameInstance = {
 	java_path?: string
 	extra_launch_args?: string[]
 	custom_env_vars?: [string, string][]
+	game_options: [string, string][]
+	// Keyed by path relative to the instance, without .disabled
+	update_policies: Record<string, UpdatePolicy>
 
 	memory?: MemorySettings
 	force_fullscreen?: boolean
 	game_resolution?: [number, number]
 	hooks: Hooks
+
+	preferred_account?: string
+	allow_concurrent_instances: boolean
 }
 
 type InstallStage =
@@ -40,6 +46,8 @@ type InstallStage =
 	| 'not_installed'
 
 type LinkedData = {
+	provider: 'modrinth' | 'curseforge'
+	// For CurseForge packs, these are the mod and file IDs
 	project_id: ModrinthId
 	version_id: ModrinthId
 
@@ -58,10 +66,39 @@ type ContentFile = {
 }
 
 type FileMetadata = {
+	provider: 'modrinth' | 'curseforge'
 	project_id: string
 	version_id: string
 }
 
+type UpdatePolicy = {
+	pinned: boolean
+	channel: UpdateChannel
+	// Game version to look for updates for, instead of the instance's
+	game_version?: string
+}
+
+type UpdateChannel = 'release' | 'beta' | 'alpha'
+
+type ProjectUpdate = {
+	path: string
+	provider: 'modrinth' | 'curseforge'
+	project_id: string
+	old_version_id: string
+	new_version_id: string
+	old_version_name?: string
+	new_version_name?: string
+}
+
+type RollbackSnapshot = {
+	id: string
+	operation: 'update_all_projects' | 'update_modpack' | 'repair_modpack' | 'install_modpack'
+	created: Date
+	file_count: number
+	size: number
+	added_size: number
+}
+
 type ContentFileProjectType = 'mod' | 'datapack' | 'resourcepack' | 'shaderpack'
 
 type CacheBehaviour =
@@ -73,11 +110,21 @@ type CacheBehaviour =
 	| 'must_revalidate'
 	// Ignore cache- always fetch updated data from origin
 	| 'bypass'
+	// Only serve cached data, even if expired. Never fetch from origin
+	| 'cache_only'
 
 type MemorySettings = {
 	maximum: number
+	minimum?: number
+	garbage_collector?: GarbageCollector
+	preset?: JvmPreset
+	automatic: boolean
 }
 
+type GarbageCollector = 'g1' | 'zgc' | 'shenandoah'
+
+type JvmPreset = 'aikar'
+
 type WindowSize = {
 	width: number
 	height: number
@@ -125,6 +172,8 @@ type AppSettings = {
 
 	extra_launch_args: string[]
 	custom_env_vars: [string, string][]
+	game_options: [string, string][]
+	default_game_options: [string, string][]
 	memory: MemorySettings
 	force_fullscreen: boolean
 	game_resolution: [number, number]
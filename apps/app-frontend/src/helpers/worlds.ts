@@ -70,6 +70,41 @@ export interface Chat {
 	extra: Chat[]
 }
 
+export type WorldBackup = {
+	file_name: string
+	world: string
+	created: string
+	size: number
+}
+
+export type WorldSnapshot = {
+	id: string
+	world: string
+	created: string
+	file_count: number
+	size: number
+	added_size: number
+	live: boolean
+}
+
+export type BackupRetention = {
+	keep_last?: number
+	keep_daily_days?: number
+	max_total_size?: number
+}
+
+export type WorldBackupSchedule = {
+	on_exit: boolean
+	interval_minutes?: number
+	daily: boolean
+	retention: BackupRetention
+}
+
+export type PruneResult = {
+	removed_snapshots: string[]
+	freed_bytes: number
+}
+
 export type ServerData = {
 	refreshing: boolean
 	lastSuccessfulRefresh?: number
@@ -131,6 +166,74 @@ export async function backup_world(instance: string, world: string): Promise<num
 	return await invoke('plugin:worlds|backup_world', { instance, world })
 }
 
+export async function list_world_backups(
+	instance: string,
+	world?: string,
+): Promise<WorldBackup[]> {
+	return await invoke('plugin:worlds|list_world_backups', { instance, world })
+}
+
+export async function restore_world_backup(
+	instance: string,
+	fileName: string,
+	asNewWorld: boolean,
+): Promise<string> {
+	return await invoke('plugin:worlds|restore_world_backup', { instance, fileName, asNewWorld })
+}
+
+export async function create_world_snapshot(
+	instance: string,
+	world: string,
+): Promise<WorldSnapshot> {
+	return await invoke('plugin:worlds|create_world_snapshot', { instance, world })
+}
+
+export async function list_world_snapshots(
+	instance: string,
+	world?: string,
+): Promise<WorldSnapshot[]> {
+	return await invoke('plugin:worlds|list_world_snapshots', { instance, world })
+}
+
+export async function restore_world_snapshot(
+	instance: string,
+	snapshotId: string,
+	asNewWorld: boolean,
+): Promise<string> {
+	return await invoke('plugin:worlds|restore_world_snapshot', {
+		instance,
+		snapshotId,
+		asNewWorld,
+	})
+}
+
+export async function delete_world_snapshot(instance: string, snapshotId: string): Promise<number> {
+	return await invoke('plugin:worlds|delete_world_snapshot', { instance, snapshotId })
+}
+
+export async function prune_world_snapshots(
+	instance: string,
+	world: string,
+	keepLast: number,
+): Promise<PruneResult> {
+	return await invoke('plugin:worlds|prune_world_snapshots', { instance, world, keepLast })
+}
+
+export async function get_world_backup_schedule(
+	instance: string,
+	world?: string,
+): Promise<WorldBackupSchedule | null> {
+	return await invoke('plugin:worlds|get_world_backup_schedule', { instance, world })
+}
+
+export async function set_world_backup_schedule(
+	instance: string,
+	world: string | undefined,
+	schedule: WorldBackupSchedule | null,
+): Promise<void> {
+	return await invoke('plugin:worlds|set_world_backup_schedule', { instance, world, schedule })
+}
+
 export async function delete_world(instance: string, world: string): Promise<void> {
 	return await invoke('plugin:worlds|delete_world', { instance, world })
 }
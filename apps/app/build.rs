@@ -40,6 +40,7 @@ fn main() {
                         "get_search_results",
                         "get_search_results_many",
                         "purge_cache_types",
+                        "gc_content_store",
                     ])
                     .default_permission(
                         DefaultPermissionRule::AllowAllCommands,
@@ -116,6 +117,10 @@ fn main() {
                         "logs_delete_logs",
                         "logs_delete_logs_by_filename",
                         "logs_get_latest_log_cursor",
+                        "logs_get_concurrent_log_cursor",
+                        "logs_get_log_events_cursor",
+                        "logs_get_crash_files",
+                        "logs_analyze_crash",
                     ])
                     .default_permission(
                         DefaultPermissionRule::AllowAllCommands,
@@ -166,7 +171,11 @@ fn main() {
             .plugin(
                 "pack",
                 InlinedPlugin::new()
-                    .commands(&["pack_install", "pack_get_profile_from_pack"])
+                    .commands(&[
+                        "pack_install",
+                        "pack_install_curseforge",
+                        "pack_get_profile_from_pack",
+                    ])
                     .default_permission(
                         DefaultPermissionRule::AllowAllCommands,
                     ),
@@ -177,7 +186,12 @@ fn main() {
                     .commands(&[
                         "process_get_all",
                         "process_get_by_profile_path",
+                        "process_get_game_status",
+                        "process_get_sessions",
+                        "process_get_daily_session_stats",
+                        "process_get_profile_session_stats",
                         "process_kill",
+                        "process_stop",
                         "process_wait_for",
                     ])
                     .default_permission(
@@ -198,19 +212,37 @@ fn main() {
                         "profile_list",
                         "profile_check_installed",
                         "profile_install",
+                        "profile_verify",
                         "profile_update_all",
+                        "profile_preview_update_all",
                         "profile_update_project",
+                        "profile_edit_update_policy",
+                        "profile_list_rollback_snapshots",
+                        "profile_rollback",
+                        "profile_delete_rollback_snapshot",
                         "profile_add_project_from_version",
+                        "profile_resolve_dependencies",
+                        "profile_install_plan",
                         "profile_add_project_from_path",
                         "profile_toggle_disable_project",
                         "profile_remove_project",
                         "profile_update_managed_modrinth_version",
                         "profile_repair_managed_modrinth",
+                        "profile_update_managed_curseforge_version",
+                        "profile_repair_managed_curseforge",
                         "profile_run",
+                        "profile_run_offline",
+                        "profile_run_with_account",
+                        "profile_get_offline_missing_files",
                         "profile_kill",
+                        "profile_stop",
                         "profile_edit",
                         "profile_edit_icon",
                         "profile_export_mrpack",
+                        "profile_export_curseforge",
+                        "profile_export_mmc",
+                        "profile_export_instance_zip",
+                        "profile_export_server_pack",
                         "profile_get_pack_export_candidates",
                     ])
                     .default_permission(
@@ -308,6 +340,15 @@ fn main() {
                         "rename_world",
                         "reset_world_icon",
                         "backup_world",
+                        "list_world_backups",
+                        "restore_world_backup",
+                        "create_world_snapshot",
+                        "list_world_snapshots",
+                        "restore_world_snapshot",
+                        "delete_world_snapshot",
+                        "prune_world_snapshots",
+                        "get_world_backup_schedule",
+                        "set_world_backup_schedule",
                         "delete_world",
                         "add_server_to_profile",
                         "edit_server_in_profile",
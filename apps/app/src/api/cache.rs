@@ -53,6 +53,7 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             get_search_results,
             get_search_results_many,
             purge_cache_types,
+            gc_content_store,
         ])
         .build()
 }
@@ -61,3 +62,8 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
 pub async fn purge_cache_types(cache_types: Vec<CacheValueType>) -> Result<()> {
     Ok(theseus::cache::purge_cache_types(&cache_types).await?)
 }
+
+#[tauri::command]
+pub async fn gc_content_store() -> Result<ContentStoreGcResult> {
+    Ok(theseus::cache::gc_content_store().await?)
+}
@@ -1,6 +1,10 @@
 use crate::api::Result;
+use theseus::crash::{self, CrashAnalysis, CrashFile};
 use theseus::logs::LogType;
-use theseus::logs::{self, CensoredString, LatestLogCursor, Logs};
+use theseus::logs::{
+    self, CensoredString, LatestLogCursor, LogEventFilter, LogEventsCursor,
+    Logs,
+};
 
 /*
 A log is a struct containing the filename string, stdout, and stderr, as follows:
@@ -21,6 +25,10 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             logs_delete_logs,
             logs_delete_logs_by_filename,
             logs_get_latest_log_cursor,
+            logs_get_concurrent_log_cursor,
+            logs_get_log_events_cursor,
+            logs_get_crash_files,
+            logs_analyze_crash,
         ])
         .build()
 }
@@ -83,3 +91,56 @@ pub async fn logs_get_latest_log_cursor(
 ) -> Result<LatestLogCursor> {
     Ok(logs::get_latest_log_cursor(profile_path, cursor).await?)
 }
+
+/// Get live log from the launcher log of an extra copy of a running profile
+#[tauri::command]
+pub async fn logs_get_concurrent_log_cursor(
+    profile_path: &str,
+    concurrent_index: u32,
+    cursor: u64, // 0 to start at beginning of file
+) -> Result<LatestLogCursor> {
+    Ok(
+        logs::get_concurrent_log_cursor(profile_path, concurrent_index, cursor)
+            .await?,
+    )
+}
+
+/// Get the structured log events of a running profile from a cursor,
+/// filtered by level, logger and thread
+#[tauri::command]
+pub async fn logs_get_log_events_cursor(
+    profile_path: &str,
+    concurrent_index: u32,
+    cursor: u64, // 0 to start at beginning of file
+    filter: LogEventFilter,
+) -> Result<LogEventsCursor> {
+    Ok(logs::get_log_events_cursor(
+        profile_path,
+        concurrent_index,
+        cursor,
+        filter,
+    )
+    .await?)
+}
+
+/// Get the crash reports and JVM fatal error logs of a profile, newest first
+#[tauri::command]
+pub async fn logs_get_crash_files(
+    profile_path: &str,
+) -> Result<Vec<CrashFile>> {
+    Ok(crash::get_crash_files(profile_path).await?)
+}
+
+/// Analyze a crash file of a profile, or its newest one if no path is given
+#[tauri::command]
+pub async fn logs_analyze_crash(
+    profile_path: &str,
+    crash_path: Option<String>,
+) -> Result<Option<CrashAnalysis>> {
+    Ok(match crash_path {
+        Some(crash_path) => {
+            Some(crash::analyze_crash(profile_path, &crash_path).await?)
+        }
+        None => crash::analyze_latest_crash(profile_path).await?,
+    })
+}
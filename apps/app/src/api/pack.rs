@@ -2,6 +2,9 @@ use crate::api::Result;
 
 use theseus::{
     pack::{
+        install_curseforge::{
+            CurseForgePackInstall, install_zipped_curseforge_pack,
+        },
         install_from::{CreatePackLocation, CreatePackProfile},
         install_mrpack::install_zipped_mrpack,
     },
@@ -12,6 +15,7 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
     tauri::plugin::Builder::new("pack")
         .invoke_handler(tauri::generate_handler![
             pack_install,
+            pack_install_curseforge,
             pack_get_profile_from_pack,
         ])
         .build()
@@ -25,6 +29,16 @@ pub async fn pack_install(
     Ok(install_zipped_mrpack(location, profile).await?)
 }
 
+// Installs a CurseForge modpack, returning the files that have to be
+// downloaded manually
+#[tauri::command]
+pub async fn pack_install_curseforge(
+    location: CreatePackLocation,
+    profile: String,
+) -> Result<CurseForgePackInstall> {
+    Ok(install_zipped_curseforge_pack(location, profile).await?)
+}
+
 #[tauri::command]
 pub fn pack_get_profile_from_pack(
     location: CreatePackLocation,
@@ -1,5 +1,8 @@
 use crate::api::Result;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
 use theseus::prelude::*;
+use theseus::process::{DailySessionStats, GameSession, ProfileSessionStats};
 use uuid::Uuid;
 
 pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
@@ -7,7 +10,12 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
         .invoke_handler(tauri::generate_handler![
             process_get_all,
             process_get_by_profile_path,
+            process_get_game_status,
+            process_get_sessions,
+            process_get_daily_session_stats,
+            process_get_profile_session_stats,
             process_kill,
+            process_stop,
             process_wait_for,
         ])
         .build()
@@ -25,11 +33,48 @@ pub async fn process_get_by_profile_path(
     Ok(process::get_by_profile_path(path).await?)
 }
 
+#[tauri::command]
+pub async fn process_get_game_status(uuid: Uuid) -> Result<Option<GameStatus>> {
+    Ok(process::get_game_status(uuid).await?)
+}
+
+#[tauri::command]
+pub async fn process_get_sessions(
+    profile_path: Option<&str>,
+    limit: u32,
+) -> Result<Vec<GameSession>> {
+    Ok(process::get_sessions(profile_path, limit).await?)
+}
+
+#[tauri::command]
+pub async fn process_get_daily_session_stats(
+    profile_path: Option<&str>,
+    since: DateTime<Utc>,
+) -> Result<Vec<DailySessionStats>> {
+    Ok(process::get_daily_session_stats(profile_path, since).await?)
+}
+
+#[tauri::command]
+pub async fn process_get_profile_session_stats()
+-> Result<Vec<ProfileSessionStats>> {
+    Ok(process::get_profile_session_stats().await?)
+}
+
 #[tauri::command]
 pub async fn process_kill(uuid: Uuid) -> Result<()> {
     Ok(process::kill(uuid).await?)
 }
 
+// Asks a process to quit cleanly, killing it if it is still running after
+// timeout_secs
+#[tauri::command]
+pub async fn process_stop(
+    uuid: Uuid,
+    timeout_secs: Option<u64>,
+) -> Result<StopMethod> {
+    Ok(process::stop(uuid, timeout_secs.map(Duration::from_secs)).await?)
+}
+
 #[tauri::command]
 pub async fn process_wait_for(uuid: Uuid) -> Result<()> {
     Ok(process::wait_for(uuid).await?)
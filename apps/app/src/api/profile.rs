@@ -4,8 +4,14 @@ use path_util::SafeRelativeUtf8UnixPathBuf;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+use theseus::pack::install_curseforge::ManualDownload;
 use theseus::prelude::*;
 use theseus::profile::QuickPlayType;
+use theseus::profile::dependencies::InstallPlan;
+use theseus::profile::export::ServerPackFormat;
+use theseus::profile::rollback::RollbackSnapshot;
+use uuid::Uuid;
 
 pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
     tauri::plugin::Builder::new("profile")
@@ -20,19 +26,37 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             profile_list,
             profile_check_installed,
             profile_install,
+            profile_verify,
             profile_update_all,
+            profile_preview_update_all,
             profile_update_project,
+            profile_edit_update_policy,
+            profile_list_rollback_snapshots,
+            profile_rollback,
+            profile_delete_rollback_snapshot,
             profile_add_project_from_version,
+            profile_resolve_dependencies,
+            profile_install_plan,
             profile_add_project_from_path,
             profile_toggle_disable_project,
             profile_remove_project,
             profile_update_managed_modrinth_version,
             profile_repair_managed_modrinth,
+            profile_update_managed_curseforge_version,
+            profile_repair_managed_curseforge,
             profile_run,
+            profile_run_offline,
+            profile_run_with_account,
+            profile_get_offline_missing_files,
             profile_kill,
+            profile_stop,
             profile_edit,
             profile_edit_icon,
             profile_export_mrpack,
+            profile_export_curseforge,
+            profile_export_mmc,
+            profile_export_instance_zip,
+            profile_export_server_pack,
             profile_get_pack_export_candidates,
         ])
         .build()
@@ -111,12 +135,17 @@ pub async fn profile_check_installed(
     path: &str,
     project_id: &str,
 ) -> Result<bool> {
-    let check_project_id = project_id;
+    // CurseForge mod IDs are passed with a cf- prefix
+    let (provider, check_project_id) = match project_id.strip_prefix("cf-") {
+        Some(mod_id) => (ContentProvider::CurseForge, mod_id),
+        None => (ContentProvider::Modrinth, project_id),
+    };
 
     if let Ok(projects) = profile::get_projects(path, None).await {
         Ok(projects.into_iter().any(|(_, project)| {
             if let Some(metadata) = &project.metadata {
-                check_project_id == metadata.project_id
+                metadata.provider == provider
+                    && check_project_id == metadata.project_id
             } else {
                 false
             }
@@ -141,6 +170,15 @@ pub async fn profile_update_all(path: &str) -> Result<HashMap<String, String>> {
     Ok(profile::update_all_projects(path).await?)
 }
 
+/// Lists the updates profile_update_all would apply
+/// invoke('plugin:profile|profile_preview_update_all')
+#[tauri::command]
+pub async fn profile_preview_update_all(
+    path: &str,
+) -> Result<Vec<profile::ProjectUpdate>> {
+    Ok(profile::preview_update_all_projects(path).await?)
+}
+
 /// Updates a specified project
 /// invoke('plugin:profile|profile_update_project')
 #[tauri::command]
@@ -151,6 +189,43 @@ pub async fn profile_update_project(
     Ok(profile::update_project(path, project_path, None).await?)
 }
 
+/// Sets how a project is kept up to date, or resets it if policy is None
+/// invoke('plugin:profile|profile_edit_update_policy')
+#[tauri::command]
+pub async fn profile_edit_update_policy(
+    path: &str,
+    project_path: &str,
+    policy: Option<UpdatePolicy>,
+) -> Result<()> {
+    Ok(profile::edit_update_policy(path, project_path, policy).await?)
+}
+
+/// Lists the snapshots taken before updates and modpack installs
+/// invoke('plugin:profile|profile_list_rollback_snapshots')
+#[tauri::command]
+pub async fn profile_list_rollback_snapshots(
+    path: &str,
+) -> Result<Vec<RollbackSnapshot>> {
+    Ok(profile::rollback::list_rollback_snapshots(path).await?)
+}
+
+/// Restores the files and content settings of a profile to a snapshot
+/// invoke('plugin:profile|profile_rollback')
+#[tauri::command]
+pub async fn profile_rollback(path: &str, snapshot_id: &str) -> Result<()> {
+    Ok(profile::rollback::rollback_profile(path, snapshot_id).await?)
+}
+
+/// Deletes a rollback snapshot, returning the bytes freed
+/// invoke('plugin:profile|profile_delete_rollback_snapshot')
+#[tauri::command]
+pub async fn profile_delete_rollback_snapshot(
+    path: &str,
+    snapshot_id: &str,
+) -> Result<u64> {
+    Ok(profile::rollback::delete_rollback_snapshot(path, snapshot_id).await?)
+}
+
 // Adds a project to a profile from a version ID
 // invoke('plugin:profile|profile_add_project_from_version')
 #[tauri::command]
@@ -161,6 +236,23 @@ pub async fn profile_add_project_from_version(
     Ok(profile::add_project_from_version(path, version_id).await?)
 }
 
+// Resolves the dependencies of a version without installing anything
+// invoke('plugin:profile|profile_resolve_dependencies')
+#[tauri::command]
+pub async fn profile_resolve_dependencies(
+    path: &str,
+    version_id: &str,
+) -> Result<InstallPlan> {
+    Ok(profile::dependencies::resolve_dependencies(path, version_id).await?)
+}
+
+// Installs a confirmed plan from profile_resolve_dependencies
+// invoke('plugin:profile|profile_install_plan')
+#[tauri::command]
+pub async fn profile_install_plan(plan: InstallPlan) -> Result<Vec<String>> {
+    Ok(profile::dependencies::install_plan(&plan).await?)
+}
+
 // Adds a project to a profile from a path
 // invoke('plugin:profile|profile_add_project_from_path')
 #[tauri::command]
@@ -213,6 +305,28 @@ pub async fn profile_repair_managed_modrinth(path: &str) -> Result<()> {
     Ok(profile::update::repair_managed_modrinth(path).await?)
 }
 
+// Updates a managed CurseForge profile to the file of file_id
+// Returns the files that must be downloaded manually
+#[tauri::command]
+pub async fn profile_update_managed_curseforge_version(
+    path: String,
+    file_id: i32,
+) -> Result<Vec<ManualDownload>> {
+    Ok(
+        profile::update::update_managed_curseforge_version(&path, file_id)
+            .await?,
+    )
+}
+
+// Repairs a managed CurseForge profile by reinstalling the current version
+// Returns the files that must be downloaded manually
+#[tauri::command]
+pub async fn profile_repair_managed_curseforge(
+    path: &str,
+) -> Result<Vec<ManualDownload>> {
+    Ok(profile::update::repair_managed_curseforge(path).await?)
+}
+
 // Exports a profile to a .mrpack file (export_location should end in .mrpack)
 // invoke('profile_export_mrpack')
 #[tauri::command]
@@ -236,6 +350,66 @@ pub async fn profile_export_mrpack(
     Ok(())
 }
 
+/// See [`profile::export::export_curseforge`]
+#[tauri::command]
+pub async fn profile_export_curseforge(
+    path: &str,
+    export_location: PathBuf,
+    included_overrides: Vec<String>,
+    version_id: Option<String>,
+    author: Option<String>,
+) -> Result<()> {
+    profile::export::export_curseforge(
+        path,
+        export_location,
+        included_overrides,
+        version_id,
+        author,
+    )
+    .await?;
+    Ok(())
+}
+
+/// See [`profile::export::export_mmc`]
+#[tauri::command]
+pub async fn profile_export_mmc(
+    path: &str,
+    export_location: PathBuf,
+    included_overrides: Vec<String>,
+) -> Result<()> {
+    profile::export::export_mmc(path, export_location, included_overrides)
+        .await?;
+    Ok(())
+}
+
+/// See [`profile::export::export_instance_zip`]
+#[tauri::command]
+pub async fn profile_export_instance_zip(
+    path: &str,
+    export_location: PathBuf,
+) -> Result<()> {
+    profile::export::export_instance_zip(path, export_location).await?;
+    Ok(())
+}
+
+/// See [`profile::export::export_server_pack`]
+#[tauri::command]
+pub async fn profile_export_server_pack(
+    path: &str,
+    export_location: PathBuf,
+    included_overrides: Vec<String>,
+    format: ServerPackFormat,
+) -> Result<Vec<String>> {
+    let excluded = profile::export::export_server_pack(
+        path,
+        export_location,
+        included_overrides,
+        format,
+    )
+    .await?;
+    Ok(excluded)
+}
+
 /// See [`profile::get_pack_export_candidates`]
 #[tauri::command]
 pub async fn profile_get_pack_export_candidates(
@@ -245,6 +419,15 @@ pub async fn profile_get_pack_export_candidates(
     Ok(candidates)
 }
 
+/// See [`profile::verify_profile`]
+#[tauri::command]
+pub async fn profile_verify(
+    path: &str,
+) -> Result<profile::VerificationSummary> {
+    let summary = profile::verify_profile(path).await?;
+    Ok(summary)
+}
+
 // Run minecraft using a profile using the default credentials
 // Returns the UUID, which can be used to poll
 // for the actual Child in the state.
@@ -256,6 +439,40 @@ pub async fn profile_run(path: &str) -> Result<ProcessMetadata> {
     Ok(process)
 }
 
+// Run minecraft using a profile without network access, with the cached
+// name and UUID of the preferred or default account
+// invoke('plugin:profile|profile_run_offline', path)
+#[tauri::command]
+pub async fn profile_run_offline(path: &str) -> Result<ProcessMetadata> {
+    let process = profile::run_offline(path, QuickPlayType::None).await?;
+
+    Ok(process)
+}
+
+// Run minecraft using a profile and the given account, instead of the
+// default or preferred one
+// invoke('plugin:profile|profile_run_with_account', {path, accountUuid})
+#[tauri::command]
+pub async fn profile_run_with_account(
+    path: &str,
+    account_uuid: Uuid,
+) -> Result<ProcessMetadata> {
+    let process =
+        profile::run_with_account(path, account_uuid, QuickPlayType::None)
+            .await?;
+
+    Ok(process)
+}
+
+/// See [`profile::get_offline_missing_files`]
+#[tauri::command]
+pub async fn profile_get_offline_missing_files(
+    path: &str,
+) -> Result<Vec<PathBuf>> {
+    let files = profile::get_offline_missing_files(path).await?;
+    Ok(files)
+}
+
 #[tauri::command]
 pub async fn profile_kill(path: &str) -> Result<()> {
     profile::kill(path).await?;
@@ -263,6 +480,16 @@ pub async fn profile_kill(path: &str) -> Result<()> {
     Ok(())
 }
 
+// Asks the running processes of a profile to quit cleanly, killing the ones
+// still running after timeout_secs
+#[tauri::command]
+pub async fn profile_stop(
+    path: &str,
+    timeout_secs: Option<u64>,
+) -> Result<Vec<StopMethod>> {
+    Ok(profile::stop(path, timeout_secs.map(Duration::from_secs)).await?)
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EditProfile {
     pub name: Option<String>,
@@ -303,6 +530,7 @@ pub struct EditProfile {
         with = "serde_with::rust::double_option"
     )]
     pub custom_env_vars: Option<Option<Vec<(String, String)>>>,
+    pub game_options: Option<Vec<(String, String)>>,
 
     #[serde(
         default,
@@ -323,6 +551,13 @@ pub struct EditProfile {
     )]
     pub game_resolution: Option<Option<WindowSize>>,
     pub hooks: Option<Hooks>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_with::rust::double_option"
+    )]
+    pub preferred_account: Option<Option<Uuid>>,
+    pub allow_concurrent_instances: Option<bool>,
 }
 
 // Edits a profile
@@ -366,6 +601,14 @@ pub async fn profile_edit(path: &str, edit_profile: EditProfile) -> Result<()> {
         if let Some(hooks) = edit_profile.hooks.clone() {
             prof.hooks = hooks;
         }
+        if let Some(preferred_account) = edit_profile.preferred_account {
+            prof.preferred_account = preferred_account;
+        }
+        if let Some(allow_concurrent_instances) =
+            edit_profile.allow_concurrent_instances
+        {
+            prof.allow_concurrent_instances = allow_concurrent_instances;
+        }
 
         prof.modified = chrono::Utc::now();
 
@@ -376,6 +619,9 @@ pub async fn profile_edit(path: &str, edit_profile: EditProfile) -> Result<()> {
         {
             prof.extra_launch_args = extra_launch_args;
         }
+        if let Some(game_options) = edit_profile.game_options.clone() {
+            prof.game_options = game_options;
+        }
 
         async { Ok(()) }
     })
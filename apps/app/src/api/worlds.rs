@@ -5,11 +5,12 @@ use tauri::{AppHandle, Manager, Runtime};
 use theseus::prelude::ProcessMetadata;
 use theseus::profile::{QuickPlayType, get_full_path};
 use theseus::server_address::ServerAddress;
+use theseus::world_backups::{PruneResult, WorldBackupSchedule, WorldSnapshot};
 use theseus::worlds::{
     DisplayStatus, ProtocolVersion, ServerPackStatus, ServerStatus, World,
-    WorldType, WorldWithProfile,
+    WorldBackup, WorldType, WorldWithProfile,
 };
-use theseus::{profile, worlds};
+use theseus::{profile, world_backups, worlds};
 
 pub fn init<R: Runtime>() -> tauri::plugin::TauriPlugin<R> {
     tauri::plugin::Builder::new("worlds")
@@ -21,6 +22,15 @@ pub fn init<R: Runtime>() -> tauri::plugin::TauriPlugin<R> {
             rename_world,
             reset_world_icon,
             backup_world,
+            list_world_backups,
+            restore_world_backup,
+            create_world_snapshot,
+            list_world_snapshots,
+            restore_world_snapshot,
+            delete_world_snapshot,
+            prune_world_snapshots,
+            get_world_backup_schedule,
+            set_world_backup_schedule,
             delete_world,
             add_server_to_profile,
             edit_server_in_profile,
@@ -138,6 +148,101 @@ pub async fn backup_world(instance: &str, world: &str) -> Result<u64> {
     Ok(worlds::backup_world(&instance, world).await?)
 }
 
+#[tauri::command]
+pub async fn list_world_backups(
+    instance: &str,
+    world: Option<&str>,
+) -> Result<Vec<WorldBackup>> {
+    let instance = get_full_path(instance).await?;
+    Ok(worlds::list_world_backups(&instance, world).await?)
+}
+
+#[tauri::command]
+pub async fn restore_world_backup(
+    instance: &str,
+    file_name: &str,
+    as_new_world: bool,
+) -> Result<String> {
+    let instance = get_full_path(instance).await?;
+    Ok(
+        worlds::restore_world_backup(&instance, file_name, as_new_world)
+            .await?,
+    )
+}
+
+#[tauri::command]
+pub async fn create_world_snapshot(
+    instance: &str,
+    world: &str,
+) -> Result<WorldSnapshot> {
+    let instance = get_full_path(instance).await?;
+    Ok(world_backups::create_world_snapshot(&instance, world).await?)
+}
+
+#[tauri::command]
+pub async fn list_world_snapshots(
+    instance: &str,
+    world: Option<&str>,
+) -> Result<Vec<WorldSnapshot>> {
+    let instance = get_full_path(instance).await?;
+    Ok(world_backups::list_world_snapshots(&instance, world).await?)
+}
+
+#[tauri::command]
+pub async fn restore_world_snapshot(
+    instance: &str,
+    snapshot_id: &str,
+    as_new_world: bool,
+) -> Result<String> {
+    let instance = get_full_path(instance).await?;
+    Ok(world_backups::restore_world_snapshot(
+        &instance,
+        snapshot_id,
+        as_new_world,
+    )
+    .await?)
+}
+
+#[tauri::command]
+pub async fn delete_world_snapshot(
+    instance: &str,
+    snapshot_id: &str,
+) -> Result<u64> {
+    let instance = get_full_path(instance).await?;
+    Ok(world_backups::delete_world_snapshot(&instance, snapshot_id).await?)
+}
+
+#[tauri::command]
+pub async fn prune_world_snapshots(
+    instance: &str,
+    world: &str,
+    keep_last: usize,
+) -> Result<PruneResult> {
+    let instance = get_full_path(instance).await?;
+    Ok(
+        world_backups::prune_world_snapshots(&instance, world, keep_last)
+            .await?,
+    )
+}
+
+#[tauri::command]
+pub async fn get_world_backup_schedule(
+    instance: &str,
+    world: Option<&str>,
+) -> Result<Option<WorldBackupSchedule>> {
+    Ok(world_backups::get_world_backup_schedule(instance, world).await?)
+}
+
+#[tauri::command]
+pub async fn set_world_backup_schedule(
+    instance: &str,
+    world: Option<&str>,
+    schedule: Option<WorldBackupSchedule>,
+) -> Result<()> {
+    world_backups::set_world_backup_schedule(instance, world, schedule).await?;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn delete_world(instance: &str, world: &str) -> Result<()> {
     let instance = get_full_path(instance).await?;
@@ -1,5 +1,6 @@
 package com.modrinth.theseus;
 
+import com.modrinth.theseus.rpc.LauncherEvents;
 import com.modrinth.theseus.rpc.RpcHandlers;
 import com.modrinth.theseus.rpc.TheseusRpc;
 import java.io.IOException;
@@ -22,7 +23,8 @@ public final class MinecraftLaunch {
                 Integer.getInteger("modrinth.internal.ipc.port"),
                 new RpcHandlers()
                         .handler("set_system_property", String.class, String.class, System::setProperty)
-                        .handler("launch", () -> waitForLaunch.complete(null)));
+                        .handler("launch", () -> waitForLaunch.complete(null))
+                        .returningHandler("stop", LauncherEvents::requestStop));
 
         waitForLaunch.join();
         relaunch(mainClass, gameArgs);
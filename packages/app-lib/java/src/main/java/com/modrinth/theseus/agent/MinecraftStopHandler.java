@@ -0,0 +1,40 @@
+package com.modrinth.theseus.agent;
+
+import com.modrinth.theseus.rpc.LauncherEvents;
+import java.lang.reflect.InvocationTargetException;
+import java.lang.reflect.Method;
+import java.util.concurrent.Executor;
+
+/** Lets the launcher quit the client the same way its own quit button does. Called from the Minecraft constructor. */
+public final class MinecraftStopHandler {
+    private MinecraftStopHandler() {}
+
+    @SuppressWarnings("CallToPrintStackTrace")
+    public static void install(Object minecraft, String stopMethodName) {
+        final Method stopMethod;
+        try {
+            stopMethod = minecraft.getClass().getDeclaredMethod(stopMethodName);
+            stopMethod.setAccessible(true);
+        } catch (ReflectiveOperationException | RuntimeException e) {
+            new IllegalStateException("Failed to find Minecraft." + stopMethodName, e).printStackTrace();
+            return;
+        }
+
+        final Runnable stop = () -> {
+            try {
+                stopMethod.invoke(minecraft);
+            } catch (IllegalAccessException | InvocationTargetException e) {
+                new IllegalStateException("Failed to stop Minecraft", e).printStackTrace();
+            }
+        };
+        LauncherEvents.setStopHandler(() -> {
+            // Since 1.14 the client is an executor for its own thread. Older versions only check the flag the stop
+            // method clears once per frame, so it is safe to clear from the RPC thread.
+            if (minecraft instanceof Executor) {
+                ((Executor) minecraft).execute(stop);
+            } else {
+                stop.run();
+            }
+        });
+    }
+}
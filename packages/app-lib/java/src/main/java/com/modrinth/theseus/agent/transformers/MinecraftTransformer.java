@@ -2,10 +2,13 @@ package com.modrinth.theseus.agent.transformers;
 
 import com.modrinth.theseus.agent.InsnPattern;
 import com.modrinth.theseus.agent.QuickPlayServerVersion;
+import java.util.Arrays;
 import java.util.ListIterator;
 import org.objectweb.asm.Opcodes;
 import org.objectweb.asm.tree.AbstractInsnNode;
 import org.objectweb.asm.tree.ClassNode;
+import org.objectweb.asm.tree.FieldInsnNode;
+import org.objectweb.asm.tree.FieldNode;
 import org.objectweb.asm.tree.FrameNode;
 import org.objectweb.asm.tree.InsnNode;
 import org.objectweb.asm.tree.JumpInsnNode;
@@ -18,11 +21,78 @@ import org.objectweb.asm.tree.VarInsnNode;
 public final class MinecraftTransformer extends ClassNodeTransformer {
     private static final String SET_SERVER_NAME_DESC = "(Ljava/lang/String;I)V";
     private static final InsnPattern[] INITIALIZE_THIS_PATTERN = {InsnPattern.opcode(Opcodes.INVOKESPECIAL)};
+    private static final String STOP_HANDLER_CLASS = "com/modrinth/theseus/agent/MinecraftStopHandler";
 
     @Override
     protected boolean transform(ClassNode classNode) {
+        boolean transformed = addStopHandler(classNode);
         if (QuickPlayServerVersion.CURRENT == QuickPlayServerVersion.INJECTED) {
-            return addServerJoinSupport(classNode);
+            transformed |= addServerJoinSupport(classNode);
+        }
+        return transformed;
+    }
+
+    private static boolean addStopHandler(ClassNode classNode) {
+        // The method the quit button calls only clears the volatile flag the game loop checks, in every version
+        String stopMethod = null;
+        MethodNode constructor = null;
+        for (final MethodNode method : classNode.methods) {
+            if (constructor == null && method.name.equals("<init>")) {
+                constructor = method;
+            } else if (isStopMethod(classNode, method)) {
+                if (stopMethod != null) {
+                    // Can't know which flag is the running one, so don't risk stopping something else
+                    return false;
+                }
+                stopMethod = method.name;
+            }
+        }
+        if (constructor == null || stopMethod == null) {
+            return false;
+        }
+
+        final ListIterator<AbstractInsnNode> it = constructor.instructions.iterator();
+        if (!InsnPattern.findAndSkip(it, INITIALIZE_THIS_PATTERN)) {
+            return false;
+        }
+
+        it.add(new VarInsnNode(Opcodes.ALOAD, 0));
+        // Minecraft
+        it.add(new LdcInsnNode(stopMethod));
+        // Minecraft String
+        it.add(new MethodInsnNode(
+                Opcodes.INVOKESTATIC, STOP_HANDLER_CLASS, "install", "(Ljava/lang/Object;Ljava/lang/String;)V"));
+        //
+
+        return true;
+    }
+
+    private static boolean isStopMethod(ClassNode classNode, MethodNode method) {
+        if ((method.access & Opcodes.ACC_STATIC) != 0 || !method.desc.equals("()V") || method.name.indexOf('$') != -1) {
+            return false;
+        }
+
+        final AbstractInsnNode[] insns = Arrays.stream(method.instructions.toArray())
+                .filter(insn -> insn.getOpcode() != -1)
+                .toArray(AbstractInsnNode[]::new);
+        if (insns.length != 4
+                || !(insns[0] instanceof VarInsnNode)
+                || insns[0].getOpcode() != Opcodes.ALOAD
+                || ((VarInsnNode) insns[0]).var != 0
+                || insns[1].getOpcode() != Opcodes.ICONST_0
+                || insns[2].getOpcode() != Opcodes.PUTFIELD
+                || insns[3].getOpcode() != Opcodes.RETURN) {
+            return false;
+        }
+
+        final FieldInsnNode flag = (FieldInsnNode) insns[2];
+        if (!flag.owner.equals(classNode.name) || !flag.desc.equals("Z")) {
+            return false;
+        }
+        for (final FieldNode field : classNode.fields) {
+            if (field.name.equals(flag.name) && field.desc.equals("Z")) {
+                return (field.access & Opcodes.ACC_VOLATILE) != 0;
+            }
         }
         return false;
     }
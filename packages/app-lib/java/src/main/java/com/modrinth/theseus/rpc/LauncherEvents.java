@@ -0,0 +1,69 @@
+package com.modrinth.theseus.rpc;
+
+import com.google.gson.reflect.TypeToken;
+import java.util.List;
+import java.util.concurrent.CompletableFuture;
+
+/**
+ * Reports what the game is doing to the launcher. Each method returns a future that completes once the launcher
+ * has handled the report.
+ */
+public final class LauncherEvents {
+    private static final TypeToken<Void> VOID = TypeToken.get(Void.class);
+
+    private static volatile Runnable stopHandler;
+
+    private LauncherEvents() {}
+
+    /**
+     * Sets how the game quits cleanly when the launcher asks it to stop. The agent installs one that schedules
+     * {@code Minecraft.stop()} on the game thread, which mods can replace. Without one, the launcher kills the game.
+     */
+    public static void setStopHandler(Runnable handler) {
+        stopHandler = handler;
+    }
+
+    /** Runs the stop handler, returning whether there was one. Called by the launcher RPC. */
+    public static boolean requestStop() {
+        final Runnable handler = stopHandler;
+        if (handler == null) {
+            return false;
+        }
+        handler.run();
+        return true;
+    }
+
+    /** Reports joining a singleplayer world, identified by its folder name in {@code saves}. */
+    public static CompletableFuture<Void> worldJoined(String worldFolder) {
+        return TheseusRpc.getRpc().callMethod(VOID, "world_joined", worldFolder);
+    }
+
+    /** Reports leaving the world or server being played. */
+    public static CompletableFuture<Void> worldLeft() {
+        return TheseusRpc.getRpc().callMethod(VOID, "world_left");
+    }
+
+    public static CompletableFuture<Void> serverJoined(String host, int port) {
+        return TheseusRpc.getRpc().callMethod(VOID, "server_joined", host, port);
+    }
+
+    public static CompletableFuture<Void> fps(int fps) {
+        return TheseusRpc.getRpc().callMethod(VOID, "fps", fps);
+    }
+
+    public static CompletableFuture<Void> mods(List<LoadedMod> mods) {
+        return TheseusRpc.getRpc().callMethod(VOID, "mods", mods);
+    }
+
+    public static final class LoadedMod {
+        final String id;
+        final String name; // Optional
+        final String version; // Optional
+
+        public LoadedMod(String id, String name, String version) {
+            this.id = id;
+            this.name = name;
+            this.version = version;
+        }
+    }
+}
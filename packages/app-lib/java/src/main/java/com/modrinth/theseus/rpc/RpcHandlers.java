@@ -6,6 +6,7 @@ import java.util.HashMap;
 import java.util.Map;
 import java.util.function.BiConsumer;
 import java.util.function.Function;
+import java.util.function.Supplier;
 
 public class RpcHandlers {
     private final Map<String, Function<JsonElement[], JsonElement>> handlers = new HashMap<>();
@@ -18,6 +19,10 @@ public class RpcHandlers {
         });
     }
 
+    public RpcHandlers returningHandler(String functionName, Supplier<?> handler) {
+        return addHandler(functionName, args -> TheseusRpc.GSON.toJsonTree(handler.get()));
+    }
+
     public <A, B> RpcHandlers handler(
             String functionName, Class<A> arg1Type, Class<B> arg2Type, BiConsumer<A, B> handler) {
         return addHandler(functionName, args -> {
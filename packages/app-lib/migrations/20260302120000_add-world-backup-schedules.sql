@@ -0,0 +1,17 @@
+CREATE TABLE world_backup_schedules (
+    profile_path TEXT NOT NULL,
+    -- Empty for the instance-wide schedule, which applies to every world without its own
+    world_id TEXT NOT NULL DEFAULT '',
+
+    on_exit INTEGER NOT NULL DEFAULT FALSE,
+    interval_minutes INTEGER NULL,
+    daily INTEGER NOT NULL DEFAULT FALSE,
+
+    keep_last INTEGER NULL,
+    keep_daily_days INTEGER NULL,
+    max_total_size INTEGER NULL,
+
+    PRIMARY KEY (profile_path, world_id),
+    FOREIGN KEY (profile_path) REFERENCES profiles(path) ON DELETE CASCADE
+);
+CREATE INDEX world_backup_schedules_profile_path ON world_backup_schedules(profile_path);
@@ -0,0 +1,2 @@
+-- The account used to launch the profile instead of the default one
+ALTER TABLE profiles ADD COLUMN preferred_account TEXT NULL;
@@ -0,0 +1,2 @@
+-- Whether extra copies of the profile may be launched while it is running
+ALTER TABLE profiles ADD COLUMN allow_concurrent_instances INTEGER NOT NULL DEFAULT FALSE;
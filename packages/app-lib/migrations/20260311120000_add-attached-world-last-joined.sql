@@ -0,0 +1,2 @@
+-- When the game last reported joining the world through the launcher RPC
+ALTER TABLE attached_world_data ADD COLUMN last_joined INTEGER NULL;
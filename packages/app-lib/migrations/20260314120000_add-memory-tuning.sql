@@ -0,0 +1,11 @@
+-- JVM heap and garbage collector tuning, next to the maximum heap size
+ALTER TABLE settings ADD COLUMN mc_memory_min INTEGER NULL;
+ALTER TABLE settings ADD COLUMN mc_garbage_collector TEXT NULL;
+ALTER TABLE settings ADD COLUMN mc_jvm_preset TEXT NULL;
+ALTER TABLE settings ADD COLUMN mc_memory_auto INTEGER NOT NULL DEFAULT FALSE;
+
+-- Only read when override_mc_memory_max is set
+ALTER TABLE profiles ADD COLUMN override_mc_memory_min INTEGER NULL;
+ALTER TABLE profiles ADD COLUMN override_mc_garbage_collector TEXT NULL;
+ALTER TABLE profiles ADD COLUMN override_mc_jvm_preset TEXT NULL;
+ALTER TABLE profiles ADD COLUMN override_mc_memory_auto INTEGER NOT NULL DEFAULT FALSE;
@@ -0,0 +1,7 @@
+-- options.txt entries written before every launch. Profile entries are
+-- applied after the global ones, so they win for the same key
+ALTER TABLE settings ADD COLUMN game_options JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE profiles ADD COLUMN game_options JSONB NOT NULL DEFAULT '[]';
+
+-- options.txt entries written once to the options.txt of new profiles
+ALTER TABLE settings ADD COLUMN default_game_options JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,19 @@
+CREATE TABLE game_sessions (
+    -- The UUID of the game process
+    id TEXT NOT NULL PRIMARY KEY,
+    profile_path TEXT NOT NULL,
+
+    start_time INTEGER NOT NULL,
+    end_time INTEGER NOT NULL,
+    -- NULL if the process was ended by a signal
+    exit_code INTEGER NULL,
+    account TEXT NULL,
+
+    worlds JSONB NOT NULL,
+    servers JSONB NOT NULL,
+    crashed INTEGER NOT NULL DEFAULT FALSE,
+
+    FOREIGN KEY (profile_path) REFERENCES profiles(path) ON DELETE CASCADE
+);
+CREATE INDEX game_sessions_profile_path ON game_sessions(profile_path);
+CREATE INDEX game_sessions_start_time ON game_sessions(start_time);
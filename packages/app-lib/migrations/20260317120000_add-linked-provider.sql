@@ -0,0 +1,3 @@
+-- Where the modpack a profile is linked to comes from. For CurseForge packs,
+-- the linked project and version IDs are its mod and file IDs
+ALTER TABLE profiles ADD COLUMN linked_provider TEXT NOT NULL DEFAULT 'modrinth';
@@ -0,0 +1,3 @@
+-- How each file of a profile is kept up to date, keyed by its path relative
+-- to the profile without the .disabled extension
+ALTER TABLE profiles ADD COLUMN update_policies JSONB NOT NULL DEFAULT '{}';
@@ -1,3 +1,4 @@
+use crate::state::content_store::{self, ContentStoreGcResult};
 use crate::state::{
     CacheBehaviour, CacheValueType, CachedEntry, Organization, Project,
     SearchResults, TeamMember, User, Version,
@@ -51,3 +52,10 @@ pub async fn purge_cache_types(
 
     Ok(())
 }
+
+/// Removes the files in the shared content store that no profile uses
+/// anymore
+pub async fn gc_content_store() -> crate::Result<ContentStoreGcResult> {
+    let state = crate::State::get().await?;
+    content_store::collect_garbage(&state.pool, &state.fetch_semaphore).await
+}
@@ -0,0 +1,884 @@
+//! Analysis of Minecraft crash reports and JVM fatal error logs
+//!
+//! A crash is read for the mods it points at, through stack frames, mixin
+//! names and the mods the loader itself suspects, which are then matched
+//! against the mod files of the profile. Common causes that have a known fix
+//! are recognized from the text of the crash.
+
+use crate::state::{ContentProvider, Profile, ProjectType};
+use crate::util::io::{self, IOError};
+use crate::{ErrorKind, State};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Component, Path};
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+const CRASH_REPORTS_FOLDER: &str = "crash-reports";
+const JVM_ERROR_PREFIX: &str = "hs_err_pid";
+
+// Frames in these packages belong to the game, its libraries or the mod
+// loaders, and say nothing about which mod crashed
+const FRAMEWORK_PACKAGES: &[&str] = &[
+    "com.google.",
+    "com.mojang.",
+    "com.sun.",
+    "cpw.mods.",
+    "io.netty.",
+    "it.unimi.",
+    "java.",
+    "javax.",
+    "jdk.",
+    "kotlin.",
+    "net.fabricmc.",
+    "net.minecraft.",
+    "net.minecraftforge.",
+    "net.neoforged.",
+    "org.apache.",
+    "org.lwjgl.",
+    "org.objectweb.",
+    "org.quiltmc.",
+    "org.slf4j.",
+    "org.spongepowered.",
+    "oshi.",
+    "scala.",
+    "sun.",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashFileType {
+    /// A crash report written by the game to `crash-reports/`
+    CrashReport,
+    /// An `hs_err_pid*.log` file written by the JVM when it crashes itself
+    JvmFatalError,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrashFile {
+    /// The path of the file, relative to the profile
+    pub path: String,
+    pub file_type: CrashFileType,
+    /// Seconds since the Unix epoch the file was last modified at
+    pub age: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrashAnalysis {
+    pub file: CrashFile,
+    /// The description of the crash report, or the error the JVM reported
+    pub description: Option<String>,
+    /// The first line of the exception or the problematic native frame
+    pub error: Option<String>,
+    /// The mods pointed at by the crash, most suspicious first
+    pub suspected_mods: Vec<SuspectedMod>,
+    pub known_causes: Vec<KnownCause>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SuspectedMod {
+    pub mod_id: Option<String>,
+    /// The path of the matching file in the profile, such as
+    /// `mods/sodium.jar`
+    pub path: Option<String>,
+    /// The Modrinth project of the matching file
+    pub project_id: Option<String>,
+    pub reasons: Vec<SuspicionReason>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SuspicionReason {
+    /// The mod loader named the mod in the crash report
+    ReportedByLoader,
+    StackFrame {
+        class_name: String,
+    },
+    Mixin {
+        name: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KnownCause {
+    /// Code was compiled for a newer Java than the one running the game
+    WrongJavaVersion {
+        required: Option<u32>,
+        current: Option<u32>,
+        fix: String,
+    },
+    OutOfMemory {
+        heap: bool,
+        fix: String,
+    },
+    MissingDependency {
+        mod_id: String,
+        dependency: String,
+        fix: String,
+    },
+    DuplicateMod {
+        mod_id: String,
+        paths: Vec<String>,
+        fix: String,
+    },
+}
+
+/// What could be read out of the text of a crash, before matching it
+/// against the profile
+#[derive(Debug, Default, PartialEq)]
+struct ParsedCrash {
+    description: Option<String>,
+    error: Option<String>,
+    /// Classes of stack frames outside the game and its libraries, in the
+    /// order they appear
+    frame_classes: Vec<String>,
+    /// Mixin config names, such as `sodium.mixins.json`
+    mixin_configs: Vec<String>,
+    /// Mod IDs found in mixin handler names and in the suspected mods
+    /// section of the report
+    mixin_mod_ids: Vec<String>,
+    reported_mod_ids: Vec<String>,
+}
+
+/// Lists the crash reports and JVM fatal error logs of a profile, newest
+/// first
+#[tracing::instrument]
+pub async fn get_crash_files(
+    profile_path: &str,
+) -> crate::Result<Vec<CrashFile>> {
+    let profile_dir = crate::api::profile::get_full_path(profile_path).await?;
+
+    let mut files = Vec::new();
+    for (folder, file_type) in [
+        (Some(CRASH_REPORTS_FOLDER), CrashFileType::CrashReport),
+        (None, CrashFileType::JvmFatalError),
+    ] {
+        let dir = match folder {
+            Some(folder) => profile_dir.join(folder),
+            None => profile_dir.clone(),
+        };
+        if !dir.exists() {
+            continue;
+        }
+
+        let mut entries = io::read_dir(&dir).await?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &dir))?
+        {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let matches = match file_type {
+                CrashFileType::CrashReport => file_name.ends_with(".txt"),
+                CrashFileType::JvmFatalError => {
+                    file_name.starts_with(JVM_ERROR_PREFIX)
+                        && file_name.ends_with(".log")
+                }
+            };
+            let metadata = entry.metadata().await?;
+            if !matches || !metadata.is_file() {
+                continue;
+            }
+
+            files.push(CrashFile {
+                path: match folder {
+                    Some(folder) => format!("{folder}/{file_name}"),
+                    None => file_name,
+                },
+                file_type,
+                age: metadata
+                    .modified()
+                    .unwrap_or(SystemTime::UNIX_EPOCH)
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            });
+        }
+    }
+
+    files.sort_by(|a, b| b.age.cmp(&a.age));
+    Ok(files)
+}
+
+/// Analyzes the newest crash report or JVM fatal error log of a profile, if
+/// it has any
+#[tracing::instrument]
+pub async fn analyze_latest_crash(
+    profile_path: &str,
+) -> crate::Result<Option<CrashAnalysis>> {
+    match get_crash_files(profile_path).await?.into_iter().next() {
+        Some(file) => Ok(Some(analyze_crash_file(profile_path, file).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Analyzes a crash report or JVM fatal error log, by its path relative to
+/// the profile as returned by [`get_crash_files`]
+#[tracing::instrument]
+pub async fn analyze_crash(
+    profile_path: &str,
+    crash_path: &str,
+) -> crate::Result<CrashAnalysis> {
+    if Path::new(crash_path)
+        .components()
+        .any(|x| !matches!(x, Component::Normal(_)))
+    {
+        return Err(ErrorKind::InputError(format!(
+            "Invalid crash file path {crash_path}"
+        ))
+        .into());
+    }
+
+    let file = get_crash_files(profile_path)
+        .await?
+        .into_iter()
+        .find(|x| x.path == crash_path)
+        .ok_or_else(|| {
+            ErrorKind::InputError(format!("Unknown crash file {crash_path}"))
+        })?;
+    analyze_crash_file(profile_path, file).await
+}
+
+async fn analyze_crash_file(
+    profile_path: &str,
+    file: CrashFile,
+) -> crate::Result<CrashAnalysis> {
+    let state = State::get().await?;
+    let profile =
+        Profile::get(profile_path, &state.pool)
+            .await?
+            .ok_or_else(|| {
+                ErrorKind::UnmanagedProfileError(profile_path.to_string())
+            })?;
+    let profile_dir = crate::api::profile::get_full_path(profile_path).await?;
+
+    let (text, _) =
+        io::read_any_encoding_to_string(profile_dir.join(&file.path)).await?;
+    let parsed = match file.file_type {
+        CrashFileType::CrashReport => parse_crash_report(&text),
+        CrashFileType::JvmFatalError => parse_jvm_fatal_error(&text),
+    };
+
+    let mut mod_files = profile
+        .get_projects(None, &state.pool, &state.fetch_semaphore)
+        .await?
+        .into_iter()
+        .filter(|(path, file)| {
+            file.project_type == ProjectType::Mod && path.ends_with(".jar")
+        })
+        .collect::<Vec<_>>();
+    mod_files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let wanted_classes = parsed
+        .frame_classes
+        .iter()
+        .map(|x| class_entry_name(x))
+        .collect::<HashSet<_>>();
+    let scans = {
+        let jars = mod_files
+            .iter()
+            .map(|(path, _)| profile_dir.join(path))
+            .collect::<Vec<_>>();
+        tokio::task::spawn_blocking(move || {
+            jars.iter()
+                .map(|jar| scan_mod_jar(jar, &wanted_classes))
+                .collect::<Vec<_>>()
+        })
+        .await?
+    };
+
+    let mut suspected_mods: Vec<SuspectedMod> = Vec::new();
+    let mut suspect = |mod_id: Option<&str>,
+                       file_index: Option<usize>,
+                       reason: SuspicionReason| {
+        let path = file_index.map(|x| mod_files[x].0.clone());
+        let existing = suspected_mods.iter_mut().find(|x| {
+            (path.is_some() && x.path == path)
+                || (path.is_none()
+                    && x.path.is_none()
+                    && x.mod_id.as_deref() == mod_id)
+        });
+        let suspected = match existing {
+            Some(suspected) => suspected,
+            None => {
+                suspected_mods.push(SuspectedMod {
+                    mod_id: mod_id.map(|x| x.to_string()),
+                    project_id: file_index.and_then(|x| {
+                        mod_files[x]
+                            .1
+                            .metadata
+                            .as_ref()
+                            .filter(|x| x.provider == ContentProvider::Modrinth)
+                            .map(|x| x.project_id.clone())
+                    }),
+                    path,
+                    reasons: Vec::new(),
+                });
+                suspected_mods.last_mut().unwrap()
+            }
+        };
+        if suspected.mod_id.is_none() {
+            suspected.mod_id = mod_id.map(|x| x.to_string());
+        }
+        if !suspected.reasons.contains(&reason) {
+            suspected.reasons.push(reason);
+        }
+    };
+    let find_by_mod_id = |mod_id: &str| {
+        scans
+            .iter()
+            .position(|x| x.mod_ids.iter().any(|x| x == mod_id))
+    };
+
+    for mod_id in &parsed.reported_mod_ids {
+        suspect(
+            Some(mod_id),
+            find_by_mod_id(mod_id),
+            SuspicionReason::ReportedByLoader,
+        );
+    }
+    for class_name in &parsed.frame_classes {
+        let entry_name = class_entry_name(class_name);
+        if let Some(index) =
+            scans.iter().position(|x| x.classes.contains(&entry_name))
+        {
+            suspect(
+                scans[index].mod_ids.first().map(|x| &**x),
+                Some(index),
+                SuspicionReason::StackFrame {
+                    class_name: class_name.clone(),
+                },
+            );
+        }
+    }
+    for config in &parsed.mixin_configs {
+        let index = scans.iter().position(|x| x.mixin_configs.contains(config));
+        let mod_id = match index {
+            Some(index) => scans[index].mod_ids.first().cloned(),
+            None => mod_id_from_mixin_config(config),
+        };
+        suspect(
+            mod_id.as_deref(),
+            index,
+            SuspicionReason::Mixin {
+                name: config.clone(),
+            },
+        );
+    }
+    for mod_id in &parsed.mixin_mod_ids {
+        suspect(
+            Some(mod_id),
+            find_by_mod_id(mod_id),
+            SuspicionReason::Mixin {
+                name: mod_id.clone(),
+            },
+        );
+    }
+
+    // More kinds of evidence make a mod more suspicious, and the sort is
+    // stable so ties keep the order the evidence was found in
+    suspected_mods.sort_by(|a, b| b.reasons.len().cmp(&a.reasons.len()));
+
+    let mut known_causes = detect_known_causes(&text);
+
+    // Two files declaring the same mod ID are a duplicate, whether or not
+    // the loader got far enough to say so
+    let mut files_by_mod_id: HashMap<&str, Vec<String>> = HashMap::new();
+    for (index, scan) in scans.iter().enumerate() {
+        for mod_id in &scan.mod_ids {
+            files_by_mod_id
+                .entry(mod_id)
+                .or_default()
+                .push(mod_files[index].0.clone());
+        }
+    }
+    for cause in &mut known_causes {
+        if let KnownCause::DuplicateMod { mod_id, paths, fix } = cause
+            && let Some(files) = files_by_mod_id.remove(mod_id.as_str())
+        {
+            *fix = duplicate_mod_fix(mod_id, &files);
+            *paths = files;
+        }
+    }
+    let mut duplicates = files_by_mod_id
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .collect::<Vec<_>>();
+    duplicates.sort();
+    for (mod_id, paths) in duplicates {
+        known_causes.push(KnownCause::DuplicateMod {
+            mod_id: mod_id.to_string(),
+            fix: duplicate_mod_fix(mod_id, &paths),
+            paths,
+        });
+    }
+
+    Ok(CrashAnalysis {
+        file,
+        description: parsed.description,
+        error: parsed.error,
+        suspected_mods,
+        known_causes,
+    })
+}
+
+fn duplicate_mod_fix(mod_id: &str, paths: &[String]) -> String {
+    if paths.is_empty() {
+        format!("Remove all but one copy of the mod {mod_id}")
+    } else {
+        format!(
+            "Remove all but one of these copies of the mod {mod_id}: {}",
+            paths.join(", ")
+        )
+    }
+}
+
+fn class_entry_name(class_name: &str) -> String {
+    // Inner and lambda classes live in their own class files, but share
+    // the jar of their outer class
+    let outer = class_name.split('$').next().unwrap_or(class_name);
+    format!("{}.class", outer.replace('.', "/"))
+}
+
+fn is_framework_class(class_name: &str) -> bool {
+    FRAMEWORK_PACKAGES.iter().any(|x| class_name.starts_with(x))
+}
+
+fn mod_id_from_mixin_config(config: &str) -> Option<String> {
+    let name = config.strip_suffix(".json")?;
+    let mod_id = name
+        .strip_suffix(".mixins")
+        .or_else(|| name.strip_prefix("mixins."))
+        .unwrap_or(name);
+    (!mod_id.is_empty() && !mod_id.contains('.')).then(|| mod_id.to_string())
+}
+
+fn push_unique(list: &mut Vec<String>, value: &str) {
+    if !list.iter().any(|x| x == value) {
+        list.push(value.to_string());
+    }
+}
+
+static FRAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:at |[jJ] +(?:\d+ +c\d +)?)(?:[\w.\-]+(?:@[\w.\-]+)?/)?([A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)+)\.([\w$<>]+)\(",
+    )
+    .unwrap()
+});
+static MIXIN_CONFIG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b([\w\-]+(?:\.[\w\-]+)*\.json):[\w.$]+").unwrap()
+});
+static MIXIN_MOD_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:\$[a-z]{3}\d{3}\$([a-z][a-z0-9_]*)\$|\bfrom mod ([\w\-]+))")
+        .unwrap()
+});
+static SUSPECTED_MOD_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\(([\w\-]+)\)").unwrap());
+
+fn parse_stack_frames(text: &str, parsed: &mut ParsedCrash) {
+    for line in text.lines() {
+        let line = line.trim();
+
+        if let Some(captures) = FRAME_REGEX.captures(line) {
+            let class_name = &captures[1];
+            if !is_framework_class(class_name) {
+                push_unique(&mut parsed.frame_classes, class_name);
+            }
+        }
+
+        for captures in MIXIN_CONFIG_REGEX.captures_iter(line) {
+            push_unique(&mut parsed.mixin_configs, &captures[1]);
+        }
+        for captures in MIXIN_MOD_REGEX.captures_iter(line) {
+            if let Some(mod_id) = captures.get(1).or_else(|| captures.get(2)) {
+                push_unique(&mut parsed.mixin_mod_ids, mod_id.as_str());
+            }
+        }
+    }
+}
+
+/// Parses a crash report written by the game
+fn parse_crash_report(text: &str) -> ParsedCrash {
+    let mut parsed = ParsedCrash::default();
+
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.next() {
+        if let Some(description) = line.strip_prefix("Description: ") {
+            parsed.description = Some(description.trim().to_string());
+            // The exception follows the description after a blank line
+            while lines.peek().is_some_and(|x| x.trim().is_empty()) {
+                lines.next();
+            }
+            if let Some(error) = lines.peek() {
+                parsed.error = Some(error.trim().to_string());
+            }
+        } else if let Some(suspected) =
+            line.trim().strip_prefix("Suspected Mod")
+        {
+            // Forge and NeoForge list the mods they suspect either on the
+            // same line or indented below it, as `Name (modid), Version: ...`
+            let mut entries = vec![suspected.to_string()];
+            while let Some(next) =
+                lines.next_if(|x| x.starts_with('\t') || x.starts_with("  "))
+            {
+                entries.push(next.to_string());
+            }
+            for entry in entries {
+                if let Some(captures) = SUSPECTED_MOD_REGEX.captures(&entry) {
+                    push_unique(&mut parsed.reported_mod_ids, &captures[1]);
+                }
+            }
+        }
+    }
+
+    parse_stack_frames(text, &mut parsed);
+    parsed
+}
+
+/// Parses a fatal error log written by the JVM
+fn parse_jvm_fatal_error(text: &str) -> ParsedCrash {
+    let mut parsed = ParsedCrash::default();
+
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let Some(line) = line.strip_prefix('#') else {
+            continue;
+        };
+        let line = line.trim();
+
+        if parsed.description.is_none()
+            && (line.starts_with("There is insufficient memory")
+                || line.starts_with("EXCEPTION_")
+                || line.starts_with("SIG")
+                || line.starts_with("Internal Error")
+                || line.starts_with("Out of Memory Error"))
+        {
+            parsed.description = Some(line.to_string());
+        } else if line == "Problematic frame:"
+            && let Some(frame) = lines.next()
+        {
+            parsed.error =
+                Some(frame.trim_start_matches('#').trim().to_string());
+        }
+    }
+
+    parse_stack_frames(text, &mut parsed);
+    parsed
+}
+
+static CLASS_VERSION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"class file version (\d+)\.\d+\), this version of the Java Runtime only recognizes class file versions up to (\d+)\.\d+",
+    )
+    .unwrap()
+});
+static FABRIC_MISSING_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"Mod '[^']*' \(([\w\-]+)\).*? requires .*?'?([\w\-]+)'?(?: \(([\w\-]+)\))?, which is missing",
+    )
+    .unwrap()
+});
+static FORGE_MISSING_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"Mod ID: '([\w\-]+)', Requested by: '([\w\-]+)'").unwrap()
+});
+static DUPLICATE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?:mod id '([\w\-]+)' has multiple|duplicate mods?(?: id)?:? '([\w\-]+)'|mod id: '([\w\-]+)' from mod files)",
+    )
+    .unwrap()
+});
+
+/// Recognizes common causes of crashes that have a known fix
+fn detect_known_causes(text: &str) -> Vec<KnownCause> {
+    let mut causes = Vec::new();
+
+    if let Some(captures) = CLASS_VERSION_REGEX.captures(text) {
+        // Class file versions are offset from Java versions by 44
+        let java_version =
+            |x: &str| x.parse::<u32>().ok().and_then(|x| x.checked_sub(44));
+        let required = java_version(&captures[1]);
+        let current = java_version(&captures[2]);
+        causes.push(KnownCause::WrongJavaVersion {
+            required,
+            current,
+            fix: match required {
+                Some(required) => {
+                    format!("Launch the profile with Java {required} or newer")
+                }
+                None => {
+                    "Launch the profile with a newer Java version".to_string()
+                }
+            },
+        });
+    } else if text.contains("java.lang.UnsupportedClassVersionError") {
+        causes.push(KnownCause::WrongJavaVersion {
+            required: None,
+            current: None,
+            fix: "Launch the profile with a newer Java version".to_string(),
+        });
+    }
+
+    if text.contains("java.lang.OutOfMemoryError") {
+        causes.push(KnownCause::OutOfMemory {
+            heap: true,
+            fix: "Allocate more memory to the profile, or remove \
+                  memory-hungry mods and resource packs"
+                .to_string(),
+        });
+    } else if text.contains("There is insufficient memory for the Java")
+        || text.contains("Out of Memory Error")
+    {
+        causes.push(KnownCause::OutOfMemory {
+            heap: false,
+            fix: "Allocate less memory to the profile, or close other \
+                  programs, so the system has memory left for the game"
+                .to_string(),
+        });
+    }
+
+    let mut missing = Vec::new();
+    for captures in FABRIC_MISSING_REGEX.captures_iter(text) {
+        let dependency = captures.get(3).unwrap_or_else(|| {
+            captures.get(2).expect("dependency is always captured")
+        });
+        missing
+            .push((captures[1].to_string(), dependency.as_str().to_string()));
+    }
+    for captures in FORGE_MISSING_REGEX.captures_iter(text) {
+        missing.push((captures[2].to_string(), captures[1].to_string()));
+    }
+    let mut seen = HashSet::new();
+    for (mod_id, dependency) in missing {
+        if seen.insert((mod_id.clone(), dependency.clone())) {
+            causes.push(KnownCause::MissingDependency {
+                fix: format!("Install {dependency}, which {mod_id} requires"),
+                mod_id,
+                dependency,
+            });
+        }
+    }
+
+    let mut duplicates = Vec::new();
+    for captures in DUPLICATE_REGEX.captures_iter(text) {
+        if let Some(mod_id) = captures
+            .get(1)
+            .or_else(|| captures.get(2))
+            .or_else(|| captures.get(3))
+        {
+            push_unique(&mut duplicates, mod_id.as_str());
+        }
+    }
+    for mod_id in duplicates {
+        causes.push(KnownCause::DuplicateMod {
+            fix: duplicate_mod_fix(&mod_id, &[]),
+            mod_id,
+            paths: Vec::new(),
+        });
+    }
+
+    causes
+}
+
+#[derive(Default)]
+struct ModJarScan {
+    mod_ids: Vec<String>,
+    mixin_configs: HashSet<String>,
+    /// The wanted class files that are in the jar
+    classes: HashSet<String>,
+}
+
+static MODS_TOML_ID_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?m)^\s*modId\s*=\s*"([^"]+)""#).unwrap());
+static MCMOD_INFO_ID_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""modid"\s*:\s*"([^"]+)""#).unwrap());
+
+/// Reads the mod IDs, mixin configs and wanted classes of a mod jar. Jars
+/// that cannot be read are treated as empty
+fn scan_mod_jar(path: &Path, wanted_classes: &HashSet<String>) -> ModJarScan {
+    let mut scan = ModJarScan::default();
+    let Ok(mut archive) = std::fs::File::open(path)
+        .map(zip::ZipArchive::new)
+        .and_then(|x| x.map_err(std::io::Error::other))
+    else {
+        return scan;
+    };
+
+    let mut read_entry = |name: &str| {
+        let mut file = archive.by_name(name).ok()?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).ok()?;
+        Some(contents)
+    };
+
+    if let Some(json) = read_entry("fabric.mod.json")
+        .and_then(|x| serde_json::from_str::<serde_json::Value>(&x).ok())
+        && let Some(id) = json["id"].as_str()
+    {
+        push_unique(&mut scan.mod_ids, id);
+    }
+    if let Some(json) = read_entry("quilt.mod.json")
+        .and_then(|x| serde_json::from_str::<serde_json::Value>(&x).ok())
+        && let Some(id) = json["quilt_loader"]["id"].as_str()
+    {
+        push_unique(&mut scan.mod_ids, id);
+    }
+    for name in ["META-INF/neoforge.mods.toml", "META-INF/mods.toml"] {
+        if let Some(toml) = read_entry(name) {
+            for captures in MODS_TOML_ID_REGEX.captures_iter(&toml) {
+                push_unique(&mut scan.mod_ids, &captures[1]);
+            }
+        }
+    }
+    if let Some(info) = read_entry("mcmod.info") {
+        for captures in MCMOD_INFO_ID_REGEX.captures_iter(&info) {
+            push_unique(&mut scan.mod_ids, &captures[1]);
+        }
+    }
+
+    for name in archive.file_names() {
+        if wanted_classes.contains(name) {
+            scan.classes.insert(name.to_string());
+        } else if !name.contains('/')
+            && name.ends_with(".json")
+            && name.contains("mixin")
+        {
+            scan.mixin_configs.insert(name.to_string());
+        }
+    }
+
+    scan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRASH_REPORT: &str = "---- Minecraft Crash Report ----
+// Who set us up the TNT?
+
+Time: 2024-05-01 12:00:00
+Description: Ticking entity
+
+java.lang.NullPointerException: Cannot invoke \"Object.hashCode()\"
+\tat me.example.coolmod.entity.CoolEntity.tick(CoolEntity.java:42)
+\tat net.minecraft.class_1297.handler$zza000$othermod$onTick(class_1297.java:1234)
+\tat net.minecraft.class_1937.method_18472(class_1937.java:500)
+\tat java.base/java.lang.Thread.run(Thread.java:1583)
+
+-- Head --
+Mixins in Stacktrace:
+\tnet.minecraft.class_1297:
+\t\tfancy.mixins.json:EntityMixin (fancy.mixins.json)
+Suspected Mods:
+\tCool Mod (coolmod), Version: 1.0
+";
+
+    #[test]
+    fn test_parse_crash_report() {
+        let parsed = parse_crash_report(CRASH_REPORT);
+        assert_eq!(parsed.description.as_deref(), Some("Ticking entity"));
+        assert_eq!(
+            parsed.error.as_deref(),
+            Some(
+                "java.lang.NullPointerException: Cannot invoke \"Object.hashCode()\""
+            )
+        );
+        assert_eq!(
+            parsed.frame_classes,
+            vec!["me.example.coolmod.entity.CoolEntity"]
+        );
+        assert_eq!(parsed.mixin_configs, vec!["fancy.mixins.json"]);
+        assert_eq!(parsed.mixin_mod_ids, vec!["othermod"]);
+        assert_eq!(parsed.reported_mod_ids, vec!["coolmod"]);
+    }
+
+    #[test]
+    fn test_parse_jvm_fatal_error() {
+        let parsed = parse_jvm_fatal_error(
+            "#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  EXCEPTION_ACCESS_VIOLATION (0xc0000005) at pc=0x00007ffb, pid=1234, tid=5678
+#
+# Problematic frame:
+# C  [atio6axx.dll+0x1234]
+#
+j  org.lwjgl.opengl.GL11C.glDrawElements(IIJ)V+0
+j  me.example.shaders.Renderer.draw()V+12
+J 1234 c2 me.example.shaders.Pipeline.run()V (56 bytes)
+",
+        );
+        assert_eq!(
+            parsed.description.as_deref(),
+            Some(
+                "EXCEPTION_ACCESS_VIOLATION (0xc0000005) at pc=0x00007ffb, pid=1234, tid=5678"
+            )
+        );
+        assert_eq!(parsed.error.as_deref(), Some("C  [atio6axx.dll+0x1234]"));
+        assert_eq!(
+            parsed.frame_classes,
+            vec!["me.example.shaders.Renderer", "me.example.shaders.Pipeline"]
+        );
+    }
+
+    #[test]
+    fn test_detect_known_causes() {
+        assert_eq!(
+            detect_known_causes(
+                "java.lang.UnsupportedClassVersionError: a/B has been compiled by a more recent version of the Java Runtime (class file version 65.0), this version of the Java Runtime only recognizes class file versions up to 61.0"
+            ),
+            vec![KnownCause::WrongJavaVersion {
+                required: Some(21),
+                current: Some(17),
+                fix: "Launch the profile with Java 21 or newer".to_string(),
+            }]
+        );
+
+        let causes = detect_known_causes(
+            "Mod 'Sodium Extra' (sodium-extra) 0.5.1 requires version 0.5.0 or later of mod 'Sodium' (sodium), which is missing!
+Mod 'Cool' (cool) 1.0 requires any version of fabric-api, which is missing!
+Mod ID: 'architectury', Requested by: 'rei', Expected range: '[9,)'
+Mod ID 'lithium' has multiple versions
+java.lang.OutOfMemoryError: Java heap space",
+        );
+        let summary = causes
+            .iter()
+            .map(|x| match x {
+                KnownCause::MissingDependency {
+                    mod_id, dependency, ..
+                } => format!("missing {dependency} for {mod_id}"),
+                KnownCause::DuplicateMod { mod_id, .. } => {
+                    format!("duplicate {mod_id}")
+                }
+                KnownCause::OutOfMemory { heap, .. } => format!("oom {heap}"),
+                KnownCause::WrongJavaVersion { .. } => "java".to_string(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                "oom true",
+                "missing sodium for sodium-extra",
+                "missing fabric-api for cool",
+                "missing architectury for rei",
+                "duplicate lithium",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mod_id_from_mixin_config() {
+        assert_eq!(
+            mod_id_from_mixin_config("sodium.mixins.json").as_deref(),
+            Some("sodium")
+        );
+        assert_eq!(
+            mod_id_from_mixin_config("mixins.modid.json").as_deref(),
+            Some("modid")
+        );
+        assert_eq!(mod_id_from_mixin_config("a.b.mixins.json"), None);
+    }
+}
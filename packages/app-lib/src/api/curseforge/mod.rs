@@ -369,6 +369,42 @@ pub async fn get_game_version_types(
     Ok(response.data)
 }
 
+/// Compute the CurseForge fingerprint of a file: a MurmurHash2 (seed 1) of
+/// its contents with all whitespace bytes removed
+pub fn compute_fingerprint(data: &[u8]) -> u32 {
+    const M: u32 = 0x5bd1e995;
+
+    let data = data
+        .iter()
+        .copied()
+        .filter(|b| !matches!(b, b'\t' | b'\n' | b'\r' | b' '))
+        .collect::<Vec<u8>>();
+
+    let mut hash = 1 ^ (data.len() as u32);
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k =
+            u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> 24;
+        k = k.wrapping_mul(M);
+        hash = hash.wrapping_mul(M) ^ k;
+    }
+
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        for (i, b) in remainder.iter().enumerate() {
+            hash ^= (*b as u32) << (8 * i);
+        }
+        hash = hash.wrapping_mul(M);
+    }
+
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^= hash >> 15;
+    hash
+}
+
 /// Match files by fingerprint (murmur2 hash)
 #[tracing::instrument(skip(fetch_semaphore, pool))]
 pub async fn match_fingerprints(
@@ -807,6 +843,24 @@ mod tests {
         assert!(query.contains("pageSize=20"));
     }
 
+    #[test]
+    fn test_fingerprint_ignores_whitespace() {
+        assert_eq!(
+            compute_fingerprint(b"{ \"id\": 1 }\r\n"),
+            compute_fingerprint(b"{\"id\":1}")
+        );
+        assert_ne!(
+            compute_fingerprint(b"{\"id\":1}"),
+            compute_fingerprint(b"{\"id\":2}")
+        );
+
+        // Reference MurmurHash2 (seed 1) of the text without its spaces
+        assert_eq!(
+            compute_fingerprint(b"The quick brown fox jumps over the lazy dog"),
+            3751777527
+        );
+    }
+
     #[test]
     fn test_class_id_conversion() {
         assert_eq!(ClassId::from_project_type("mod"), Some(ClassId::Mods));
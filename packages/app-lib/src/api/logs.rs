@@ -6,8 +6,13 @@ use serde::{Deserialize, Serialize};
 use tokio::{
     fs::File,
     io::{AsyncReadExt, AsyncSeekExt},
+    sync::broadcast,
 };
 
+use crate::state::{
+    CONCURRENT_LOGS_PREFIX, LAUNCHER_LOG_EVENTS_PATH, Log4jEvent,
+    ProcessLogEvent, concurrent_logs_subfolder,
+};
 use crate::{
     State,
     prelude::Credentials,
@@ -35,6 +40,115 @@ pub struct LatestLogCursor {
     pub new_file: bool,
 }
 
+#[derive(Serialize, Debug)]
+pub struct LogEventsCursor {
+    pub cursor: u64,
+    pub events: Vec<Log4jEvent>,
+    pub new_file: bool,
+}
+
+/// The level of a log event, ordered from least to most severe
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd,
+)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    pub fn from_string(string: &str) -> Option<Self> {
+        match string.to_ascii_uppercase().as_str() {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            "FATAL" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+}
+
+/// Selects log events by level, logger and thread. Unset fields match every
+/// event
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LogEventFilter {
+    /// Only events at this level or a more severe one. Events with an
+    /// unknown level are always kept
+    pub min_level: Option<LogLevel>,
+    /// Only events of this logger or of loggers nested in it, such as
+    /// `net.minecraft` for `net.minecraft.client.Minecraft`
+    pub logger: Option<String>,
+    pub thread: Option<String>,
+}
+
+impl LogEventFilter {
+    pub fn matches(&self, event: &Log4jEvent) -> bool {
+        if let Some(min_level) = self.min_level
+            && let Some(level) =
+                event.level.as_deref().and_then(LogLevel::from_string)
+            && level < min_level
+        {
+            return false;
+        }
+
+        if let Some(logger) = &self.logger {
+            let Some(event_logger) = &event.logger else {
+                return false;
+            };
+            let nested = event_logger
+                .strip_prefix(logger.as_str())
+                .is_some_and(|x| x.is_empty() || x.starts_with(['.', '/']));
+            if !nested {
+                return false;
+            }
+        }
+
+        self.thread.is_none() || self.thread == event.thread
+    }
+}
+
+/// A live subscription to the log events of running processes, created with
+/// [`subscribe_log_events`]
+pub struct LogEventSubscription {
+    receiver: broadcast::Receiver<ProcessLogEvent>,
+    profile_path: Option<String>,
+    filter: LogEventFilter,
+}
+
+impl LogEventSubscription {
+    /// Waits for the next matching event. Returns `None` once the process
+    /// manager is gone
+    pub async fn next(&mut self) -> Option<ProcessLogEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => {
+                    if self
+                        .profile_path
+                        .as_ref()
+                        .is_none_or(|x| *x == event.profile_path)
+                        && self.filter.matches(&event.event)
+                    {
+                        return Some(event);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        "Log event subscriber lagged behind, skipped {skipped} events"
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Debug)] // Not deserialize
 #[serde(transparent)]
 pub struct CensoredString(String);
@@ -121,32 +235,76 @@ pub async fn get_logs_from_type(
         {
             let entry: std::fs::DirEntry =
                 entry.map_err(|e| IOError::with_path(e, &logs_folder))?;
-            let age = entry
-                .metadata()?
-                .created()
-                .unwrap_or(SystemTime::UNIX_EPOCH);
             let path = entry.path();
-            if !path.is_file() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+
+            // Extra copies of a running profile log to their own subfolder,
+            // and their logs are listed as `<subfolder>/<file name>`
+            if path.is_dir()
+                && log_type == LogType::InfoLog
+                && file_name.starts_with(CONCURRENT_LOGS_PREFIX)
+            {
+                for entry in std::fs::read_dir(&path)
+                    .map_err(|e| IOError::with_path(e, &path))?
+                {
+                    let entry: std::fs::DirEntry =
+                        entry.map_err(|e| IOError::with_path(e, &path))?;
+                    if entry.path().is_file()
+                        && entry.file_name() != LAUNCHER_LOG_EVENTS_PATH
+                    {
+                        let sub_file_name = format!(
+                            "{file_name}/{}",
+                            entry.file_name().to_string_lossy()
+                        );
+                        logs.push(
+                            build_log_entry(
+                                &entry,
+                                log_type,
+                                profile_path,
+                                sub_file_name,
+                                clear_contents,
+                            )
+                            .await,
+                        );
+                    }
+                }
                 continue;
             }
-            if let Some(file_name) = path.file_name() {
-                let file_name = file_name.to_string_lossy().to_string();
-                logs.push(
-                    Logs::build(
-                        log_type,
-                        age,
-                        profile_path,
-                        file_name,
-                        clear_contents,
-                    )
-                    .await,
-                );
+
+            // The structured events sidecar is read through
+            // get_log_events_cursor instead
+            if !path.is_file() || file_name == LAUNCHER_LOG_EVENTS_PATH {
+                continue;
             }
+            logs.push(
+                build_log_entry(
+                    &entry,
+                    log_type,
+                    profile_path,
+                    file_name,
+                    clear_contents,
+                )
+                .await,
+            );
         }
     }
     Ok(())
 }
 
+async fn build_log_entry(
+    entry: &std::fs::DirEntry,
+    log_type: LogType,
+    profile_path: &str,
+    file_name: String,
+    clear_contents: Option<bool>,
+) -> crate::Result<Logs> {
+    let age = entry
+        .metadata()?
+        .created()
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    Logs::build(log_type, age, profile_path, file_name, clear_contents).await
+}
+
 #[tracing::instrument]
 pub async fn get_logs(
     profile_path_id: &str,
@@ -307,6 +465,21 @@ pub async fn get_latest_log_cursor(
     get_generic_live_log_cursor(profile_path, "launcher_log.txt", cursor).await
 }
 
+/// Like [`get_latest_log_cursor`], but for the copy of a profile with the
+/// given `concurrent_index` in its process metadata
+#[tracing::instrument]
+pub async fn get_concurrent_log_cursor(
+    profile_path: &str,
+    concurrent_index: u32,
+    cursor: u64, // 0 to start at beginning of file
+) -> crate::Result<LatestLogCursor> {
+    let log_file_name = match concurrent_logs_subfolder(concurrent_index) {
+        Some(subfolder) => format!("{subfolder}/launcher_log.txt"),
+        None => "launcher_log.txt".to_string(),
+    };
+    get_generic_live_log_cursor(profile_path, &log_file_name, cursor).await
+}
+
 #[tracing::instrument]
 pub async fn get_generic_live_log_cursor(
     profile_path_id: &str,
@@ -364,3 +537,153 @@ pub async fn get_generic_live_log_cursor(
         output,
     })
 }
+
+/// Subscribes to the log events of the running processes of a profile, or
+/// of every profile if `profile_path` is `None`
+///
+/// Only game versions that log with log4j XML events produce log events.
+#[tracing::instrument]
+pub async fn subscribe_log_events(
+    profile_path: Option<String>,
+    filter: LogEventFilter,
+) -> crate::Result<LogEventSubscription> {
+    let state = State::get().await?;
+    Ok(LogEventSubscription {
+        receiver: state.process_manager.subscribe_log_events(),
+        profile_path,
+        filter,
+    })
+}
+
+/// Like [`get_concurrent_log_cursor`], but reads the structured log events
+/// of the latest launch that match `filter`
+///
+/// Only complete lines of the events file are read, so the returned cursor
+/// never points in the middle of an event.
+#[tracing::instrument]
+pub async fn get_log_events_cursor(
+    profile_path: &str,
+    concurrent_index: u32,
+    mut cursor: u64, // 0 to start at beginning of file
+    filter: LogEventFilter,
+) -> crate::Result<LogEventsCursor> {
+    let state = State::get().await?;
+    let mut path = state.directories.profile_logs_dir(profile_path);
+    if let Some(subfolder) = concurrent_logs_subfolder(concurrent_index) {
+        path.push(subfolder);
+    }
+    path.push(LAUNCHER_LOG_EVENTS_PATH);
+
+    if !path.exists() {
+        return Ok(LogEventsCursor {
+            cursor: 0,
+            events: Vec::new(),
+            new_file: false,
+        });
+    }
+
+    let mut file = File::open(&path)
+        .await
+        .map_err(|e| IOError::with_path(e, &path))?;
+    let metadata = file
+        .metadata()
+        .await
+        .map_err(|e| IOError::with_path(e, &path))?;
+
+    let mut new_file = false;
+    if cursor > metadata.len() {
+        // The file was replaced by a new launch since the last read
+        cursor = 0;
+        new_file = true;
+    }
+
+    let mut buffer = Vec::new();
+    file.seek(SeekFrom::Start(cursor))
+        .map_err(|e| IOError::with_path(e, &path))
+        .await?;
+    file.read_to_end(&mut buffer)
+        .map_err(|e| IOError::with_path(e, &path))
+        .await?;
+    let complete_len = buffer
+        .iter()
+        .rposition(|x| *x == b'\n')
+        .map_or(0, |x| x + 1);
+    buffer.truncate(complete_len);
+
+    let credentials = Credentials::get_all(&state.pool)
+        .await?
+        .into_iter()
+        .map(|x| x.1)
+        .collect::<Vec<_>>();
+    let censor = |text: Option<String>| {
+        text.map(|x| CensoredString::censor(x, &credentials).0)
+    };
+
+    let mut events = Vec::new();
+    for line in String::from_utf8_lossy(&buffer).lines() {
+        let event = match serde_json::from_str::<Log4jEvent>(line) {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("Skipping malformed log event: {e}");
+                continue;
+            }
+        };
+        if filter.matches(&event) {
+            events.push(Log4jEvent {
+                message: censor(event.message),
+                throwable: censor(event.throwable),
+                ..event
+            });
+        }
+    }
+
+    Ok(LogEventsCursor {
+        cursor: cursor + complete_len as u64,
+        events,
+        new_file,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(logger: &str, level: &str, thread: &str) -> Log4jEvent {
+        Log4jEvent {
+            logger: Some(logger.to_string()),
+            level: Some(level.to_string()),
+            thread: Some(thread.to_string()),
+            message: Some("message".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_log_event_filter() {
+        let filter = LogEventFilter {
+            min_level: Some(LogLevel::Warn),
+            logger: Some("net.minecraft".to_string()),
+            thread: None,
+        };
+        assert!(filter.matches(&event("net.minecraft", "WARN", "Render")));
+        assert!(filter.matches(&event(
+            "net.minecraft.client.Minecraft",
+            "ERROR",
+            "Render"
+        )));
+        assert!(!filter.matches(&event("net.minecraft", "INFO", "Render")));
+        assert!(!filter.matches(&event(
+            "net.minecraftforge",
+            "WARN",
+            "Render"
+        )));
+        assert!(filter.matches(&event("net.minecraft", "CUSTOM", "Render")));
+
+        let filter = LogEventFilter {
+            thread: Some("Server thread".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&event("sodium", "DEBUG", "Server thread")));
+        assert!(!filter.matches(&event("sodium", "DEBUG", "Render thread")));
+    }
+}
@@ -1,13 +1,19 @@
 use crate::State;
-use crate::state::CachedEntry;
+use crate::state::{CacheBehaviour, CachedEntry};
 pub use daedalus::minecraft::VersionManifest;
 pub use daedalus::modded::Manifest;
 
 #[tracing::instrument]
 pub async fn get_minecraft_versions() -> crate::Result<VersionManifest> {
+    get_minecraft_versions_with_cache(None).await
+}
+
+pub(crate) async fn get_minecraft_versions_with_cache(
+    cache_behaviour: Option<CacheBehaviour>,
+) -> crate::Result<VersionManifest> {
     let state = State::get().await?;
     let minecraft_versions = CachedEntry::get_minecraft_manifest(
-        None,
+        cache_behaviour,
         &state.pool,
         &state.api_semaphore,
     )
@@ -21,10 +27,17 @@ pub async fn get_minecraft_versions() -> crate::Result<VersionManifest> {
 
 // #[tracing::instrument]
 pub async fn get_loader_versions(loader: &str) -> crate::Result<Manifest> {
+    get_loader_versions_with_cache(loader, None).await
+}
+
+pub(crate) async fn get_loader_versions_with_cache(
+    loader: &str,
+    cache_behaviour: Option<CacheBehaviour>,
+) -> crate::Result<Manifest> {
     let state = State::get().await?;
     let loaders = CachedEntry::get_loader_manifest(
         loader,
-        None,
+        cache_behaviour,
         &state.pool,
         &state.api_semaphore,
     )
@@ -1,5 +1,6 @@
 //! API for interacting with Theseus
 pub mod cache;
+pub mod crash;
 pub mod curseforge;
 pub mod handler;
 pub mod jre;
@@ -14,6 +15,7 @@ pub mod profile;
 pub mod server_address;
 pub mod settings;
 pub mod tags;
+pub mod world_backups;
 pub mod worlds;
 
 pub mod data {
@@ -25,18 +27,21 @@ pub mod data {
     };
     pub use crate::state::{
         CacheBehaviour, CacheValueType, CfCachedAuthor, CfCachedCategory,
-        CfCachedDependency, CfCachedFile, CfCachedFingerprint, CfCachedProject,
-        CfSearchResults, ContentProvider, Credentials, Dependency,
-        DirectoryInfo, Hooks, JavaVersion, LinkedData, MemorySettings,
-        ModLoader, ModrinthCredentials, Organization, ProcessMetadata,
-        ProfileFile, Project, ProjectType, SearchResult, SearchResults,
-        Settings, TeamMember, Theme, User, Version, WindowSize,
+        CfCachedDependency, CfCachedFile, CfCachedFileUpdate,
+        CfCachedFingerprint, CfCachedProject, CfSearchResults, ContentProvider,
+        Credentials, Dependency, DirectoryInfo, GameServer, GameStatus,
+        GarbageCollector, Hooks, JavaVersion, JvmPreset, LinkedData, LoadedMod,
+        Log4jEvent, MemorySettings, ModLoader, ModrinthCredentials,
+        Organization, ProcessLogEvent, ProcessMetadata, ProfileFile, Project,
+        ProjectType, SearchResult, SearchResults, Settings, StopMethod,
+        TeamMember, Theme, UpdateChannel, UpdatePolicy, User, Version,
+        WindowSize, content_store::ContentStoreGcResult,
     };
 }
 
 pub mod prelude {
     pub use crate::{
-        State, curseforge,
+        State, crash, curseforge,
         data::*,
         event::CommandPayload,
         jre, metadata, minecraft_auth, mr_auth, pack, process,
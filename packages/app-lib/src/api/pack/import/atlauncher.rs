@@ -10,7 +10,7 @@ use crate::{
         install_from::CreatePackDescription,
     },
     prelude::ModLoader,
-    state::{LinkedData, ProfileInstallStage},
+    state::{ContentProvider, LinkedData, ProfileInstallStage},
     util::io,
 };
 
@@ -162,6 +162,7 @@ pub async fn import_atlauncher(
     let description = CreatePackDescription {
         icon,
         override_title: Some(atinstance.launcher.name.clone()),
+        provider: ContentProvider::Modrinth,
         project_id: None,
         version_id: None,
         existing_loading_bar: None,
@@ -225,6 +226,7 @@ async fn import_atlauncher_unmanaged(
             && let Some(ref version_id) = description.version_id
         {
             prof.linked_data = Some(LinkedData {
+                provider: description.provider,
                 project_id: project_id.clone(),
                 version_id: version_id.clone(),
                 locked: true,
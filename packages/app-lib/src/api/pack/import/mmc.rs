@@ -8,6 +8,7 @@ use crate::{
         import::{self, copy_dotminecraft},
         install_from::{self, CreatePackDescription, PackDependency},
     },
+    state::ContentProvider,
     util::io,
 };
 
@@ -206,6 +207,7 @@ pub async fn import_mmc(
     let mut description = CreatePackDescription {
         icon,
         override_title: instance_cfg.name,
+        provider: ContentProvider::Modrinth,
         project_id: None,
         version_id: None,
         existing_loading_bar: None,
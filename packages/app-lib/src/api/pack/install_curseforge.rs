@@ -0,0 +1,714 @@
+use crate::State;
+use crate::api::curseforge::{self, ClassId};
+use crate::data::ProjectType;
+use crate::event::LoadingBarId;
+use crate::event::LoadingBarType;
+use crate::event::emit::{
+    emit_loading, emit_warning, init_loading, loading_try_for_each_concurrent,
+};
+use crate::pack::install_from::{
+    CreatePack, CreatePackDescription, CreatePackLocation, PackDependency,
+    PackOverride, PackZipReader, extract_pack_overrides, finish_pack_install,
+    generate_pack_from_file, get_pack_overrides, read_pack_zip,
+    setup_pack_profile,
+};
+use crate::profile;
+use crate::state::{
+    CacheBehaviour, CfCachedFile, CfCachedProject, ContentProvider,
+    ProfileInstallStage, cache_file_hash, content_store,
+};
+use crate::util::fetch::{fetch, fetch_advanced, write_cached_icon};
+use crate::util::io;
+use futures::StreamExt;
+use path_util::SafeRelativeUtf8UnixPathBuf;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::ErrorKind;
+
+/// The manifest.json of a CurseForge modpack zip, as read by the installer
+/// and written by [`crate::profile::export::export_curseforge`]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeManifest {
+    pub minecraft: CurseForgeManifestMinecraft,
+    pub manifest_type: String,
+    #[serde(default)]
+    pub manifest_version: u32,
+    pub name: String,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub files: Vec<CurseForgeManifestFile>,
+    #[serde(default = "default_overrides")]
+    pub overrides: String,
+}
+
+fn default_overrides() -> String {
+    "overrides".to_string()
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeManifestMinecraft {
+    pub version: String,
+    #[serde(default)]
+    pub mod_loaders: Vec<CurseForgeManifestModLoader>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeManifestModLoader {
+    /// The loader and its version, such as `forge-47.2.0`
+    pub id: String,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CurseForgeManifestFile {
+    #[serde(rename = "projectID")]
+    pub project_id: i32,
+    #[serde(rename = "fileID")]
+    pub file_id: i32,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl CurseForgeManifest {
+    /// Converts the game and loader versions to the dependency format of
+    /// .mrpack files, using the primary loader if there are several
+    pub fn dependencies(&self) -> HashMap<PackDependency, String> {
+        let mut dependencies = HashMap::new();
+        dependencies
+            .insert(PackDependency::Minecraft, self.minecraft.version.clone());
+
+        let loader = self
+            .minecraft
+            .mod_loaders
+            .iter()
+            .find(|x| x.primary)
+            .or_else(|| self.minecraft.mod_loaders.first());
+
+        if let Some(loader) = loader
+            && let Some((name, version)) = loader.id.split_once('-')
+        {
+            let dependency = match name {
+                "forge" => Some(PackDependency::Forge),
+                "neoforge" => Some(PackDependency::NeoForge),
+                "fabric" => Some(PackDependency::FabricLoader),
+                "quilt" => Some(PackDependency::QuiltLoader),
+                _ => None,
+            };
+
+            if let Some(dependency) = dependency {
+                dependencies.insert(dependency, version.to_string());
+            }
+        }
+
+        dependencies
+    }
+}
+
+/// A modpack file that could not be downloaded automatically, because its
+/// author disabled third-party downloads or it no longer exists. It has to
+/// be downloaded from the CurseForge website and placed at `path`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManualDownload {
+    pub project_id: i32,
+    pub file_id: i32,
+    pub project_name: Option<String>,
+    pub file_name: Option<String>,
+    /// Path to place the file at, relative to the profile
+    pub path: Option<String>,
+    /// Page of the file on the CurseForge website
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CurseForgePackInstall {
+    pub profile_path: String,
+    pub manual_downloads: Vec<ManualDownload>,
+}
+
+/// Warns the user about the files of a modpack they have to download
+/// themselves, if there are any
+pub(crate) async fn emit_manual_downloads_warning(
+    manual_downloads: &[ManualDownload],
+) -> crate::Result<()> {
+    if !manual_downloads.is_empty() {
+        emit_warning(&format!(
+            "Some modpack files must be downloaded manually from CurseForge: {}",
+            manual_downloads
+                .iter()
+                .map(|x| x
+                    .file_name
+                    .clone()
+                    .or_else(|| x.project_name.clone())
+                    .unwrap_or_else(|| x.url.clone()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Install a CurseForge modpack
+/// Wrapper around install_zipped_curseforge_pack_files that downloads the
+/// pack zip if needed. If it fails, it will remove the profile (fail safely)
+pub async fn install_zipped_curseforge_pack(
+    location: CreatePackLocation,
+    profile_path: String,
+) -> crate::Result<CurseForgePackInstall> {
+    let create_pack = match location {
+        CreatePackLocation::FromCurseForgeId {
+            project_id,
+            file_id,
+            title,
+            icon_url,
+        } => {
+            generate_pack_from_curseforge_id(
+                project_id,
+                file_id,
+                title,
+                icon_url,
+                profile_path.clone(),
+                None,
+            )
+            .await
+        }
+        CreatePackLocation::FromCurseForgeZip { path } => {
+            generate_pack_from_file(path, profile_path.clone()).await
+        }
+        CreatePackLocation::FromVersionId { .. }
+        | CreatePackLocation::FromFile { .. } => {
+            Err(crate::ErrorKind::InputError(
+                "Location is not a CurseForge modpack".to_string(),
+            )
+            .into())
+        }
+    };
+
+    // Install pack files, and if it fails, fail safely by removing the profile
+    let result = match create_pack {
+        Ok(create_pack) => {
+            install_zipped_curseforge_pack_files(create_pack, false).await
+        }
+        Err(err) => Err(err),
+    };
+
+    match result {
+        Ok(install) => Ok(install),
+        Err(err) => {
+            let _ = crate::api::profile::remove(&profile_path).await;
+
+            Err(err)
+        }
+    }
+}
+
+#[tracing::instrument]
+pub async fn generate_pack_from_curseforge_id(
+    project_id: i32,
+    file_id: i32,
+    title: String,
+    icon_url: Option<String>,
+    profile_path: String,
+
+    // Existing loading bar, pre-initialized with PackFileDownload
+    initialized_loading_bar: Option<LoadingBarId>,
+) -> crate::Result<CreatePack> {
+    let state = State::get().await?;
+
+    let loading_bar = if let Some(bar) = initialized_loading_bar {
+        emit_loading(&bar, 0.0, Some("Downloading pack file"))?;
+        bar
+    } else {
+        init_loading(
+            LoadingBarType::PackFileDownload {
+                profile_path: profile_path.clone(),
+                pack_name: title,
+                icon: icon_url,
+                pack_version: file_id.to_string(),
+            },
+            100.0,
+            "Downloading pack file",
+        )
+        .await?
+    };
+
+    emit_loading(&loading_bar, 0.0, Some("Fetching file"))?;
+    let file = curseforge::get_file_cached(file_id, None)
+        .await?
+        .filter(|x| x.mod_id == project_id)
+        .ok_or_else(|| {
+            crate::ErrorKind::InputError(
+                "Invalid CurseForge file ID specified!".to_string(),
+            )
+        })?;
+    emit_loading(&loading_bar, 10.0, None)?;
+
+    let url = match file.download_url {
+        Some(url) => url,
+        None => curseforge::get_download_url(project_id, file_id)
+            .await
+            .map_err(|e| {
+                crate::ErrorKind::InputError(format!(
+                    "Unable to get download URL for CurseForge modpack: {e}. The author may have disabled third-party downloads."
+                ))
+            })?,
+    };
+
+    let file = fetch_advanced(
+        Method::GET,
+        &url,
+        file.sha1_hash.as_deref(),
+        None,
+        None,
+        Some((&loading_bar, 70.0)),
+        &state.fetch_semaphore,
+        &state.pool,
+    )
+    .await?;
+    emit_loading(&loading_bar, 0.0, Some("Fetching project metadata"))?;
+
+    let project = curseforge::get_mod_cached(project_id, None)
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::InputError(
+                "Invalid CurseForge project ID specified!".to_string(),
+            )
+        })?;
+
+    emit_loading(&loading_bar, 10.0, Some("Retrieving icon"))?;
+    let icon = if let Some(icon_url) = project.logo_url {
+        let icon_bytes =
+            fetch(&icon_url, None, &state.fetch_semaphore, &state.pool).await?;
+
+        if let Some(filename) = icon_url.rsplit('/').next() {
+            Some(
+                write_cached_icon(
+                    filename,
+                    &state.directories.caches_dir(),
+                    icon_bytes,
+                    &state.io_semaphore,
+                )
+                .await?,
+            )
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+    emit_loading(&loading_bar, 10.0, None)?;
+
+    Ok(CreatePack {
+        file,
+        description: CreatePackDescription {
+            icon,
+            override_title: Some(project.name),
+            provider: ContentProvider::CurseForge,
+            project_id: Some(project_id.to_string()),
+            version_id: Some(file_id.to_string()),
+            existing_loading_bar: Some(loading_bar),
+            profile_path,
+        },
+    })
+}
+
+/// Install all files of a CurseForge modpack zip
+/// Does not remove the profile if it fails
+pub async fn install_zipped_curseforge_pack_files(
+    create_pack: CreatePack,
+    ignore_lock: bool,
+) -> crate::Result<CurseForgePackInstall> {
+    let state = &State::get().await?;
+
+    let file = create_pack.file;
+    let mut description = create_pack.description;
+    let profile_path = description.profile_path.clone();
+
+    let contents = PackContents::read(&file).await?;
+
+    let loading_bar = setup_pack_profile(
+        &mut description,
+        &contents.manifest.name,
+        &contents.manifest.dependencies(),
+        contents.paths().into_iter().collect(),
+        contents.manifest.version.clone(),
+        ignore_lock,
+    )
+    .await?;
+
+    emit_loading(&loading_bar, 0.0, Some("Resolving modpack files"))?;
+
+    let PackContents {
+        mut zip_reader,
+        files,
+        overrides,
+        ..
+    } = contents;
+
+    let mut downloads = Vec::new();
+    let mut manual_downloads = Vec::new();
+
+    for pack_file in files {
+        let url = match &pack_file.file {
+            Some(file) if file.download_url.is_some() => {
+                file.download_url.clone()
+            }
+            Some(_) => curseforge::get_download_url(
+                pack_file.project_id,
+                pack_file.file_id,
+            )
+            .await
+            .ok(),
+            None => None,
+        }
+        .filter(|x| !x.is_empty());
+
+        match (pack_file.file, pack_file.path, url) {
+            (Some(file), Some(path), Some(url)) => {
+                downloads.push((file, path, url));
+            }
+            (file, path, _) => {
+                let project = pack_file.project.as_ref();
+                let url = match project.and_then(|x| x.website_url.as_ref()) {
+                    Some(website_url) => format!(
+                        "{}/files/{}",
+                        website_url.trim_end_matches('/'),
+                        pack_file.file_id
+                    ),
+                    None => format!(
+                        "https://www.curseforge.com/projects/{}",
+                        pack_file.project_id
+                    ),
+                };
+
+                manual_downloads.push(ManualDownload {
+                    project_id: pack_file.project_id,
+                    file_id: pack_file.file_id,
+                    project_name: project.map(|x| x.name.clone()),
+                    file_name: file.map(|x| x.file_name),
+                    path: path.map(|x| x.to_string()),
+                    url,
+                });
+            }
+        }
+    }
+
+    let num_files = downloads.len();
+    loading_try_for_each_concurrent(
+        futures::stream::iter(downloads.into_iter()).map(
+            Ok::<
+                (CfCachedFile, SafeRelativeUtf8UnixPathBuf, String),
+                crate::Error,
+            >,
+        ),
+        None,
+        Some(&loading_bar),
+        70.0,
+        num_files,
+        None,
+        |(file, path, url)| {
+            let profile_path = profile_path.clone();
+            async move {
+                let hash = file.sha1_hash.as_deref();
+                let bytes =
+                    fetch(&url, hash, &state.fetch_semaphore, &state.pool)
+                        .await?;
+
+                let full_path = profile::get_full_path(&profile_path)
+                    .await?
+                    .join(path.as_str());
+
+                cache_file_hash(
+                    bytes.clone(),
+                    &profile_path,
+                    path.as_str(),
+                    hash,
+                    ProjectType::get_from_parent_folder(&full_path),
+                    &state.pool,
+                )
+                .await?;
+
+                content_store::link_bytes(
+                    bytes,
+                    hash,
+                    &full_path,
+                    &state.io_semaphore,
+                )
+                .await?;
+
+                Ok(())
+            }
+        },
+    )
+    .await?;
+
+    extract_pack_overrides(
+        &mut zip_reader,
+        overrides,
+        &profile_path,
+        &loading_bar,
+    )
+    .await?;
+
+    finish_pack_install(&description, loading_bar).await?;
+
+    Ok(CurseForgePackInstall {
+        profile_path,
+        manual_downloads,
+    })
+}
+
+/// The path, relative to the profile, a modpack file is installed to. The
+/// folder depends on the class of its project, defaulting to mods
+fn get_pack_file_path(
+    file: &CfCachedFile,
+    project: Option<&CfCachedProject>,
+) -> Option<SafeRelativeUtf8UnixPathBuf> {
+    let project_type = project
+        .and_then(|x| x.class_id)
+        .and_then(ClassId::from_i32)
+        .and_then(|x| match x {
+            ClassId::Mods => Some(ProjectType::Mod),
+            ClassId::ResourcePacks => Some(ProjectType::ResourcePack),
+            ClassId::Shaders => Some(ProjectType::ShaderPack),
+            ClassId::DataPacks => Some(ProjectType::DataPack),
+            _ => None,
+        })
+        .unwrap_or(ProjectType::Mod);
+
+    SafeRelativeUtf8UnixPathBuf::try_from(format!(
+        "{}/{}",
+        project_type.get_folder(),
+        file.file_name
+    ))
+    .ok()
+}
+
+/// A CurseForge modpack zip, with its required files resolved through the
+/// CurseForge API
+struct PackContents<'a> {
+    zip_reader: PackZipReader<'a>,
+    manifest: CurseForgeManifest,
+    files: Vec<PackContentsFile>,
+    overrides: Vec<PackOverride>,
+}
+
+struct PackContentsFile {
+    project_id: i32,
+    file_id: i32,
+    project: Option<CfCachedProject>,
+    /// `None` if the file does not exist or belongs to another project
+    file: Option<CfCachedFile>,
+    /// Path relative to the profile the file is installed to
+    path: Option<SafeRelativeUtf8UnixPathBuf>,
+}
+
+impl<'a> PackContents<'a> {
+    async fn read(file: &'a bytes::Bytes) -> crate::Result<Self> {
+        let (zip_reader, manifest) = read_pack_zip::<CurseForgeManifest>(
+            file,
+            "manifest.json",
+            "CurseForge modpack",
+        )
+        .await?;
+
+        if manifest.manifest_type != "minecraftModpack" {
+            return Err(crate::ErrorKind::InputError(
+                "Pack is not a Minecraft modpack".to_string(),
+            )
+            .into());
+        }
+
+        //TODO: Future update: prompt user for optional files in a modpack
+        let pack_files = manifest
+            .files
+            .iter()
+            .filter(|x| x.required)
+            .collect::<Vec<_>>();
+
+        let mut files = curseforge::get_files_cached(
+            &pack_files.iter().map(|x| x.file_id).collect::<Vec<_>>(),
+            None,
+        )
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x))
+        .collect::<HashMap<_, _>>();
+
+        let mut projects = curseforge::get_mods_cached(
+            &pack_files.iter().map(|x| x.project_id).collect::<Vec<_>>(),
+            None,
+        )
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x))
+        .collect::<HashMap<_, _>>();
+
+        let files = pack_files
+            .into_iter()
+            .map(|pack_file| {
+                let project = projects.remove(&pack_file.project_id);
+                let file = files
+                    .remove(&pack_file.file_id)
+                    .filter(|x| x.mod_id == pack_file.project_id);
+                let path = file
+                    .as_ref()
+                    .and_then(|x| get_pack_file_path(x, project.as_ref()));
+
+                PackContentsFile {
+                    project_id: pack_file.project_id,
+                    file_id: pack_file.file_id,
+                    project,
+                    file,
+                    path,
+                }
+            })
+            .collect();
+
+        let overrides =
+            get_pack_overrides(&zip_reader, &[manifest.overrides.as_str()])?;
+
+        Ok(Self {
+            zip_reader,
+            manifest,
+            files,
+            overrides,
+        })
+    }
+
+    /// Paths relative to the profile, of both pack files and overrides
+    fn paths(&self) -> HashSet<String> {
+        self.files
+            .iter()
+            .filter_map(|x| x.path.as_ref().map(|x| x.to_string()))
+            .chain(self.overrides.iter().map(|x| x.path.clone()))
+            .collect()
+    }
+
+    /// CurseForge file IDs of the pack files
+    fn file_ids(&self) -> HashSet<String> {
+        self.files.iter().map(|x| x.file_id.to_string()).collect()
+    }
+}
+
+/// Reads the paths a CurseForge modpack zip places in a profile
+pub(crate) async fn get_curseforge_pack_paths(
+    file: &bytes::Bytes,
+) -> crate::Result<Vec<String>> {
+    Ok(PackContents::read(file)
+        .await?
+        .paths()
+        .into_iter()
+        .collect())
+}
+
+/// Removes the files an older version of a CurseForge modpack installed
+/// that the new version no longer has, so they can't linger after updating.
+/// Files the user added are kept
+pub async fn remove_related_curseforge_files(
+    profile_path: String,
+    old_pack: bytes::Bytes,
+    new_pack: &bytes::Bytes,
+) -> crate::Result<()> {
+    let old_contents = PackContents::read(&old_pack).await?;
+    let new_contents = PackContents::read(new_pack).await?;
+    let old_file_ids = old_contents.file_ids();
+    let new_file_ids = new_contents.file_ids();
+
+    // Set install stage to installing, and do not change it back (as files are being removed and are not being reinstalled here)
+    profile::edit(&profile_path, |prof| {
+        prof.install_stage = ProfileInstallStage::PackInstalling;
+        async { Ok(()) }
+    })
+    .await?;
+
+    let state = State::get().await?;
+    let profile = profile::get(&profile_path).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    let profile_full_path = profile::get_full_path(&profile_path).await?;
+
+    let mut to_remove = old_contents
+        .paths()
+        .difference(&new_contents.paths())
+        .cloned()
+        .collect::<HashSet<_>>();
+
+    // Pack files may have been renamed since they were installed, so they
+    // are also removed by the CurseForge file they were recognized as
+    for (file_path, project) in profile
+        .get_projects(
+            Some(CacheBehaviour::MustRevalidate),
+            &state.pool,
+            &state.api_semaphore,
+        )
+        .await?
+    {
+        if let Some(metadata) = &project.metadata
+            && metadata.provider == ContentProvider::CurseForge
+            && old_file_ids.contains(&metadata.version_id)
+            && !new_file_ids.contains(&metadata.version_id)
+        {
+            to_remove.insert(file_path);
+        }
+    }
+
+    for path in to_remove {
+        for path in [format!("{path}.disabled"), path] {
+            match io::remove_file(profile_full_path.join(path)).await {
+                Ok(_) => (),
+                Err(err) if err.kind() == ErrorKind::NotFound => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_dependencies() {
+        let manifest: CurseForgeManifest = serde_json::from_str(
+            r#"{
+                "minecraft": {
+                    "version": "1.20.1",
+                    "modLoaders": [
+                        { "id": "fabric-0.15.7", "primary": false },
+                        { "id": "forge-47.2.0", "primary": true }
+                    ]
+                },
+                "manifestType": "minecraftModpack",
+                "manifestVersion": 1,
+                "name": "Example Pack",
+                "version": "1.0.0",
+                "author": "someone",
+                "files": [
+                    { "projectID": 238222, "fileID": 4712864, "required": true }
+                ],
+                "overrides": "overrides"
+            }"#,
+        )
+        .unwrap();
+
+        let dependencies = manifest.dependencies();
+        assert_eq!(dependencies.len(), 2);
+        assert_eq!(dependencies[&PackDependency::Minecraft], "1.20.1");
+        assert_eq!(dependencies[&PackDependency::Forge], "47.2.0");
+        assert_eq!(manifest.files[0].project_id, 238222);
+        assert!(manifest.files[0].required);
+    }
+}
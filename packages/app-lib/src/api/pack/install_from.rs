@@ -1,15 +1,21 @@
 use crate::State;
-use crate::data::ModLoader;
-use crate::event::emit::{emit_loading, init_loading};
+use crate::data::{ModLoader, ProjectType};
+use crate::event::emit::{emit_loading, init_loading, init_or_edit_loading};
 use crate::event::{LoadingBarId, LoadingBarType};
-use crate::state::{CachedEntry, LinkedData, ProfileInstallStage, SideType};
-use crate::util::fetch::{fetch, fetch_advanced, write_cached_icon};
+use crate::state::{
+    CachedEntry, ContentProvider, LinkedData, ProfileInstallStage, SideType,
+    cache_file_hash,
+};
+use crate::util::fetch::{fetch, fetch_advanced, write, write_cached_icon};
 use crate::util::io;
 
+use async_zip::tokio::read::seek::ZipFileReader;
 use path_util::SafeRelativeUtf8UnixPathBuf;
 use reqwest::Method;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::Cursor;
 
 use std::path::PathBuf;
 
@@ -93,6 +99,17 @@ pub enum CreatePackLocation {
     FromFile {
         path: PathBuf,
     },
+    // Create a pack from a CurseForge modpack project and file ID
+    FromCurseForgeId {
+        project_id: i32,
+        file_id: i32,
+        title: String,
+        icon_url: Option<String>,
+    },
+    // Create a pack from a CurseForge modpack zip (a manifest.json and overrides)
+    FromCurseForgeZip {
+        path: PathBuf,
+    },
 }
 
 #[derive(Serialize, Deserialize)]
@@ -136,6 +153,8 @@ pub struct CreatePack {
 pub struct CreatePackDescription {
     pub icon: Option<PathBuf>,
     pub override_title: Option<String>,
+    /// Where project_id and version_id come from
+    pub provider: ContentProvider,
     pub project_id: Option<String>,
     pub version_id: Option<String>,
     pub existing_loading_bar: Option<LoadingBarId>,
@@ -155,13 +174,31 @@ pub fn get_profile_from_pack(
             name: title,
             icon_url,
             linked_data: Some(LinkedData {
+                provider: ContentProvider::Modrinth,
                 project_id,
                 version_id,
                 locked: true,
             }),
             ..Default::default()
         },
-        CreatePackLocation::FromFile { path } => {
+        CreatePackLocation::FromCurseForgeId {
+            project_id,
+            file_id,
+            title,
+            icon_url,
+        } => CreatePackProfile {
+            name: title,
+            icon_url,
+            linked_data: Some(LinkedData {
+                provider: ContentProvider::CurseForge,
+                project_id: project_id.to_string(),
+                version_id: file_id.to_string(),
+                locked: true,
+            }),
+            ..Default::default()
+        },
+        CreatePackLocation::FromFile { path }
+        | CreatePackLocation::FromCurseForgeZip { path } => {
             let file_name = path
                 .file_stem()
                 .unwrap_or_default()
@@ -295,6 +332,7 @@ pub async fn generate_pack_from_version_id(
         description: CreatePackDescription {
             icon,
             override_title: None,
+            provider: ContentProvider::Modrinth,
             project_id: Some(project_id),
             version_id: Some(version_id),
             existing_loading_bar: Some(loading_bar),
@@ -315,6 +353,7 @@ pub async fn generate_pack_from_file(
         description: CreatePackDescription {
             icon: None,
             override_title: None,
+            provider: ContentProvider::Modrinth,
             project_id: None,
             version_id: None,
             existing_loading_bar: None,
@@ -388,6 +427,7 @@ pub async fn set_profile_information(
             && let Some(ref version_id) = description.version_id
         {
             prof.linked_data = Some(LinkedData {
+                provider: description.provider,
                 project_id: project_id.clone(),
                 version_id: version_id.clone(),
                 locked: if !ignore_lock {
@@ -411,3 +451,272 @@ pub async fn set_profile_information(
     .await?;
     Ok(())
 }
+
+/// A modpack zip read from memory
+pub(crate) type PackZipReader<'a> = ZipFileReader<Cursor<&'a bytes::Bytes>>;
+
+/// Opens a modpack zip and reads its manifest
+pub(crate) async fn read_pack_zip<'a, T: DeserializeOwned>(
+    file: &'a bytes::Bytes,
+    manifest_name: &str,
+    pack_kind: &str,
+) -> crate::Result<(PackZipReader<'a>, T)> {
+    // Create zip reader around file
+    let mut zip_reader = ZipFileReader::with_tokio(Cursor::new(file))
+        .await
+        .map_err(|_| {
+            crate::Error::from(crate::ErrorKind::InputError(
+                "Failed to read input modpack zip".to_string(),
+            ))
+        })?;
+
+    let Some(manifest_idx) =
+        zip_reader.file().entries().iter().position(|f| {
+            f.filename().as_str().is_ok_and(|x| x == manifest_name)
+        })
+    else {
+        return Err(crate::Error::from(crate::ErrorKind::InputError(format!(
+            "No pack manifest found in {pack_kind}"
+        ))));
+    };
+
+    let mut manifest = String::new();
+    let mut reader = zip_reader.reader_with_entry(manifest_idx).await?;
+    reader.read_to_string_checked(&mut manifest).await?;
+
+    Ok((zip_reader, serde_json::from_str(&manifest)?))
+}
+
+/// A file of a modpack zip that is copied into the profile as is
+pub(crate) struct PackOverride {
+    /// Index of the entry in the zip
+    pub index: usize,
+    /// Path relative to the profile
+    pub path: String,
+}
+
+/// Lists the files in the override folders of a modpack zip
+pub(crate) fn get_pack_overrides(
+    zip_reader: &PackZipReader<'_>,
+    override_dirs: &[&str],
+) -> crate::Result<Vec<PackOverride>> {
+    let mut overrides = Vec::new();
+    for (index, file) in zip_reader.file().entries().iter().enumerate() {
+        let filename = file.filename().as_str().unwrap_or_default();
+        if filename.ends_with('/') {
+            continue;
+        }
+
+        let Some(dir) = override_dirs.iter().find(|dir| {
+            filename
+                .strip_prefix(dir.trim_end_matches('/'))
+                .is_some_and(|x| x.starts_with('/'))
+        }) else {
+            continue;
+        };
+
+        let path = SafeRelativeUtf8UnixPathBuf::try_from(filename.to_string())?;
+        let path = path.strip_prefix(dir.trim_end_matches('/')).map_err(|_| {
+            crate::Error::from(crate::ErrorKind::OtherError(format!(
+                "Failed to strip override prefix from override file path: {path}"
+            )))
+        })?;
+
+        overrides.push(PackOverride {
+            index,
+            path: path.as_str().to_string(),
+        });
+    }
+
+    Ok(overrides)
+}
+
+/// Prepares a profile for the files of a modpack and starts its download
+/// loading bar
+///
+/// Profiles that are already installed get a rollback snapshot of
+/// `pack_paths` first. Profiles created for the pack have nothing to roll
+/// back to, and updates take their snapshot before the old pack's files are
+/// removed.
+pub(crate) async fn setup_pack_profile(
+    description: &mut CreatePackDescription,
+    pack_name: &str,
+    dependencies: &HashMap<PackDependency, String>,
+    pack_paths: Vec<String>,
+    pack_version: Option<String>,
+    ignore_lock: bool,
+) -> crate::Result<LoadingBarId> {
+    let profile_path = &description.profile_path;
+
+    if crate::api::profile::get(profile_path)
+        .await?
+        .is_some_and(|x| {
+            x.install_stage != ProfileInstallStage::PackInstalling
+                && x.install_stage != ProfileInstallStage::NotInstalled
+        })
+    {
+        crate::api::profile::rollback::create_rollback_snapshot(
+            profile_path,
+            crate::api::profile::rollback::RollbackOperation::InstallModpack,
+            pack_paths,
+        )
+        .await?;
+    }
+
+    // Sets generated profile attributes to the pack ones (using profile::edit)
+    set_profile_information(
+        profile_path.clone(),
+        description,
+        pack_name,
+        dependencies,
+        ignore_lock,
+    )
+    .await?;
+
+    init_or_edit_loading(
+        description.existing_loading_bar.take(),
+        LoadingBarType::PackDownload {
+            profile_path: profile_path.clone(),
+            pack_name: pack_name.to_string(),
+            icon: description.icon.clone(),
+            pack_id: match description.provider {
+                ContentProvider::Modrinth => description.project_id.clone(),
+                ContentProvider::CurseForge => None,
+            },
+            pack_version,
+        },
+        100.0,
+        "Downloading modpack",
+    )
+    .await
+}
+
+/// Writes the overrides of a modpack zip into the profile
+pub(crate) async fn extract_pack_overrides(
+    zip_reader: &mut PackZipReader<'_>,
+    overrides: Vec<PackOverride>,
+    profile_path: &str,
+    loading_bar: &LoadingBarId,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+
+    emit_loading(loading_bar, 0.0, Some("Extracting overrides"))?;
+
+    let overrides_count = overrides.len();
+    for (i, PackOverride { index, path }) in overrides.into_iter().enumerate() {
+        let mut file_bytes = vec![];
+        let mut reader = zip_reader.reader_with_entry(index).await?;
+        reader.read_to_end_checked(&mut file_bytes).await?;
+
+        let file_bytes = bytes::Bytes::from(file_bytes);
+
+        cache_file_hash(
+            file_bytes.clone(),
+            profile_path,
+            &path,
+            None,
+            ProjectType::get_from_parent_folder(&path),
+            &state.pool,
+        )
+        .await?;
+
+        write(
+            &crate::api::profile::get_full_path(profile_path)
+                .await?
+                .join(&path),
+            &file_bytes,
+            &state.io_semaphore,
+        )
+        .await?;
+
+        emit_loading(
+            loading_bar,
+            30.0 / overrides_count as f64,
+            Some(&format!("Extracting override {}/{overrides_count}", i + 1)),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Picks up the icon of a modpack from its overrides and installs the game
+/// of the profile, once all the pack files are in place
+pub(crate) async fn finish_pack_install(
+    description: &CreatePackDescription,
+    loading_bar: LoadingBarId,
+) -> crate::Result<()> {
+    let profile_path = &description.profile_path;
+
+    // If the icon doesn't exist, we expect icon.png to be a potential icon.
+    // If it doesn't exist, and an override to icon.png exists, cache and use that
+    let potential_icon = crate::api::profile::get_full_path(profile_path)
+        .await?
+        .join("icon.png");
+    if description.icon.is_none() && potential_icon.exists() {
+        crate::api::profile::edit_icon(profile_path, Some(&potential_icon))
+            .await?;
+    }
+
+    if let Some(profile_val) = crate::api::profile::get(profile_path).await? {
+        crate::launcher::install_minecraft(
+            &profile_val,
+            Some(loading_bar),
+            false,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_zip::{Compression, ZipEntryBuilder};
+
+    #[tokio::test]
+    async fn pack_overrides_are_relative_to_their_folder() {
+        let mut zip = Vec::new();
+        let mut writer =
+            async_zip::tokio::write::ZipFileWriter::with_tokio(&mut zip);
+        for name in [
+            "manifest.json",
+            "overrides/",
+            "overrides/config/a.toml",
+            "client-overrides/options.txt",
+            "overrides-extra/b.txt",
+        ] {
+            writer
+                .write_entry_whole(
+                    ZipEntryBuilder::new(
+                        name.to_string().into(),
+                        Compression::Deflate,
+                    ),
+                    b"data",
+                )
+                .await
+                .unwrap();
+        }
+        writer.close().await.unwrap();
+
+        let zip = bytes::Bytes::from(zip);
+        let zip_reader =
+            ZipFileReader::with_tokio(Cursor::new(&zip)).await.unwrap();
+        let paths = |override_dirs| {
+            get_pack_overrides(&zip_reader, override_dirs)
+                .unwrap()
+                .into_iter()
+                .map(|x| (x.index, x.path))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            paths(&["overrides", "client-overrides"]),
+            [
+                (2, "config/a.toml".to_string()),
+                (3, "options.txt".to_string())
+            ]
+        );
+        assert_eq!(paths(&["overrides/"]), [(2, "config/a.toml".to_string())]);
+    }
+}
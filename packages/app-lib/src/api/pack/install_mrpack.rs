@@ -1,26 +1,30 @@
-use crate::event::LoadingBarType;
-use crate::event::emit::{
-    emit_loading, init_or_edit_loading, loading_try_for_each_concurrent,
-};
+use crate::event::emit::loading_try_for_each_concurrent;
 use crate::pack::install_from::{
-    EnvType, PackFile, PackFileHash, set_profile_information,
+    EnvType, PackFile, PackFileHash, PackOverride, PackZipReader,
+    extract_pack_overrides, finish_pack_install, get_pack_overrides,
+    read_pack_zip, setup_pack_profile,
 };
 use crate::state::{
-    CacheBehaviour, CachedEntry, ProfileInstallStage, SideType, cache_file_hash,
+    CacheBehaviour, CachedEntry, ContentProvider, ProfileInstallStage,
+    SideType, cache_file_hash, content_store,
 };
-use crate::util::fetch::{fetch_mirrors, write};
+use crate::util::fetch::fetch_mirrors;
 use crate::util::io;
 use crate::{State, profile};
-use async_zip::base::read::seek::ZipFileReader;
 use futures::StreamExt;
-use path_util::SafeRelativeUtf8UnixPathBuf;
 
+use super::install_curseforge::{
+    emit_manual_downloads_warning, install_zipped_curseforge_pack,
+};
 use super::install_from::{
     CreatePack, CreatePackLocation, PackFormat, generate_pack_from_file,
     generate_pack_from_version_id,
 };
 use crate::data::ProjectType;
-use std::io::{Cursor, ErrorKind};
+use std::io::ErrorKind;
+
+/// Folders of a Modrinth modpack zip whose files are copied into the profile
+const MRPACK_OVERRIDE_DIRS: &[&str] = &["overrides", "client-overrides"];
 
 /// Install a pack
 /// Wrapper around install_pack_files that generates a pack creation description, and
@@ -51,6 +55,15 @@ pub async fn install_zipped_mrpack(
         CreatePackLocation::FromFile { path } => {
             generate_pack_from_file(path, profile_path.clone()).await?
         }
+        CreatePackLocation::FromCurseForgeId { .. }
+        | CreatePackLocation::FromCurseForgeZip { .. } => {
+            let install =
+                install_zipped_curseforge_pack(location, profile_path).await?;
+
+            emit_manual_downloads_warning(&install.manual_downloads).await?;
+
+            return Ok(install.profile_path);
+        }
     };
 
     // Install pack files, and if it fails, fail safely by removing the profile
@@ -75,71 +88,23 @@ pub async fn install_zipped_mrpack_files(
     let state = &State::get().await?;
 
     let file = create_pack.file;
-    let description = create_pack.description.clone(); // make a copy for profile edit function
-    let icon = create_pack.description.icon;
-    let project_id = create_pack.description.project_id;
-    let version_id = create_pack.description.version_id;
-    let existing_loading_bar = create_pack.description.existing_loading_bar;
-    let profile_path = create_pack.description.profile_path;
-    let icon_exists = icon.is_some();
-
-    let reader: Cursor<&bytes::Bytes> = Cursor::new(&file);
-
-    // Create zip reader around file
-    let mut zip_reader =
-        ZipFileReader::with_tokio(reader).await.map_err(|_| {
-            crate::Error::from(crate::ErrorKind::InputError(
-                "Failed to read input modpack zip".to_string(),
-            ))
-        })?;
-
-    // Extract index of modrinth.index.json
-    let Some(manifest_idx) = zip_reader.file().entries().iter().position(|f| {
-        matches!(f.filename().as_str(), Ok("modrinth.index.json"))
-    }) else {
-        return Err(crate::Error::from(crate::ErrorKind::InputError(
-            "No pack manifest found in mrpack".to_string(),
-        )));
-    };
-
-    let mut manifest = String::new();
-    let mut reader = zip_reader.reader_with_entry(manifest_idx).await?;
-    reader.read_to_string_checked(&mut manifest).await?;
+    let mut description = create_pack.description;
+    let profile_path = description.profile_path.clone();
 
-    let pack: PackFormat = serde_json::from_str(&manifest)?;
-
-    if &*pack.game != "minecraft" {
-        return Err(crate::ErrorKind::InputError(
-            "Pack does not support Minecraft".to_string(),
-        )
-        .into());
-    }
+    let (mut zip_reader, pack) = read_mrpack(&file).await?;
+    let overrides = get_pack_overrides(&zip_reader, MRPACK_OVERRIDE_DIRS)?;
 
-    // Sets generated profile attributes to the pack ones (using profile::edit)
-    set_profile_information(
-        profile_path.clone(),
-        &description,
+    let pack_version = description.version_id.clone();
+    let loading_bar = setup_pack_profile(
+        &mut description,
         &pack.name,
         &pack.dependencies,
+        get_pack_paths(&pack, &overrides),
+        pack_version,
         ignore_lock,
     )
     .await?;
 
-    let profile_path = profile_path.clone();
-    let loading_bar = init_or_edit_loading(
-        existing_loading_bar,
-        LoadingBarType::PackDownload {
-            profile_path: profile_path.clone(),
-            pack_name: pack.name.clone(),
-            icon,
-            pack_id: project_id,
-            pack_version: version_id,
-        },
-        100.0,
-        "Downloading modpack",
-    )
-    .await?;
-
     let num_files = pack.files.len();
     loading_try_for_each_concurrent(
         futures::stream::iter(pack.files.into_iter())
@@ -177,17 +142,25 @@ pub async fn install_zipped_mrpack_files(
                     .await?
                     .join(project.path.as_str());
 
+                let hash =
+                    project.hashes.get(&PackFileHash::Sha1).map(|x| &**x);
                 cache_file_hash(
                     file.clone(),
                     &profile_path,
                     project.path.as_str(),
-                    project.hashes.get(&PackFileHash::Sha1).map(|x| &**x),
+                    hash,
                     ProjectType::get_from_parent_folder(&path),
                     &state.pool,
                 )
                 .await?;
 
-                write(&path, &file, &state.io_semaphore).await?;
+                content_store::link_bytes(
+                    file,
+                    hash,
+                    &path,
+                    &state.io_semaphore,
+                )
+                .await?;
 
                 Ok(())
             }
@@ -195,93 +168,60 @@ pub async fn install_zipped_mrpack_files(
     )
     .await?;
 
-    emit_loading(&loading_bar, 0.0, Some("Extracting overrides"))?;
+    extract_pack_overrides(
+        &mut zip_reader,
+        overrides,
+        &profile_path,
+        &loading_bar,
+    )
+    .await?;
 
-    let override_file_entries = zip_reader
-        .file()
-        .entries()
-        .iter()
-        .enumerate()
-        .filter_map(|(index, file)| {
-            let filename = file.filename().as_str().unwrap_or_default();
-            ((filename.starts_with("overrides/")
-                || filename.starts_with("client-overrides/"))
-                && !filename.ends_with('/'))
-            .then(|| (index, file.clone()))
-        })
-        .collect::<Vec<_>>();
-    let override_file_entries_count = override_file_entries.len();
-
-    for (i, (index, file)) in override_file_entries.into_iter().enumerate() {
-        let relative_override_file_path =
-            SafeRelativeUtf8UnixPathBuf::try_from(
-                file.filename().as_str().unwrap().to_string(),
-            )?;
-        let relative_override_file_path = relative_override_file_path
-            .strip_prefix("overrides")
-            .or_else(|_| relative_override_file_path.strip_prefix("client-overrides"))
-            .map_err(|_| {
-                crate::Error::from(crate::ErrorKind::OtherError(
-                    format!("Failed to strip override prefix from override file path: {relative_override_file_path}")
-                ))
-            })?;
-
-        let mut file_bytes = vec![];
-        let mut reader = zip_reader.reader_with_entry(index).await?;
-        reader.read_to_end_checked(&mut file_bytes).await?;
-
-        let file_bytes = bytes::Bytes::from(file_bytes);
-
-        cache_file_hash(
-            file_bytes.clone(),
-            &profile_path,
-            relative_override_file_path.as_str(),
-            None,
-            ProjectType::get_from_parent_folder(
-                relative_override_file_path.as_str(),
-            ),
-            &state.pool,
-        )
-        .await?;
-
-        write(
-            &profile::get_full_path(&profile_path)
-                .await?
-                .join(relative_override_file_path.as_str()),
-            &file_bytes,
-            &state.io_semaphore,
-        )
-        .await?;
-
-        emit_loading(
-            &loading_bar,
-            30.0 / override_file_entries_count as f64,
-            Some(&format!(
-                "Extracting override {}/{override_file_entries_count}",
-                i + 1
-            )),
-        )?;
-    }
+    finish_pack_install(&description, loading_bar).await?;
 
-    // If the icon doesn't exist, we expect icon.png to be a potential icon.
-    // If it doesn't exist, and an override to icon.png exists, cache and use that
-    let potential_icon = profile::get_full_path(&profile_path)
-        .await?
-        .join("icon.png");
-    if !icon_exists && potential_icon.exists() {
-        profile::edit_icon(&profile_path, Some(&potential_icon)).await?;
-    }
+    Ok::<String, crate::Error>(profile_path)
+}
+
+/// Opens a Modrinth modpack zip and reads its index
+async fn read_mrpack(
+    mrpack_file: &bytes::Bytes,
+) -> crate::Result<(PackZipReader<'_>, PackFormat)> {
+    let (zip_reader, pack) = read_pack_zip::<PackFormat>(
+        mrpack_file,
+        "modrinth.index.json",
+        "mrpack",
+    )
+    .await?;
 
-    if let Some(profile_val) = profile::get(&profile_path).await? {
-        crate::launcher::install_minecraft(
-            &profile_val,
-            Some(loading_bar),
-            false,
+    if &*pack.game != "minecraft" {
+        return Err(crate::ErrorKind::InputError(
+            "Pack does not support Minecraft".to_string(),
         )
-        .await?;
+        .into());
     }
 
-    Ok::<String, crate::Error>(profile_path.clone())
+    Ok((zip_reader, pack))
+}
+
+/// The paths a Modrinth modpack places in a profile, of both its files and
+/// overrides
+fn get_pack_paths(
+    pack: &PackFormat,
+    overrides: &[PackOverride],
+) -> Vec<String> {
+    pack.files
+        .iter()
+        .map(|x| x.path.to_string())
+        .chain(overrides.iter().map(|x| x.path.clone()))
+        .collect()
+}
+
+/// Reads the paths a Modrinth modpack zip places in a profile
+pub(crate) async fn get_mrpack_paths(
+    mrpack_file: &bytes::Bytes,
+) -> crate::Result<Vec<String>> {
+    let (zip_reader, pack) = read_mrpack(mrpack_file).await?;
+    let overrides = get_pack_overrides(&zip_reader, MRPACK_OVERRIDE_DIRS)?;
+    Ok(get_pack_paths(&pack, &overrides))
 }
 
 #[tracing::instrument(skip(mrpack_file))]
@@ -290,38 +230,7 @@ pub async fn remove_all_related_files(
     profile_path: String,
     mrpack_file: bytes::Bytes,
 ) -> crate::Result<()> {
-    let reader: Cursor<&bytes::Bytes> = Cursor::new(&mrpack_file);
-
-    // Create zip reader around file
-    let mut zip_reader =
-        ZipFileReader::with_tokio(reader).await.map_err(|_| {
-            crate::Error::from(crate::ErrorKind::InputError(
-                "Failed to read input modpack zip".to_string(),
-            ))
-        })?;
-
-    // Extract index of modrinth.index.json
-    let Some(manifest_idx) = zip_reader.file().entries().iter().position(|f| {
-        matches!(f.filename().as_str(), Ok("modrinth.index.json"))
-    }) else {
-        return Err(crate::Error::from(crate::ErrorKind::InputError(
-            "No pack manifest found in mrpack".to_string(),
-        )));
-    };
-
-    let mut manifest = String::new();
-
-    let mut reader = zip_reader.reader_with_entry(manifest_idx).await?;
-    reader.read_to_string_checked(&mut manifest).await?;
-
-    let pack: PackFormat = serde_json::from_str(&manifest)?;
-
-    if &*pack.game != "minecraft" {
-        return Err(crate::ErrorKind::InputError(
-            "Pack does not support Minecraft".to_string(),
-        )
-        .into());
-    }
+    let (zip_reader, pack) = read_mrpack(&mrpack_file).await?;
 
     // Set install stage to installing, and do not change it back (as files are being removed and are not being reinstalled here)
     crate::api::profile::edit(&profile_path, |prof| {
@@ -368,6 +277,7 @@ pub async fn remove_all_related_files(
         .await?
     {
         if let Some(metadata) = &project.metadata
+            && metadata.provider == ContentProvider::Modrinth
             && to_remove.contains(&metadata.project_id)
         {
             match io::remove_file(profile_full_path.join(file_path)).await {
@@ -390,36 +300,9 @@ pub async fn remove_all_related_files(
     }
 
     // Iterate over each 'overrides' file and remove it
-    let override_file_entries =
-        zip_reader.file().entries().iter().filter(|file| {
-            let filename = file.filename().as_str().unwrap_or_default();
-            (filename.starts_with("overrides/")
-                || filename.starts_with("client-overrides/"))
-                && !filename.ends_with('/')
-        });
-
-    for file in override_file_entries {
-        let relative_override_file_path =
-            SafeRelativeUtf8UnixPathBuf::try_from(
-                file.filename().as_str().unwrap().to_string(),
-            )?;
-        let relative_override_file_path = relative_override_file_path
-            .strip_prefix("overrides")
-            .or_else(|_| relative_override_file_path.strip_prefix("client-overrides"))
-            .map_err(|_| {
-                crate::Error::from(crate::ErrorKind::OtherError(
-                    format!("Failed to strip override prefix from override file path: {relative_override_file_path}")
-                ))
-            })?;
-
+    for file in get_pack_overrides(&zip_reader, MRPACK_OVERRIDE_DIRS)? {
         // Remove this file if a corresponding one exists in the filesystem
-        match io::remove_file(
-            profile::get_full_path(&profile_path)
-                .await?
-                .join(relative_override_file_path.as_str()),
-        )
-        .await
-        {
+        match io::remove_file(profile_full_path.join(&file.path)).await {
             Ok(_) => (),
             Err(err) if err.kind() == ErrorKind::NotFound => (),
             Err(err) => return Err(err.into()),
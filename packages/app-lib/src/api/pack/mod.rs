@@ -1,3 +1,4 @@
 pub mod import;
+pub mod install_curseforge;
 pub mod install_from;
 pub mod install_mrpack;
@@ -1,12 +1,21 @@
 //! Theseus process management interface
 
-use crate::state::ProcessMetadata;
+use crate::state::game_sessions;
+use crate::state::{
+    DEFAULT_STOP_TIMEOUT, GameStatus, ProcessMetadata, StopMethod,
+};
 pub use crate::{
     State,
     state::{Hooks, MemorySettings, Profile, Settings, WindowSize},
 };
+use chrono::{DateTime, Utc};
+use std::time::Duration;
 use uuid::Uuid;
 
+pub use crate::state::game_sessions::{
+    DailySessionStats, GameSession, ProfileSessionStats,
+};
+
 // Gets the Profile paths of each *running* stored process in the state
 #[tracing::instrument]
 pub async fn get_all() -> crate::Result<Vec<ProcessMetadata>> {
@@ -30,6 +39,14 @@ pub async fn get_by_profile_path(
     Ok(processes)
 }
 
+// Gets what the game running in a process last reported about itself, such
+// as the world or server being played and the loaded mods
+#[tracing::instrument]
+pub async fn get_game_status(uuid: Uuid) -> crate::Result<Option<GameStatus>> {
+    let state = State::get().await?;
+    Ok(state.process_manager.get_game_status(uuid))
+}
+
 // Kill a child process stored in the state by UUID, as a string
 #[tracing::instrument]
 pub async fn kill(uuid: Uuid) -> crate::Result<()> {
@@ -39,6 +56,20 @@ pub async fn kill(uuid: Uuid) -> crate::Result<()> {
     Ok(())
 }
 
+// Ask a child process stored in the state to quit cleanly, killing it if it
+// is still running after the timeout, 30 seconds by default
+#[tracing::instrument]
+pub async fn stop(
+    uuid: Uuid,
+    timeout: Option<Duration>,
+) -> crate::Result<StopMethod> {
+    let state = State::get().await?;
+    state
+        .process_manager
+        .stop(uuid, timeout.unwrap_or(DEFAULT_STOP_TIMEOUT))
+        .await
+}
+
 // Wait for a child process stored in the state by UUID
 #[tracing::instrument]
 pub async fn wait_for(uuid: Uuid) -> crate::Result<()> {
@@ -47,3 +78,32 @@ pub async fn wait_for(uuid: Uuid) -> crate::Result<()> {
 
     Ok(())
 }
+
+// Gets the newest finished sessions of a profile, or of all profiles
+#[tracing::instrument]
+pub async fn get_sessions(
+    profile_path: Option<&str>,
+    limit: u32,
+) -> crate::Result<Vec<GameSession>> {
+    let state = State::get().await?;
+    GameSession::get_recent(profile_path, limit, &state.pool).await
+}
+
+// Gets the number of sessions, play time and crashes of each day since a
+// point in time, for a profile or for all profiles
+#[tracing::instrument]
+pub async fn get_daily_session_stats(
+    profile_path: Option<&str>,
+    since: DateTime<Utc>,
+) -> crate::Result<Vec<DailySessionStats>> {
+    let state = State::get().await?;
+    game_sessions::get_daily_stats(profile_path, since, &state.pool).await
+}
+
+// Gets the session totals of every profile, the ones that crash most first
+#[tracing::instrument]
+pub async fn get_profile_session_stats()
+-> crate::Result<Vec<ProfileSessionStats>> {
+    let state = State::get().await?;
+    game_sessions::get_profile_stats(&state.pool).await
+}
@@ -1,7 +1,9 @@
 //! Theseus profile management interface
 use crate::launcher::get_loader_version_from_profile;
 use crate::settings::Hooks;
-use crate::state::{LauncherFeatureVersion, LinkedData, ProfileInstallStage};
+use crate::state::{
+    LauncherFeatureVersion, LinkedData, ProfileInstallStage, Settings,
+};
 use crate::util::io::{self, canonicalize};
 use crate::{ErrorKind, pack, profile};
 pub use crate::{State, state::Profile};
@@ -10,6 +12,7 @@ use crate::{
     prelude::ModLoader,
 };
 use chrono::Utc;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tracing::{info, trace};
 
@@ -91,6 +94,8 @@ pub async fn profile_create(
         java_path: None,
         extra_launch_args: None,
         custom_env_vars: None,
+        game_options: Vec::new(),
+        update_policies: HashMap::new(),
         memory: None,
         force_fullscreen: None,
         game_resolution: None,
@@ -99,6 +104,8 @@ pub async fn profile_create(
             wrapper: None,
             post_exit: None,
         },
+        preferred_account: None,
+        allow_concurrent_instances: false,
     };
 
     let result = async {
@@ -124,6 +131,13 @@ pub async fn profile_create(
 
         profile.upsert(&state.pool).await?;
 
+        let settings = Settings::get(&state.pool).await?;
+        crate::launcher::set_game_options(
+            &full_path,
+            &settings.default_game_options,
+        )
+        .await?;
+
         emit_profile(&profile.path, ProfilePayloadType::Created).await?;
 
         if !skip_install_profile.unwrap_or(false) {
@@ -0,0 +1,479 @@
+//! Resolution of the dependencies of Modrinth versions added to a profile
+
+use crate::event::ProfilePayloadType;
+use crate::event::emit::emit_profile;
+use crate::state::{
+    CachedEntry, ContentProvider, DependencyType, ModLoader, Profile,
+    ProjectType, Version,
+};
+use crate::{ErrorKind, State};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Quilt ships its own replacement of Fabric API, which mods requiring
+// Fabric API load fine with
+const FABRIC_API_PROJECT_ID: &str = "P7dR8mSH";
+
+/// The versions to install for adding a version to a profile, along with
+/// every problem found while resolving its dependencies
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstallPlan {
+    pub profile_path: String,
+    /// The versions to download, starting with the requested one
+    pub versions: Vec<PlannedVersion>,
+    /// Incompatibilities between the planned versions and projects that are
+    /// installed or planned, or between the requested version and the profile
+    pub conflicts: Vec<DependencyConflict>,
+    /// Required dependencies that have no version compatible with the
+    /// profile
+    pub unresolved: Vec<UnresolvedDependency>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlannedVersion {
+    pub project_id: String,
+    pub version_id: String,
+    pub version_number: String,
+    pub file_name: Option<String>,
+    /// The project whose version requires this one, if it is not the
+    /// requested version
+    pub required_by: Option<String>,
+    /// Files of other versions of the same project, which are removed when
+    /// this version is installed
+    pub replaces: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DependencyConflict {
+    /// The project whose version declares the incompatibility
+    pub project_id: String,
+    /// `None` if the requested version itself is not made for the game
+    /// version or loader of the profile
+    pub incompatible_project_id: Option<String>,
+    /// The file of the incompatible project, if it is already installed
+    pub installed_path: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnresolvedDependency {
+    pub project_id: Option<String>,
+    pub version_id: Option<String>,
+    pub required_by: String,
+}
+
+struct InstalledProject {
+    path: String,
+    version_id: String,
+}
+
+/// Resolves the required dependencies of a version for the game version and
+/// loader of a profile, without downloading anything
+///
+/// Dependencies that are already installed are kept as they are. A
+/// dependency pinned to a version is planned with that version, otherwise
+/// the newest compatible version of the project is picked.
+#[tracing::instrument]
+pub async fn resolve_dependencies(
+    profile_path: &str,
+    version_id: &str,
+) -> crate::Result<InstallPlan> {
+    let state = State::get().await?;
+    let profile =
+        Profile::get(profile_path, &state.pool)
+            .await?
+            .ok_or_else(|| {
+                ErrorKind::UnmanagedProfileError(profile_path.to_string())
+            })?;
+
+    let mut installed: HashMap<String, Vec<InstalledProject>> = HashMap::new();
+    for (path, file) in profile
+        .get_projects(None, &state.pool, &state.fetch_semaphore)
+        .await?
+    {
+        if let Some(metadata) = file.metadata
+            && metadata.provider == ContentProvider::Modrinth
+        {
+            installed.entry(metadata.project_id).or_default().push(
+                InstalledProject {
+                    path,
+                    version_id: metadata.version_id,
+                },
+            );
+        }
+    }
+
+    let requested = get_version(version_id).await?;
+
+    let mut plan = InstallPlan {
+        profile_path: profile_path.to_string(),
+        versions: Vec::new(),
+        conflicts: Vec::new(),
+        unresolved: Vec::new(),
+    };
+    if !is_compatible_version(&requested, &profile.game_version, profile.loader)
+    {
+        plan.conflicts.push(DependencyConflict {
+            project_id: requested.project_id.clone(),
+            incompatible_project_id: None,
+            installed_path: None,
+        });
+    }
+    let mut incompatibilities = Vec::new();
+    let mut planned_projects = HashSet::new();
+    let mut queue = VecDeque::from([(requested, None::<String>)]);
+
+    while let Some((version, required_by)) = queue.pop_front() {
+        if !planned_projects.insert(version.project_id.clone()) {
+            continue;
+        }
+
+        plan.versions.push(PlannedVersion {
+            project_id: version.project_id.clone(),
+            version_id: version.id.clone(),
+            version_number: version.version_number.clone(),
+            file_name: primary_file_name(&version),
+            required_by,
+            replaces: installed
+                .get(&version.project_id)
+                .into_iter()
+                .flatten()
+                .filter(|x| x.version_id != version.id)
+                .map(|x| x.path.clone())
+                .collect(),
+        });
+
+        for dependency in &version.dependencies {
+            match dependency.dependency_type {
+                DependencyType::Required => {
+                    if dependency.project_id.as_deref()
+                        == Some(FABRIC_API_PROJECT_ID)
+                        && profile.loader == ModLoader::Quilt
+                    {
+                        continue;
+                    }
+
+                    if let Some(project_id) = &dependency.project_id
+                        && (installed.contains_key(project_id)
+                            || planned_projects.contains(project_id))
+                    {
+                        continue;
+                    }
+
+                    // A pinned version made for another game version or
+                    // loader is left unresolved
+                    let resolved = if let Some(version_id) =
+                        &dependency.version_id
+                    {
+                        pick_compatible_version(
+                            vec![get_version(version_id).await?],
+                            &profile.game_version,
+                            profile.loader,
+                        )
+                    } else if let Some(project_id) = &dependency.project_id {
+                        get_newest_compatible_version(
+                            project_id,
+                            &profile.game_version,
+                            profile.loader,
+                        )
+                        .await?
+                    } else {
+                        None
+                    };
+
+                    match resolved {
+                        Some(resolved)
+                            if !installed
+                                .contains_key(&resolved.project_id) =>
+                        {
+                            queue.push_back((
+                                resolved,
+                                Some(version.project_id.clone()),
+                            ));
+                        }
+                        Some(_) => {}
+                        None => plan.unresolved.push(UnresolvedDependency {
+                            project_id: dependency.project_id.clone(),
+                            version_id: dependency.version_id.clone(),
+                            required_by: version.project_id.clone(),
+                        }),
+                    }
+                }
+                DependencyType::Incompatible => {
+                    let incompatible_project_id = match (
+                        &dependency.project_id,
+                        &dependency.version_id,
+                    ) {
+                        (Some(project_id), _) => project_id.clone(),
+                        (None, Some(version_id)) => {
+                            get_version(version_id).await?.project_id
+                        }
+                        (None, None) => continue,
+                    };
+
+                    plan.conflicts.extend(
+                        installed
+                            .get(&incompatible_project_id)
+                            .into_iter()
+                            .flatten()
+                            .map(|x| DependencyConflict {
+                                project_id: version.project_id.clone(),
+                                incompatible_project_id: Some(
+                                    incompatible_project_id.clone(),
+                                ),
+                                installed_path: Some(x.path.clone()),
+                            }),
+                    );
+                    incompatibilities.push((
+                        version.project_id.clone(),
+                        incompatible_project_id,
+                    ));
+                }
+                DependencyType::Optional | DependencyType::Embedded => {}
+            }
+        }
+    }
+
+    // Planned versions may be incompatible with each other, regardless of
+    // the order they were planned in
+    for (project_id, incompatible_project_id) in incompatibilities {
+        if planned_projects.contains(&incompatible_project_id) {
+            plan.conflicts.push(DependencyConflict {
+                project_id,
+                incompatible_project_id: Some(incompatible_project_id),
+                installed_path: None,
+            });
+        }
+    }
+
+    // Installed versions may declare themselves incompatible with a planned
+    // project as well
+    let installed_version_ids = installed
+        .values()
+        .flatten()
+        .map(|x| &*x.version_id)
+        .collect::<Vec<_>>();
+    for installed_version in CachedEntry::get_version_many(
+        &installed_version_ids,
+        None,
+        &state.pool,
+        &state.fetch_semaphore,
+    )
+    .await?
+    {
+        for dependency in &installed_version.dependencies {
+            if let DependencyType::Incompatible = dependency.dependency_type
+                && let Some(project_id) = &dependency.project_id
+                && planned_projects.contains(project_id)
+            {
+                plan.conflicts.push(DependencyConflict {
+                    project_id: installed_version.project_id.clone(),
+                    incompatible_project_id: Some(project_id.clone()),
+                    installed_path: installed
+                        .get(&installed_version.project_id)
+                        .and_then(|x| {
+                            x.iter()
+                                .find(|x| x.version_id == installed_version.id)
+                        })
+                        .map(|x| x.path.clone()),
+                });
+            }
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Installs the versions of a plan returned by [`resolve_dependencies`],
+/// replacing the files of other versions of the same projects
+///
+/// Returns the paths of the installed files, relative to the profile
+#[tracing::instrument(skip(plan))]
+pub async fn install_plan(plan: &InstallPlan) -> crate::Result<Vec<String>> {
+    let state = State::get().await?;
+
+    let mut paths = Vec::new();
+    for version in &plan.versions {
+        // Added first, so a failed download keeps the replaced version
+        let path = Profile::add_project_version(
+            &plan.profile_path,
+            &version.version_id,
+            &state.pool,
+            &state.fetch_semaphore,
+            &state.io_semaphore,
+        )
+        .await?;
+
+        for replaced in &version.replaces {
+            if *replaced != path {
+                Profile::remove_project(&plan.profile_path, replaced).await?;
+            }
+        }
+
+        paths.push(path);
+    }
+
+    emit_profile(&plan.profile_path, ProfilePayloadType::Edited).await?;
+
+    Ok(paths)
+}
+
+async fn get_version(version_id: &str) -> crate::Result<Version> {
+    let state = State::get().await?;
+    CachedEntry::get_version(
+        version_id,
+        None,
+        &state.pool,
+        &state.fetch_semaphore,
+    )
+    .await?
+    .ok_or_else(|| {
+        ErrorKind::InputError(format!("Unknown version {version_id}")).into()
+    })
+}
+
+fn primary_file_name(version: &Version) -> Option<String> {
+    version
+        .files
+        .iter()
+        .find(|x| x.primary)
+        .or_else(|| version.files.first())
+        .map(|x| x.filename.clone())
+}
+
+async fn get_newest_compatible_version(
+    project_id: &str,
+    game_version: &str,
+    loader: ModLoader,
+) -> crate::Result<Option<Version>> {
+    let state = State::get().await?;
+    let Some(project) = CachedEntry::get_project(
+        project_id,
+        None,
+        &state.pool,
+        &state.fetch_semaphore,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let versions = CachedEntry::get_version_many(
+        &project.versions.iter().map(|x| &**x).collect::<Vec<_>>(),
+        None,
+        &state.pool,
+        &state.fetch_semaphore,
+    )
+    .await?;
+
+    Ok(pick_compatible_version(versions, game_version, loader))
+}
+
+/// Picks the newest version made for the game version, preferring versions
+/// made for the loader itself over those it can load otherwise
+fn pick_compatible_version(
+    mut versions: Vec<Version>,
+    game_version: &str,
+    loader: ModLoader,
+) -> Option<Version> {
+    versions.sort_by(|a, b| b.date_published.cmp(&a.date_published));
+    versions.retain(|x| x.game_versions.iter().any(|x| x == game_version));
+
+    let has_loader = |version: &Version, loader: &str| {
+        version.loaders.iter().any(|x| x == loader)
+    };
+    let is_mod = |version: &Version| {
+        ProjectType::get_from_loaders(version.loaders.clone())
+            == Some(ProjectType::Mod)
+    };
+
+    if let Some(index) = versions
+        .iter()
+        .position(|x| !is_mod(x) || has_loader(x, loader.as_str()))
+    {
+        return Some(versions.swap_remove(index));
+    }
+
+    versions.into_iter().find(|x| {
+        has_loader(x, "datapack")
+            || (loader == ModLoader::Quilt && has_loader(x, "fabric"))
+    })
+}
+
+/// Whether a version can be installed on a profile with the game version
+/// and loader
+fn is_compatible_version(
+    version: &Version,
+    game_version: &str,
+    loader: ModLoader,
+) -> bool {
+    pick_compatible_version(vec![version.clone()], game_version, loader)
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn version(id: &str, day: u32, loaders: &[&str]) -> Version {
+        Version {
+            id: id.to_string(),
+            project_id: "project".to_string(),
+            author_id: String::new(),
+            featured: false,
+            name: id.to_string(),
+            version_number: id.to_string(),
+            changelog: String::new(),
+            changelog_url: None,
+            date_published: Utc
+                .with_ymd_and_hms(2024, 1, day, 0, 0, 0)
+                .unwrap(),
+            downloads: 0,
+            version_type: "release".to_string(),
+            files: Vec::new(),
+            dependencies: Vec::new(),
+            game_versions: vec!["1.20.1".to_string()],
+            loaders: loaders.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_pick_compatible_version() {
+        let versions = vec![
+            version("forge", 3, &["forge"]),
+            version("fabric-old", 1, &["fabric"]),
+            version("fabric", 2, &["fabric"]),
+        ];
+
+        let pick = |loader| {
+            pick_compatible_version(versions.clone(), "1.20.1", loader)
+                .map(|x| x.id)
+        };
+        assert_eq!(pick(ModLoader::Fabric).as_deref(), Some("fabric"));
+        assert_eq!(pick(ModLoader::Forge).as_deref(), Some("forge"));
+        assert_eq!(pick(ModLoader::Quilt).as_deref(), Some("fabric"));
+        assert_eq!(pick(ModLoader::NeoForge), None);
+        assert_eq!(
+            pick_compatible_version(versions, "1.19.2", ModLoader::Fabric)
+                .map(|x| x.id),
+            None
+        );
+    }
+
+    #[test]
+    fn test_requested_version_compatibility() {
+        let fabric = version("fabric", 1, &["fabric"]);
+        let datapack = version("datapack", 1, &["datapack"]);
+
+        assert!(is_compatible_version(&fabric, "1.20.1", ModLoader::Fabric));
+        assert!(is_compatible_version(&fabric, "1.20.1", ModLoader::Quilt));
+        assert!(!is_compatible_version(&fabric, "1.20.1", ModLoader::Forge));
+        assert!(!is_compatible_version(&fabric, "1.19.2", ModLoader::Fabric));
+        assert!(is_compatible_version(&datapack, "1.20.1", ModLoader::Forge));
+        assert!(!is_compatible_version(
+            &datapack,
+            "1.19.2",
+            ModLoader::Forge
+        ));
+    }
+}
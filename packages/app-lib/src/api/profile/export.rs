@@ -0,0 +1,1166 @@
+//! Exporters for formats other launchers can import, and for dedicated
+//! server packs
+//!
+//! These are the counterparts of the importers in `pack::import`. The
+//! Modrinth format is exported by [`super::export_mrpack`].
+
+use crate::State;
+use crate::api::curseforge::{compute_fingerprint, get_fingerprints_cached};
+use crate::data::Settings;
+use crate::event::LoadingBarType;
+use crate::event::emit::{emit_loading, init_loading};
+use crate::launcher::download::download_version_info;
+use crate::launcher::get_loader_version_from_profile;
+use crate::pack::install_curseforge::{
+    CurseForgeManifest, CurseForgeManifestFile, CurseForgeManifestMinecraft,
+    CurseForgeManifestModLoader,
+};
+use crate::pack::install_from::{EnvType, PackFormat};
+use crate::prelude::ModLoader;
+use crate::profile::{
+    add_all_recursive_folder_paths, get, get_full_path, pack_get_relative_path,
+};
+use crate::state::{
+    CacheBehaviour, CachedEntry, ContentProvider, Profile, SideType,
+};
+use crate::util::fetch::{self, fetch, fetch_json};
+use crate::util::io::{self, IOError};
+use async_zip::base::read::seek::ZipFileReader;
+use async_zip::tokio::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use daedalus as d;
+use path_util::SafeRelativeUtf8UnixPathBuf;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio_util::compat::FuturesAsyncWriteCompatExt;
+
+/// Exports the profile to a CurseForge modpack zip (`manifest.json` and
+/// `overrides`)
+///
+/// Content files are referenced by their CurseForge project and file IDs when
+/// CurseForge knows their fingerprint, everything else is added as an override.
+#[tracing::instrument(skip_all)]
+pub async fn export_curseforge(
+    profile_path: &str,
+    export_path: PathBuf,
+    included_export_candidates: Vec<String>, // which folders/files to include in the export
+    version: Option<String>,
+    author: Option<String>,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    let profile = get_profile(profile_path).await?;
+    let profile_base_path = get_full_path(profile_path).await?;
+
+    // Match every included content file against CurseForge fingerprints
+    let mut fingerprints = HashMap::new();
+    for (path, _) in profile
+        .get_projects(
+            Some(CacheBehaviour::MustRevalidate),
+            &state.pool,
+            &state.api_semaphore,
+        )
+        .await?
+    {
+        if !is_included(&path, &included_export_candidates) {
+            continue;
+        }
+        let data = io::read(profile_base_path.join(&path)).await?;
+        fingerprints.insert(path, compute_fingerprint(&data) as i64);
+    }
+
+    let matches = get_fingerprints_cached(
+        &fingerprints.values().copied().collect::<Vec<_>>(),
+        None,
+    )
+    .await?
+    .into_iter()
+    .map(|x| (x.fingerprint, x))
+    .collect::<HashMap<_, _>>();
+
+    let mut files = Vec::new();
+    let mut skipped_paths = HashSet::new();
+    for (path, fingerprint) in fingerprints {
+        if let Some(file) = matches.get(&fingerprint) {
+            files.push(CurseForgeManifestFile {
+                project_id: file.mod_id,
+                file_id: file.file_id,
+                required: !path.ends_with(".disabled"),
+            });
+            skipped_paths.insert(path);
+        }
+    }
+
+    let mod_loaders = loader_version_for_export(&profile)
+        .map(|version| CurseForgeManifestModLoader {
+            id: format!("{}-{version}", profile.loader.as_str()),
+            primary: true,
+        })
+        .into_iter()
+        .collect();
+
+    let manifest = CurseForgeManifest {
+        minecraft: CurseForgeManifestMinecraft {
+            version: profile.game_version.clone(),
+            mod_loaders,
+        },
+        manifest_type: "minecraftModpack".to_string(),
+        manifest_version: 1,
+        name: profile.name.clone(),
+        version: Some(version.unwrap_or_else(|| "1.0.0".to_string())),
+        author: Some(author.unwrap_or_default()),
+        files,
+        overrides: "overrides".to_string(),
+    };
+
+    let mut writer = create_zip(&export_path).await?;
+    write_zip_entry(
+        &mut writer,
+        "manifest.json",
+        &serde_json::to_vec_pretty(&manifest)?,
+    )
+    .await?;
+    write_profile_files(
+        &mut writer,
+        &profile,
+        &profile_base_path,
+        Some(&included_export_candidates),
+        &skipped_paths,
+        "overrides/",
+        "Exporting profile to CurseForge modpack",
+    )
+    .await?;
+    writer.close().await?;
+
+    Ok(())
+}
+
+/// Exports the profile to a MultiMC/Prism Launcher instance zip
+/// (`instance.cfg`, `mmc-pack.json` and the `.minecraft` folder)
+#[tracing::instrument(skip_all)]
+pub async fn export_mmc(
+    profile_path: &str,
+    export_path: PathBuf,
+    included_export_candidates: Vec<String>, // which folders/files to include in the export
+) -> crate::Result<()> {
+    let profile = get_profile(profile_path).await?;
+    let profile_base_path = get_full_path(profile_path).await?;
+
+    let mut instance_cfg = vec![
+        "[General]".to_string(),
+        "ConfigVersion=1.2".to_string(),
+        "InstanceType=OneSix".to_string(),
+        "iconKey=default".to_string(),
+        format!("name={}", profile.name),
+    ];
+    if let Some(java_args) = &profile.extra_launch_args {
+        instance_cfg.push(format!("JvmArgs={}", java_args.join(" ")));
+        instance_cfg.push("OverrideJavaArgs=true".to_string());
+    }
+    if let Some(memory) = &profile.memory {
+        instance_cfg.push(format!("MaxMemAlloc={}", memory.maximum));
+        instance_cfg.push("OverrideMemory=true".to_string());
+    }
+    let instance_cfg = instance_cfg.join("\n") + "\n";
+
+    let mut components = vec![serde_json::json!({
+        "uid": "net.minecraft",
+        "version": profile.game_version,
+        "important": true,
+    })];
+    if let Some(version) = loader_version_for_export(&profile) {
+        let uid = match profile.loader {
+            ModLoader::Forge => "net.minecraftforge",
+            ModLoader::NeoForge => "net.neoforged",
+            ModLoader::Fabric => "net.fabricmc.fabric-loader",
+            ModLoader::Quilt => "org.quiltmc.quilt-loader",
+            ModLoader::Vanilla => {
+                return Err(crate::ErrorKind::InputError(format!(
+                    "Profile {} has a loader version but no mod loader",
+                    profile.path
+                ))
+                .into());
+            }
+        };
+        components.push(serde_json::json!({
+            "uid": uid,
+            "version": version,
+        }));
+    }
+    let mmc_pack = serde_json::json!({
+        "components": components,
+        "formatVersion": 1,
+    });
+
+    let mut writer = create_zip(&export_path).await?;
+    write_zip_entry(&mut writer, "instance.cfg", instance_cfg.as_bytes())
+        .await?;
+    write_zip_entry(
+        &mut writer,
+        "mmc-pack.json",
+        &serde_json::to_vec_pretty(&mmc_pack)?,
+    )
+    .await?;
+    write_profile_files(
+        &mut writer,
+        &profile,
+        &profile_base_path,
+        Some(&included_export_candidates),
+        &HashSet::new(),
+        ".minecraft/",
+        "Exporting profile to MultiMC instance",
+    )
+    .await?;
+    writer.close().await?;
+
+    Ok(())
+}
+
+/// Exports every file of the profile's directory to a zip, as is
+#[tracing::instrument(skip_all)]
+pub async fn export_instance_zip(
+    profile_path: &str,
+    export_path: PathBuf,
+) -> crate::Result<()> {
+    let profile = get_profile(profile_path).await?;
+    let profile_base_path = get_full_path(profile_path).await?;
+
+    let mut writer = create_zip(&export_path).await?;
+    write_profile_files(
+        &mut writer,
+        &profile,
+        &profile_base_path,
+        None,
+        &HashSet::new(),
+        "",
+        "Exporting profile to zip",
+    )
+    .await?;
+    writer.close().await?;
+
+    Ok(())
+}
+
+/// How a server pack is written
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerPackFormat {
+    /// A ready to run server directory
+    Directory,
+    /// A zip of the server directory
+    Zip,
+}
+
+/// Folders and files of a client instance that have no use on a server
+const CLIENT_ONLY_PATHS: &[&str] = &[
+    "backups",
+    "crash-reports",
+    "logs",
+    "resourcepacks",
+    "saves",
+    "screenshots",
+    "shaderpacks",
+    "command_history.txt",
+    "options.txt",
+    "realms_persistence.json",
+    "servers.dat",
+    "servers.dat_old",
+];
+
+/// What to download into a server pack, and how to start it
+#[derive(Debug, PartialEq, Eq)]
+struct ServerLaunch {
+    /// File name, URL and SHA-1 of each file to download
+    downloads: Vec<(String, String, Option<String>)>,
+    /// Arguments to `java` that install the server on the first start
+    install_args: Option<String>,
+    /// Whether the installer generates `run.sh`/`run.bat` scripts that
+    /// should be used when present
+    installer_scripts: bool,
+    /// What is started after the install
+    target: LaunchTarget,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum LaunchTarget {
+    /// A server jar started with `-jar`
+    Jar(String),
+    /// A main class started from the downloaded libraries
+    MainClass {
+        jvm_args: Vec<String>,
+        classpath: Vec<String>,
+        main_class: String,
+    },
+}
+
+impl LaunchTarget {
+    /// The arguments to `java` that start the server, with the classpath
+    /// entries joined by `separator`
+    fn java_args(&self, separator: &str) -> String {
+        match self {
+            Self::Jar(jar) => format!("-jar {jar}"),
+            Self::MainClass {
+                jvm_args,
+                classpath,
+                main_class,
+            } => jvm_args
+                .iter()
+                .cloned()
+                .chain([
+                    "-cp".to_string(),
+                    classpath.join(separator),
+                    main_class.clone(),
+                ])
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+/// Exports the profile as a dedicated server, either as a directory or as
+/// a zip of that directory
+///
+/// Mods that do not run on servers are left out, based on the environments
+/// of the linked modpack's files or else on the Modrinth project's
+/// `server_side`. The server jar and loader libraries (or the loader
+/// installer, which is run by the start scripts on the first start) are
+/// downloaded next to `start.sh` and `start.bat`.
+///
+/// Returns the relative paths of the client-only files that were left out.
+#[tracing::instrument(skip_all)]
+pub async fn export_server_pack(
+    profile_path: &str,
+    export_path: PathBuf,
+    included_export_candidates: Vec<String>, // which folders/files to include in the export
+    format: ServerPackFormat,
+) -> crate::Result<Vec<String>> {
+    let state = State::get().await?;
+    let profile = get_profile(profile_path).await?;
+    let profile_base_path = get_full_path(profile_path).await?;
+
+    if format == ServerPackFormat::Directory
+        && export_path.exists()
+        && io::read_dir(&export_path)
+            .await?
+            .next_entry()
+            .await?
+            .is_some()
+    {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Server pack directory {} is not empty",
+            export_path.display()
+        ))
+        .into());
+    }
+
+    let loading_bar = init_loading(
+        LoadingBarType::ProfileExport {
+            profile_path: profile.path.clone(),
+            profile_name: profile.name.clone(),
+        },
+        100.0,
+        "Exporting server pack",
+    )
+    .await?;
+
+    emit_loading(&loading_bar, 0.0, Some("Finding client-only content"))?;
+    let (pack_envs, server_overrides) = get_linked_pack_server_data(&profile)
+        .await?
+        .unwrap_or_default();
+
+    let projects = profile
+        .get_projects(
+            Some(CacheBehaviour::MustRevalidate),
+            &state.pool,
+            &state.api_semaphore,
+        )
+        .await?
+        .into_iter()
+        .collect::<Vec<_>>();
+    let cached_projects = CachedEntry::get_project_many(
+        &projects
+            .iter()
+            .filter_map(|(_, file)| file.metadata.as_ref())
+            .filter(|x| x.provider == ContentProvider::Modrinth)
+            .map(|x| &*x.project_id)
+            .collect::<Vec<_>>(),
+        None,
+        &state.pool,
+        &state.api_semaphore,
+    )
+    .await?;
+
+    let mut client_only = projects
+        .iter()
+        .filter(|(path, file)| {
+            if let Some(server) = pack_envs.get(path.as_str()) {
+                return *server == SideType::Unsupported;
+            }
+
+            file.metadata.as_ref().is_some_and(|metadata| {
+                metadata.provider == ContentProvider::Modrinth
+                    && cached_projects.iter().any(|x| {
+                        x.id == metadata.project_id
+                            && x.server_side == SideType::Unsupported
+                    })
+            })
+        })
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    client_only.sort();
+    emit_loading(&loading_bar, 10.0, None)?;
+
+    emit_loading(&loading_bar, 0.0, Some("Downloading server files"))?;
+    let launch = get_server_launch(&profile).await?;
+    let mut downloads = Vec::new();
+    for (name, url, sha1) in &launch.downloads {
+        let file =
+            fetch(url, sha1.as_deref(), &state.fetch_semaphore, &state.pool)
+                .await?;
+        downloads.push((name.clone(), file));
+        emit_loading(&loading_bar, 40.0 / launch.downloads.len() as f64, None)?;
+    }
+
+    let settings = Settings::get(&state.pool).await?;
+    let memory = profile.memory.unwrap_or(settings.memory).maximum;
+
+    let mut writer = match format {
+        ServerPackFormat::Directory => {
+            io::create_dir_all(&export_path).await?;
+            ServerPackWriter::Directory(export_path.clone())
+        }
+        ServerPackFormat::Zip => {
+            ServerPackWriter::Zip(create_zip(&export_path).await?)
+        }
+    };
+
+    for (name, file) in downloads {
+        writer.write(&name, &file, false).await?;
+    }
+    writer
+        .write(
+            "start.sh",
+            start_script_unix(&launch, memory).as_bytes(),
+            true,
+        )
+        .await?;
+    writer
+        .write(
+            "start.bat",
+            start_script_windows(&launch, memory).as_bytes(),
+            false,
+        )
+        .await?;
+
+    emit_loading(&loading_bar, 0.0, Some("Copying files"))?;
+    let mut path_list = Vec::new();
+    add_all_recursive_folder_paths(&profile_base_path, &mut path_list).await?;
+    let path_count = path_list.len();
+    for path in path_list {
+        emit_loading(&loading_bar, 50.0 / path_count as f64, None)?;
+
+        let relative_path = pack_get_relative_path(&profile_base_path, &path)?;
+        let relative_path = relative_path.as_str();
+        if path
+            .file_name()
+            .is_some_and(|x| x.to_string_lossy().starts_with(".DS_Store"))
+            || relative_path.ends_with(".disabled")
+            || !is_included(relative_path, &included_export_candidates)
+            || CLIENT_ONLY_PATHS.iter().any(|x| {
+                relative_path == *x
+                    || relative_path.starts_with(&format!("{x}/"))
+            })
+            || client_only.iter().any(|x| x == relative_path)
+            || server_overrides.contains_key(relative_path)
+            || !path.is_file()
+        {
+            continue;
+        }
+
+        writer.copy(relative_path, &path).await?;
+    }
+    for (relative_path, file) in server_overrides {
+        writer.write(&relative_path, &file, false).await?;
+    }
+    writer.finish().await?;
+
+    emit_loading(&loading_bar, 0.0, Some("Done exporting server pack"))?;
+
+    Ok(client_only)
+}
+
+/// Reads the server environment of each file and the `server-overrides` of
+/// the Modrinth modpack a profile is linked to
+async fn get_linked_pack_server_data(
+    profile: &Profile,
+) -> crate::Result<
+    Option<(HashMap<String, SideType>, HashMap<String, bytes::Bytes>)>,
+> {
+    let Some(linked_data) = profile
+        .linked_data
+        .as_ref()
+        .filter(|x| x.provider == ContentProvider::Modrinth)
+    else {
+        return Ok(None);
+    };
+
+    let state = State::get().await?;
+    let Some(version) = CachedEntry::get_version(
+        &linked_data.version_id,
+        None,
+        &state.pool,
+        &state.api_semaphore,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+    let Some(file) = version
+        .files
+        .iter()
+        .find(|x| x.primary)
+        .or_else(|| version.files.first())
+    else {
+        return Ok(None);
+    };
+
+    let file = fetch(
+        &file.url,
+        file.hashes.get("sha1").map(|x| &**x),
+        &state.fetch_semaphore,
+        &state.pool,
+    )
+    .await?;
+    let mut zip_reader = ZipFileReader::with_tokio(Cursor::new(&file))
+        .await
+        .map_err(|_| {
+            crate::ErrorKind::InputError(
+                "Failed to read linked modpack zip".to_string(),
+            )
+        })?;
+
+    let Some(manifest_idx) = zip_reader.file().entries().iter().position(|f| {
+        matches!(f.filename().as_str(), Ok("modrinth.index.json"))
+    }) else {
+        return Ok(None);
+    };
+    let mut manifest = String::new();
+    let mut reader = zip_reader.reader_with_entry(manifest_idx).await?;
+    reader.read_to_string_checked(&mut manifest).await?;
+    let pack: PackFormat = serde_json::from_str(&manifest)?;
+
+    let envs = pack
+        .files
+        .into_iter()
+        .filter_map(|file| {
+            let server = file.env?.remove(&EnvType::Server)?;
+            Some((file.path.to_string(), server))
+        })
+        .collect();
+
+    let override_entries = zip_reader
+        .file()
+        .entries()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, file)| {
+            let filename = file.filename().as_str().ok()?;
+            let relative_path = SafeRelativeUtf8UnixPathBuf::try_from(
+                filename.strip_prefix("server-overrides/")?.to_string(),
+            )
+            .ok()?;
+            (!filename.ends_with('/')).then_some((index, relative_path))
+        })
+        .collect::<Vec<_>>();
+    let mut overrides = HashMap::new();
+    for (index, relative_path) in override_entries {
+        let mut file_bytes = Vec::new();
+        let mut reader = zip_reader.reader_with_entry(index).await?;
+        reader.read_to_end_checked(&mut file_bytes).await?;
+        overrides.insert(relative_path.to_string(), file_bytes.into());
+    }
+
+    Ok(Some((envs, overrides)))
+}
+
+async fn get_server_launch(profile: &Profile) -> crate::Result<ServerLaunch> {
+    let state = State::get().await?;
+
+    let minecraft = crate::api::metadata::get_minecraft_versions().await?;
+    let version = minecraft
+        .versions
+        .iter()
+        .find(|x| x.id == profile.game_version)
+        .ok_or_else(|| {
+            crate::ErrorKind::LauncherError(format!(
+                "Invalid game version: {}",
+                profile.game_version
+            ))
+        })?;
+    let version_info =
+        download_version_info(&state, version, None, None, None).await?;
+    let server = version_info
+        .downloads
+        .get(&daedalus::minecraft::DownloadType::Server)
+        .ok_or_else(|| {
+            crate::ErrorKind::LauncherError(format!(
+                "No server downloads exist for version {}",
+                profile.game_version
+            ))
+        })?;
+    let server_jar = (
+        "server.jar".to_string(),
+        server.url.clone(),
+        Some(server.sha1.clone()),
+    );
+
+    let launch = match profile.loader {
+        ModLoader::Vanilla => ServerLaunch {
+            downloads: vec![server_jar],
+            install_args: None,
+            installer_scripts: false,
+            target: LaunchTarget::Jar("server.jar".to_string()),
+        },
+        loader => {
+            let loader_version = get_loader_version_from_profile(
+                &profile.game_version,
+                loader,
+                profile.loader_version.as_deref(),
+            )
+            .await?
+            .ok_or_else(|| {
+                crate::ErrorKind::LauncherError(format!(
+                    "Profile {} has no loader version",
+                    profile.path
+                ))
+            })?;
+            let partial: d::modded::PartialVersionInfo = fetch_json(
+                Method::GET,
+                &loader_version.url,
+                None,
+                None,
+                &state.api_semaphore,
+                &state.pool,
+            )
+            .await?;
+
+            match loader {
+                ModLoader::Fabric | ModLoader::Quilt => knot_server_launch(
+                    loader,
+                    partial.main_class.as_deref(),
+                    &partial.libraries,
+                    &profile.game_version,
+                    server_jar,
+                )?,
+                _ => {
+                    forge_server_launch(&partial.libraries, &loader_version.id)?
+                }
+            }
+        }
+    };
+
+    Ok(launch)
+}
+
+/// Fabric and Quilt are started from the libraries of their loader version,
+/// downloaded from the maven repositories the metadata lists, with the
+/// vanilla server jar as the game
+fn knot_server_launch(
+    loader: ModLoader,
+    main_class: Option<&str>,
+    libraries: &[d::minecraft::Library],
+    game_version: &str,
+    server_jar: (String, String, Option<String>),
+) -> crate::Result<ServerLaunch> {
+    // The metadata describes the client, whose entry point has a server
+    // counterpart in the same package
+    let main_class = main_class
+        .and_then(|x| x.strip_suffix("KnotClient"))
+        .map(|x| format!("{x}KnotServer"))
+        .ok_or_else(|| {
+            crate::ErrorKind::LauncherError(format!(
+                "No server entry point for {} on {game_version}",
+                loader.as_str()
+            ))
+        })?;
+    let game_jar_property = match loader {
+        ModLoader::Quilt => "loader.gameJarPath",
+        _ => "fabric.gameJarPath",
+    };
+
+    let mut downloads = Vec::new();
+    let mut classpath = Vec::new();
+    for library in libraries.iter().filter(|x| x.downloadable) {
+        let name = library
+            .name
+            .replace(d::modded::DUMMY_REPLACE_STRING, game_version);
+        let artifact_path = d::get_path_from_artifact(&name)?;
+        let (url, sha1) = match (&library.downloads, &library.url) {
+            (
+                Some(d::minecraft::LibraryDownloads {
+                    artifact: Some(artifact),
+                    ..
+                }),
+                _,
+            ) if !artifact.url.is_empty() => {
+                (artifact.url.clone(), Some(artifact.sha1.clone()))
+            }
+            (_, Some(maven)) => (format!("{maven}{artifact_path}"), None),
+            _ => {
+                return Err(crate::ErrorKind::LauncherError(format!(
+                    "No download for library {name}"
+                ))
+                .into());
+            }
+        };
+
+        let path = format!("libraries/{artifact_path}");
+        downloads.push((path.clone(), url, sha1));
+        classpath.push(path);
+    }
+    downloads.push(server_jar);
+
+    Ok(ServerLaunch {
+        downloads,
+        install_args: None,
+        installer_scripts: false,
+        target: LaunchTarget::MainClass {
+            jvm_args: vec![format!("-D{game_jar_property}=server.jar")],
+            classpath,
+            main_class,
+        },
+    })
+}
+
+/// The installer of a Forge or NeoForge version, which is published next to
+/// the loader's own library in the maven repository the metadata lists, and
+/// the jar it creates for versions that do not use `run.sh`/`run.bat` yet
+fn forge_server_launch(
+    libraries: &[d::minecraft::Library],
+    loader_version: &str,
+) -> crate::Result<ServerLaunch> {
+    let (maven, group, artifact, version) = libraries
+        .iter()
+        .find_map(|library| {
+            let mut coordinates = library.name.split(':');
+            let group = coordinates.next()?;
+            let artifact = coordinates.next()?;
+            let version = coordinates.next()?;
+            // Forge lists its other modules, such as `fmlcore`, under the
+            // same version, but only publishes the installer with itself
+            if !matches!(
+                (group, artifact),
+                ("net.minecraftforge", "forge")
+                    | ("net.neoforged", "forge" | "neoforge")
+            ) || !version.ends_with(loader_version)
+            {
+                return None;
+            }
+
+            let maven = library.url.clone().or_else(|| {
+                let url = &library.downloads.as_ref()?.artifact.as_ref()?.url;
+                url.strip_suffix(
+                    &d::get_path_from_artifact(&library.name).ok()?,
+                )
+                .map(|x| x.to_string())
+            })?;
+            Some((maven, group, artifact, version))
+        })
+        .ok_or_else(|| {
+            crate::ErrorKind::LauncherError(format!(
+                "No loader library found for version {loader_version}"
+            ))
+        })?;
+
+    Ok(ServerLaunch {
+        downloads: vec![(
+            "installer.jar".to_string(),
+            format!(
+                "{maven}{}/{artifact}/{version}/{artifact}-{version}-installer.jar",
+                group.replace('.', "/")
+            ),
+            None,
+        )],
+        install_args: Some("-jar installer.jar --installServer".to_string()),
+        installer_scripts: true,
+        target: LaunchTarget::Jar(format!("{artifact}-{version}.jar")),
+    })
+}
+
+fn start_script_unix(launch: &ServerLaunch, memory: u32) -> String {
+    let mut script = vec![
+        "#!/usr/bin/env sh".to_string(),
+        "cd \"$(dirname \"$0\")\" || exit 1".to_string(),
+        "JAVA=\"${JAVA:-java}\"".to_string(),
+        format!("MEMORY=\"${{MEMORY:-{memory}M}}\""),
+    ];
+    if let Some(install_args) = &launch.install_args {
+        script.extend([
+            "if [ ! -f .installed ]; then".to_string(),
+            format!("    \"$JAVA\" {install_args} || exit 1"),
+            "    touch .installed".to_string(),
+            "fi".to_string(),
+        ]);
+    }
+    if launch.installer_scripts {
+        script.extend([
+            "if [ -f run.sh ]; then".to_string(),
+            "    echo \"-Xmx$MEMORY\" > user_jvm_args.txt".to_string(),
+            "    exec sh run.sh nogui".to_string(),
+            "fi".to_string(),
+        ]);
+    }
+    script.push(format!(
+        "exec \"$JAVA\" -Xmx\"$MEMORY\" {} nogui",
+        launch.target.java_args(":")
+    ));
+
+    script.join("\n") + "\n"
+}
+
+fn start_script_windows(launch: &ServerLaunch, memory: u32) -> String {
+    let mut script = vec![
+        "@echo off".to_string(),
+        "cd /d \"%~dp0\"".to_string(),
+        "if not defined JAVA set JAVA=java".to_string(),
+        format!("if not defined MEMORY set MEMORY={memory}M"),
+    ];
+    if let Some(install_args) = &launch.install_args {
+        script.extend([
+            "if not exist .installed (".to_string(),
+            format!("    \"%JAVA%\" {install_args} || exit /b 1"),
+            "    type nul > .installed".to_string(),
+            ")".to_string(),
+        ]);
+    }
+    if launch.installer_scripts {
+        script.extend([
+            "if exist run.bat (".to_string(),
+            "    echo -Xmx%MEMORY%> user_jvm_args.txt".to_string(),
+            "    call run.bat nogui".to_string(),
+            "    exit /b".to_string(),
+            ")".to_string(),
+        ]);
+    }
+    script.push(format!(
+        "\"%JAVA%\" -Xmx%MEMORY% {} nogui",
+        launch.target.java_args(";")
+    ));
+
+    script.join("\r\n") + "\r\n"
+}
+
+enum ServerPackWriter {
+    Directory(PathBuf),
+    Zip(ZipFileWriter<File>),
+}
+
+impl ServerPackWriter {
+    async fn write(
+        &mut self,
+        relative_path: &str,
+        data: &[u8],
+        executable: bool,
+    ) -> crate::Result<()> {
+        let state = State::get().await?;
+
+        match self {
+            Self::Directory(dir) => {
+                let path = dir.join(relative_path);
+                fetch::write(&path, data, &state.io_semaphore).await?;
+
+                #[cfg(unix)]
+                if executable {
+                    use std::os::unix::fs::PermissionsExt;
+
+                    tokio::fs::set_permissions(
+                        &path,
+                        std::fs::Permissions::from_mode(0o755),
+                    )
+                    .await
+                    .map_err(|e| IOError::with_path(e, &path))?;
+                }
+            }
+            Self::Zip(writer) => {
+                let mut builder = ZipEntryBuilder::new(
+                    relative_path.to_string().into(),
+                    Compression::Deflate,
+                );
+                if executable {
+                    builder = builder.unix_permissions(0o755);
+                }
+                writer.write_entry_whole(builder, data).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn copy(
+        &mut self,
+        relative_path: &str,
+        source: &Path,
+    ) -> crate::Result<()> {
+        let state = State::get().await?;
+
+        match self {
+            Self::Directory(dir) => {
+                fetch::copy(
+                    source,
+                    dir.join(relative_path),
+                    &state.io_semaphore,
+                )
+                .await?;
+            }
+            Self::Zip(writer) => {
+                let mut stream = writer
+                    .write_entry_stream(
+                        ZipEntryBuilder::new(
+                            relative_path.to_string().into(),
+                            Compression::Deflate,
+                        )
+                        .build(),
+                    )
+                    .await?
+                    .compat_write();
+                let mut file = File::open(source)
+                    .await
+                    .map_err(|e| IOError::with_path(e, source))?;
+                tokio::io::copy(&mut file, &mut stream)
+                    .await
+                    .map_err(|e| IOError::with_path(e, source))?;
+                stream.into_inner().close().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn finish(self) -> crate::Result<()> {
+        if let Self::Zip(writer) = self {
+            writer.close().await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn get_profile(profile_path: &str) -> crate::Result<Profile> {
+    get(profile_path).await?.ok_or_else(|| {
+        crate::ErrorKind::OtherError(format!(
+            "Tried to export a nonexistent or unloaded profile at path {profile_path}!"
+        ))
+        .into()
+    })
+}
+
+/// The loader version without the game version prefix Forge versions carry
+/// in the launcher metadata, or `None` for vanilla profiles
+fn loader_version_for_export(profile: &Profile) -> Option<String> {
+    if profile.loader == ModLoader::Vanilla {
+        return None;
+    }
+
+    let version = profile.loader_version.as_deref()?;
+    Some(
+        version
+            .strip_prefix(&format!("{}-", profile.game_version))
+            .unwrap_or(version)
+            .to_string(),
+    )
+}
+
+fn is_included(
+    relative_path: &str,
+    included_export_candidates: &[String],
+) -> bool {
+    included_export_candidates
+        .iter()
+        .any(|x| relative_path.starts_with(&**x))
+}
+
+async fn create_zip(export_path: &Path) -> crate::Result<ZipFileWriter<File>> {
+    let file = File::create(export_path)
+        .await
+        .map_err(|e| IOError::with_path(e, export_path))?;
+    Ok(ZipFileWriter::with_tokio(file))
+}
+
+async fn write_zip_entry(
+    writer: &mut ZipFileWriter<File>,
+    name: &str,
+    data: &[u8],
+) -> crate::Result<()> {
+    let builder =
+        ZipEntryBuilder::new(name.to_string().into(), Compression::Deflate);
+    writer.write_entry_whole(builder, data).await?;
+    Ok(())
+}
+
+/// Streams the profile's files into the zip under `prefix`, skipping
+/// `.DS_Store` files, `skipped_paths` and, if given, anything outside of
+/// the included export candidates
+async fn write_profile_files(
+    writer: &mut ZipFileWriter<File>,
+    profile: &Profile,
+    profile_base_path: &PathBuf,
+    included_export_candidates: Option<&[String]>,
+    skipped_paths: &HashSet<String>,
+    prefix: &str,
+    message: &str,
+) -> crate::Result<()> {
+    let mut path_list = Vec::new();
+    add_all_recursive_folder_paths(profile_base_path, &mut path_list).await?;
+
+    let loading_bar = init_loading(
+        LoadingBarType::ProfileExport {
+            profile_path: profile.path.clone(),
+            profile_name: profile.name.clone(),
+        },
+        path_list.len() as f64,
+        message,
+    )
+    .await?;
+
+    for path in path_list {
+        emit_loading(&loading_bar, 1.0, None)?;
+
+        let relative_path = pack_get_relative_path(profile_base_path, &path)?;
+        if path
+            .file_name()
+            .is_some_and(|x| x.to_string_lossy().starts_with(".DS_Store"))
+            || skipped_paths.contains(relative_path.as_str())
+            || included_export_candidates
+                .is_some_and(|x| !is_included(relative_path.as_str(), x))
+            || !path.is_file()
+        {
+            continue;
+        }
+
+        let mut stream = writer
+            .write_entry_stream(
+                ZipEntryBuilder::new(
+                    format!("{prefix}{relative_path}").into(),
+                    Compression::Deflate,
+                )
+                .build(),
+            )
+            .await?
+            .compat_write();
+        let mut source = File::open(&path)
+            .await
+            .map_err(|e| IOError::with_path(e, &path))?;
+        tokio::io::copy(&mut source, &mut stream)
+            .await
+            .map_err(|e| IOError::with_path(e, &path))?;
+        stream.into_inner().close().await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(name: &str, url: &str) -> d::minecraft::Library {
+        serde_json::from_value(serde_json::json!({ "name": name, "url": url }))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_forge_installer_urls() {
+        let forge = forge_server_launch(
+            &[
+                library("org.ow2.asm:asm:9.6", "https://maven.example.com/"),
+                library(
+                    "net.minecraftforge:fmlcore:1.20.1-47.2.0",
+                    "https://maven.minecraftforge.net/",
+                ),
+                library(
+                    "net.minecraftforge:forge:1.20.1-47.2.0:universal",
+                    "https://maven.minecraftforge.net/",
+                ),
+            ],
+            "1.20.1-47.2.0",
+        )
+        .unwrap();
+        assert_eq!(
+            forge.downloads[0].1,
+            "https://maven.minecraftforge.net/net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-installer.jar"
+        );
+        assert_eq!(
+            forge.target,
+            LaunchTarget::Jar("forge-1.20.1-47.2.0.jar".to_string())
+        );
+
+        // NeoForge for 1.20.1 was still published as `net.neoforged:forge`
+        let legacy_neoforge = forge_server_launch(
+            &[library(
+                "net.neoforged:forge:1.20.1-47.1.106:universal",
+                "https://maven.neoforged.net/releases/",
+            )],
+            "47.1.106",
+        )
+        .unwrap();
+        assert_eq!(
+            legacy_neoforge.downloads[0].1,
+            "https://maven.neoforged.net/releases/net/neoforged/forge/1.20.1-47.1.106/forge-1.20.1-47.1.106-installer.jar"
+        );
+
+        assert!(forge_server_launch(&[], "21.1.77").is_err());
+    }
+
+    #[test]
+    fn test_knot_server_launch() {
+        let server_jar = (
+            "server.jar".to_string(),
+            "https://example.com/server.jar".to_string(),
+            None,
+        );
+        let launch = knot_server_launch(
+            ModLoader::Fabric,
+            Some("net.fabricmc.loader.impl.launch.knot.KnotClient"),
+            &[
+                library(
+                    "net.fabricmc:fabric-loader:0.16.9",
+                    "https://maven.fabricmc.net/",
+                ),
+                library(
+                    &format!(
+                        "net.fabricmc:intermediary:{}",
+                        d::modded::DUMMY_REPLACE_STRING
+                    ),
+                    "https://maven.fabricmc.net/",
+                ),
+            ],
+            "1.21.1",
+            server_jar.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            launch.downloads,
+            vec![
+                (
+                    "libraries/net/fabricmc/fabric-loader/0.16.9/fabric-loader-0.16.9.jar".to_string(),
+                    "https://maven.fabricmc.net/net/fabricmc/fabric-loader/0.16.9/fabric-loader-0.16.9.jar".to_string(),
+                    None
+                ),
+                (
+                    "libraries/net/fabricmc/intermediary/1.21.1/intermediary-1.21.1.jar".to_string(),
+                    "https://maven.fabricmc.net/net/fabricmc/intermediary/1.21.1/intermediary-1.21.1.jar".to_string(),
+                    None
+                ),
+                server_jar,
+            ]
+        );
+        assert_eq!(
+            launch.target.java_args(":"),
+            "-Dfabric.gameJarPath=server.jar -cp libraries/net/fabricmc/fabric-loader/0.16.9/fabric-loader-0.16.9.jar:libraries/net/fabricmc/intermediary/1.21.1/intermediary-1.21.1.jar net.fabricmc.loader.impl.launch.knot.KnotServer"
+        );
+    }
+}
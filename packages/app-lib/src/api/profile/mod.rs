@@ -8,17 +8,20 @@ use crate::pack::install_from::{
     EnvType, PackDependency, PackFile, PackFileHash, PackFormat,
 };
 use crate::state::{
-    CacheBehaviour, CachedEntry, Credentials, JavaVersion, ProcessMetadata,
-    ProfileFile, ProfileInstallStage, ProjectType, SideType,
+    CacheBehaviour, CachedEntry, ContentProvider, Credentials, JavaVersion,
+    ProcessMetadata, ProfileFile, ProfileInstallStage, ProjectType, SideType,
+    StopMethod, UpdatePolicy,
 };
 
 use crate::event::{ProfilePayloadType, emit::emit_profile};
+pub use crate::launcher::verify::VerificationSummary;
 use crate::util::fetch;
 use crate::util::io::{self, IOError};
 pub use crate::{State, state::Profile};
 use async_zip::tokio::write::ZipFileWriter;
 use async_zip::{Compression, ZipEntryBuilder};
 use path_util::SafeRelativeUtf8UnixPathBuf;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use std::collections::{HashMap, HashSet};
@@ -27,15 +30,20 @@ use crate::data::Settings;
 use crate::server_address::ServerAddress;
 use dashmap::DashMap;
 use std::iter::FromIterator;
+use std::time::Duration;
 use std::{
     future::Future,
     path::{Path, PathBuf},
     sync::Arc,
 };
 use tokio::io::AsyncReadExt;
-use tokio::{fs::File, process::Command, sync::RwLock};
+use tokio::{fs::File, sync::RwLock};
+use uuid::Uuid;
 
 pub mod create;
+pub mod dependencies;
+pub mod export;
+pub mod rollback;
 pub mod update;
 
 #[derive(Debug, Clone)]
@@ -253,6 +261,24 @@ pub async fn install(path: &str, force: bool) -> crate::Result<()> {
     Ok(())
 }
 
+/// Verifies the game files of an installed profile against their known
+/// hashes, and downloads again only the missing or corrupted ones
+#[tracing::instrument]
+pub async fn verify_profile(path: &str) -> crate::Result<VerificationSummary> {
+    let profile = get(path).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(path.to_string()).as_error()
+    })?;
+
+    if profile.install_stage != ProfileInstallStage::Installed {
+        return Err(crate::ErrorKind::LauncherError(format!(
+            "Profile {path} is not installed"
+        ))
+        .into());
+    }
+
+    crate::launcher::verify::verify_minecraft(&profile).await
+}
+
 #[tracing::instrument]
 pub async fn update_all_projects(
     profile_path: &str,
@@ -282,6 +308,15 @@ pub async fn update_all_projects(
             .collect::<Vec<_>>();
         let len = keys.len();
 
+        if !keys.is_empty() {
+            rollback::create_rollback_snapshot(
+                profile_path,
+                rollback::RollbackOperation::UpdateAllProjects,
+                Vec::new(),
+            )
+            .await?;
+        }
+
         let map = Arc::new(RwLock::new(HashMap::new()));
 
         use futures::StreamExt;
@@ -297,8 +332,7 @@ pub async fn update_all_projects(
 
                 async move {
                     let new_path =
-                        update_project(profile_path, &project, Some(true))
-                            .await?;
+                        update_project_file(profile_path, &project).await?;
 
                     map.write().await.insert(project, new_path);
 
@@ -309,9 +343,17 @@ pub async fn update_all_projects(
         )
         .await?;
 
+        let map = Arc::try_unwrap(map).unwrap().into_inner();
+        move_update_policies(
+            profile_path,
+            map.iter()
+                .map(|(old, new)| (old.as_str(), Some(new.as_str()))),
+        )
+        .await?;
+
         emit_profile(profile_path, ProfilePayloadType::Edited).await?;
 
-        Ok(Arc::try_unwrap(map).unwrap().into_inner())
+        Ok(map)
     } else {
         Err(
             crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
@@ -320,13 +362,177 @@ pub async fn update_all_projects(
     }
 }
 
-/// Updates a project to the latest version
+/// A pending update of a file of a profile
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectUpdate {
+    /// Path of the file, relative to the profile
+    pub path: String,
+    pub provider: ContentProvider,
+    pub project_id: String,
+    pub old_version_id: String,
+    pub new_version_id: String,
+    /// Version number, or display name for CurseForge files
+    pub old_version_name: Option<String>,
+    pub new_version_name: Option<String>,
+}
+
+/// Lists the updates update_all_projects would apply, following the update
+/// policy of each file
+#[tracing::instrument]
+pub async fn preview_update_all_projects(
+    profile_path: &str,
+) -> crate::Result<Vec<ProjectUpdate>> {
+    let state = State::get().await?;
+    let profile = get(profile_path).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+            .as_error()
+    })?;
+
+    let mut updates = profile
+        .get_projects(
+            Some(CacheBehaviour::MustRevalidate),
+            &state.pool,
+            &state.api_semaphore,
+        )
+        .await?
+        .into_iter()
+        .filter_map(|(path, file)| {
+            let metadata = file.metadata?;
+            Some(ProjectUpdate {
+                path,
+                provider: metadata.provider,
+                project_id: metadata.project_id,
+                old_version_id: metadata.version_id,
+                new_version_id: file.update_version_id?,
+                old_version_name: None,
+                new_version_name: None,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let version_ids = updates
+        .iter()
+        .filter(|x| x.provider == ContentProvider::Modrinth)
+        .flat_map(|x| [&*x.old_version_id, &*x.new_version_id])
+        .collect::<Vec<_>>();
+    let versions = CachedEntry::get_version_many(
+        &version_ids,
+        None,
+        &state.pool,
+        &state.api_semaphore,
+    )
+    .await?
+    .into_iter()
+    .map(|x| (x.id, x.version_number))
+    .collect::<HashMap<_, _>>();
+
+    let file_ids = updates
+        .iter()
+        .filter(|x| x.provider == ContentProvider::CurseForge)
+        .flat_map(|x| [&x.old_version_id, &x.new_version_id])
+        .filter_map(|x| x.parse::<i32>().ok())
+        .collect::<Vec<_>>();
+    let cf_files = crate::api::curseforge::get_files_cached(&file_ids, None)
+        .await?
+        .into_iter()
+        .map(|x| (x.id.to_string(), x.display_name))
+        .collect::<HashMap<_, _>>();
+
+    for update in &mut updates {
+        let names = match update.provider {
+            ContentProvider::Modrinth => &versions,
+            ContentProvider::CurseForge => &cf_files,
+        };
+        update.old_version_name = names.get(&update.old_version_id).cloned();
+        update.new_version_name = names.get(&update.new_version_id).cloned();
+    }
+
+    updates.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(updates)
+}
+
+/// Sets how a project is kept up to date, or resets it to the default
+/// policy if None
+#[tracing::instrument]
+pub async fn edit_update_policy(
+    profile_path: &str,
+    project_path: &str,
+    policy: Option<UpdatePolicy>,
+) -> crate::Result<()> {
+    let key = project_path.trim_end_matches(".disabled").to_string();
+
+    edit(profile_path, |profile| {
+        match policy.clone().filter(|x| *x != UpdatePolicy::default()) {
+            Some(policy) => {
+                profile.update_policies.insert(key.clone(), policy);
+            }
+            None => {
+                profile.update_policies.remove(&key);
+            }
+        }
+
+        async { Ok(()) }
+    })
+    .await
+}
+
+/// Moves the update policies of projects whose path changed, or forgets
+/// them if the project was removed
+async fn move_update_policies<'a>(
+    profile_path: &str,
+    moved: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+) -> crate::Result<()> {
+    let Some(mut profile) = get(profile_path).await? else {
+        return Ok(());
+    };
+
+    let mut changed = false;
+    for (old, new) in moved {
+        if let Some(policy) = profile
+            .update_policies
+            .remove(old.trim_end_matches(".disabled"))
+        {
+            changed = true;
+            if let Some(new) = new {
+                profile.update_policies.insert(
+                    new.trim_end_matches(".disabled").to_string(),
+                    policy,
+                );
+            }
+        }
+    }
+
+    if changed {
+        let state = State::get().await?;
+        profile.upsert(&state.pool).await?;
+    }
+
+    Ok(())
+}
+
+/// Updates a project to the latest version its update policy allows
 /// Uses and returns the relative path to the project
 #[tracing::instrument]
 pub async fn update_project(
     profile_path: &str,
     project_path: &str,
     skip_send_event: Option<bool>,
+) -> crate::Result<String> {
+    let path = update_project_file(profile_path, project_path).await?;
+
+    move_update_policies(profile_path, [(project_path, Some(&*path))]).await?;
+
+    if !skip_send_event.unwrap_or(false) {
+        emit_profile(profile_path, ProfilePayloadType::Edited).await?;
+    }
+
+    Ok(path)
+}
+
+async fn update_project_file(
+    profile_path: &str,
+    project_path: &str,
 ) -> crate::Result<String> {
     if let Some(profile) = get(profile_path).await? {
         let state = State::get().await?;
@@ -340,23 +546,47 @@ pub async fn update_project(
             .remove(project_path)
             && let Some(update_version) = &file.update_version_id
         {
-            let path = Profile::add_project_version(
-                profile_path,
-                update_version,
-                &state.pool,
-                &state.fetch_semaphore,
-                &state.io_semaphore,
-            )
-            .await?;
+            let path = match file.metadata.as_ref().map(|x| x.provider) {
+                Some(ContentProvider::CurseForge) => {
+                    let ids = file.metadata.as_ref().and_then(|x| {
+                        Some((
+                            x.project_id.parse::<i32>().ok()?,
+                            update_version.parse::<i32>().ok()?,
+                        ))
+                    });
+                    let Some((mod_id, file_id)) = ids else {
+                        return Err(crate::ErrorKind::InputError(
+                            "This project cannot be updated!".to_string(),
+                        )
+                        .as_error());
+                    };
+
+                    Profile::add_curseforge_project(
+                        profile_path,
+                        mod_id,
+                        file_id,
+                        &state.pool,
+                        &state.fetch_semaphore,
+                        &state.io_semaphore,
+                    )
+                    .await?
+                }
+                _ => {
+                    Profile::add_project_version(
+                        profile_path,
+                        update_version,
+                        &state.pool,
+                        &state.fetch_semaphore,
+                        &state.io_semaphore,
+                    )
+                    .await?
+                }
+            };
 
             if path != project_path {
                 Profile::remove_project(profile_path, project_path).await?;
             }
 
-            if !skip_send_event.unwrap_or(false) {
-                emit_profile(profile_path, ProfilePayloadType::Edited).await?;
-            }
-
             return Ok(path);
         }
 
@@ -472,6 +702,7 @@ pub async fn remove_project(
     project: &str,
 ) -> crate::Result<()> {
     Profile::remove_project(profile_path, project).await?;
+    move_update_policies(profile_path, [(project, None)]).await?;
 
     emit_profile(profile_path, ProfilePayloadType::Edited).await?;
 
@@ -633,7 +864,7 @@ pub async fn get_pack_export_candidates(
     Ok(path_list)
 }
 
-fn pack_get_relative_path(
+pub(crate) fn pack_get_relative_path(
     profile_path: &PathBuf,
     path: &PathBuf,
 ) -> crate::Result<SafeRelativeUtf8UnixPathBuf> {
@@ -653,6 +884,9 @@ fn pack_get_relative_path(
 
 /// Run Minecraft using a profile and the default credentials, logged in credentials,
 /// failing with an error if no credentials are available
+///
+/// If the profile has a preferred account, it is used instead of the default
+/// one, unless it has been removed since.
 #[tracing::instrument]
 pub async fn run(
     path: &str,
@@ -660,11 +894,91 @@ pub async fn run(
 ) -> crate::Result<ProcessMetadata> {
     let state = State::get().await?;
 
+    let preferred_account = get(path).await?.and_then(|x| x.preferred_account);
+    if let Some(account_uuid) = preferred_account {
+        if let Some(credentials) =
+            Credentials::get_credential(account_uuid, &state.pool).await?
+        {
+            return run_credentials(path, &credentials, quick_play_type, false)
+                .await;
+        }
+
+        tracing::warn!(
+            "Preferred account {account_uuid} of profile {path} no longer exists, using the default account"
+        );
+    }
+
     let default_account = Credentials::get_default_credential(&state.pool)
         .await?
         .ok_or_else(|| crate::ErrorKind::NoCredentialsError.as_error())?;
 
-    run_credentials(path, &default_account, quick_play_type).await
+    run_credentials(path, &default_account, quick_play_type, false).await
+}
+
+/// Run Minecraft using a profile and the credentials of the given account,
+/// regardless of the default or preferred account
+#[tracing::instrument]
+pub async fn run_with_account(
+    path: &str,
+    account_uuid: Uuid,
+    quick_play_type: QuickPlayType,
+) -> crate::Result<ProcessMetadata> {
+    let state = State::get().await?;
+
+    let credentials = Credentials::get_credential(account_uuid, &state.pool)
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::InputError(format!(
+                "Unknown account {account_uuid}"
+            ))
+        })?;
+
+    run_credentials(path, &credentials, quick_play_type, false).await
+}
+
+/// Run Minecraft using a profile without network access
+///
+/// The preferred account of the profile, or else the default account, is
+/// used with the name and UUID it had when it was last refreshed, and its
+/// tokens are not refreshed. Metadata is only read
+/// from the cache, and the launch fails listing the missing files if any
+/// game file is not already downloaded.
+#[tracing::instrument]
+pub async fn run_offline(
+    path: &str,
+    quick_play_type: QuickPlayType,
+) -> crate::Result<ProcessMetadata> {
+    let state = State::get().await?;
+
+    let preferred_account =
+        match get(path).await?.and_then(|x| x.preferred_account) {
+            Some(account_uuid) => {
+                Credentials::get_offline_by_uuid(account_uuid, &state.pool)
+                    .await?
+            }
+            None => None,
+        };
+    let account = match preferred_account {
+        Some(account) => account,
+        None => Credentials::get_active_offline(&state.pool)
+            .await?
+            .ok_or_else(|| crate::ErrorKind::NoCredentialsError.as_error())?,
+    };
+
+    run_credentials(path, &account, quick_play_type, true).await
+}
+
+/// Lists the game files that are missing to launch a profile offline
+/// with [`run_offline`]
+#[tracing::instrument]
+pub async fn get_offline_missing_files(
+    path: &str,
+) -> crate::Result<Vec<PathBuf>> {
+    let profile = get(path).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(path.to_string()).as_error()
+    })?;
+
+    crate::launcher::get_offline_missing_files(&profile).await
 }
 
 /// Run Minecraft using a profile, and credentials for authentication
@@ -673,6 +987,7 @@ async fn run_credentials(
     path: &str,
     credentials: &Credentials,
     quick_play_type: QuickPlayType,
+    offline: bool,
 ) -> crate::Result<ProcessMetadata> {
     let state = State::get().await?;
     let settings = Settings::get(&state.pool).await?;
@@ -682,42 +997,14 @@ async fn run_credentials(
         ))
     })?;
 
-    let pre_launch_hooks = profile
+    // Run by the launcher once the Java installation is known, so that
+    // it can be passed to the hook
+    let pre_launch_hook = profile
         .hooks
         .pre_launch
-        .as_ref()
-        .or(settings.hooks.pre_launch.as_ref())
+        .clone()
+        .or(settings.hooks.pre_launch.clone())
         .filter(|hook_command| !hook_command.is_empty());
-    if let Some(hook) = pre_launch_hooks {
-        // TODO: hook parameters
-        let mut cmd = shlex::split(hook)
-            .ok_or_else(|| {
-                crate::ErrorKind::LauncherError(format!(
-                    "Invalid pre-launch command: {hook}",
-                ))
-            })?
-            .into_iter();
-
-        if let Some(command) = cmd.next() {
-            let full_path = get_full_path(&profile.path).await?;
-            let result = Command::new(command)
-                .args(cmd)
-                .current_dir(&full_path)
-                .spawn()
-                .map_err(|e| IOError::with_path(e, &full_path))?
-                .wait()
-                .await
-                .map_err(IOError::from)?;
-
-            if !result.success() {
-                return Err(crate::ErrorKind::LauncherError(format!(
-                    "Non-zero exit code for pre-launch hook: {}",
-                    result.code().unwrap_or(-1)
-                ))
-                .as_error());
-            }
-        }
-    }
 
     let java_args = profile
         .extra_launch_args
@@ -748,8 +1035,10 @@ async fn run_credentials(
         .or(settings.hooks.post_exit)
         .filter(|hook_command| !hook_command.is_empty());
 
-    // Any options.txt settings that we want set, add here
-    let mut mc_set_options: Vec<(String, String)> = vec![];
+    // Any options.txt settings that we want set, add here. Later entries
+    // win, so profile options override the global ones
+    let mut mc_set_options: Vec<(String, String)> = settings.game_options;
+    mc_set_options.extend(profile.game_options.iter().cloned());
     if let Some(fullscreen) = profile.force_fullscreen {
         // Profile fullscreen setting takes priority
         mc_set_options.push(("fullscreen".to_string(), fullscreen.to_string()));
@@ -762,13 +1051,15 @@ async fn run_credentials(
         &java_args,
         &env_args,
         &mc_set_options,
-        &wrapper,
+        pre_launch_hook.as_deref(),
+        wrapper.as_deref(),
         &memory,
         &resolution,
         credentials,
         post_exit_hook,
         &profile,
         quick_play_type,
+        offline,
     )
     .await
 }
@@ -784,6 +1075,22 @@ pub async fn kill(path: &str) -> crate::Result<()> {
     Ok(())
 }
 
+/// Asks every running process of a profile to quit cleanly, killing the ones
+/// still running after `timeout`. Returns how each process was stopped
+pub async fn stop(
+    path: &str,
+    timeout: Option<Duration>,
+) -> crate::Result<Vec<StopMethod>> {
+    let processes = crate::api::process::get_by_profile_path(path).await?;
+
+    futures::future::try_join_all(
+        processes
+            .into_iter()
+            .map(|process| crate::api::process::stop(process.uuid, timeout)),
+    )
+    .await
+}
+
 /// Update playtime- sending a request to the server to update the playtime
 #[tracing::instrument]
 pub async fn try_update_playtime(path: &str) -> crate::Result<()> {
@@ -798,8 +1105,11 @@ pub async fn try_update_playtime(path: &str) -> crate::Result<()> {
 
     let res = if updated_recent_playtime > 0 {
         // Create update struct to send to labrinth
-        let modrinth_pack_version_id =
-            profile.linked_data.as_ref().map(|l| l.version_id.clone());
+        let modrinth_pack_version_id = profile
+            .linked_data
+            .as_ref()
+            .filter(|l| l.provider == ContentProvider::Modrinth)
+            .map(|l| l.version_id.clone());
         let playtime_update_json = json!({
             "seconds": updated_recent_playtime,
             "loader": profile.loader.as_str(),
@@ -813,7 +1123,9 @@ pub async fn try_update_playtime(path: &str) -> crate::Result<()> {
             .get_projects(None, &state.pool, &state.api_semaphore)
             .await?
         {
-            if let Some(metadata) = project.metadata {
+            if let Some(metadata) = project.metadata
+                && metadata.provider == ContentProvider::Modrinth
+            {
                 hashmap
                     .insert(metadata.version_id, playtime_update_json.clone());
             }
@@ -888,7 +1200,11 @@ pub async fn create_mrpack_json(
         .await?
         .into_iter()
         .filter_map(|(path, file)| match file.metadata {
-            Some(metadata) => Some((path, metadata.version_id)),
+            Some(metadata)
+                if metadata.provider == ContentProvider::Modrinth =>
+            {
+                Some((path, metadata.version_id))
+            }
             _ => None,
         })
         .collect::<Vec<_>>();
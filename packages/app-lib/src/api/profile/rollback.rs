@@ -0,0 +1,459 @@
+//! Rollback snapshots of a profile's content
+//!
+//! A snapshot is taken before operations that change many files at once,
+//! such as updating every project or switching modpack versions. It records
+//! the path, size and hash of every file in the content folders, and of the
+//! files a modpack places outside of them, and keeps the originals in a
+//! content-addressed store at `<instance>/backups/.rollback`, so rolling back
+//! restores them in one step. Everything else in the profile, such as worlds
+//! or data mods write while playing, is left alone.
+
+use crate::event::{ProfilePayloadType, emit::emit_profile};
+use crate::profile::{get, get_full_path};
+use crate::state::{LinkedData, ModLoader, UpdatePolicy};
+use crate::util::io::{self, IOError};
+use crate::util::snapshot_store::{self, SnapshotStore};
+use crate::{ErrorKind, Result};
+use async_walkdir::WalkDir;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::LazyLock;
+
+const STORE_DIR: &str = ".rollback";
+/// Snapshots kept per profile, older ones are removed
+const KEEP_SNAPSHOTS: usize = 10;
+/// Folders of a profile that every snapshot covers
+const CONTENT_FOLDERS: &[&str] = &[
+    "mods",
+    "config",
+    "resourcepacks",
+    "shaderpacks",
+    "datapacks",
+];
+// Project files are only ever replaced by the launcher and never edited in
+// place, so their originals are hard linked into the store instead of copied
+const PROJECT_FOLDERS: &[&str] =
+    &["mods", "resourcepacks", "shaderpacks", "datapacks"];
+
+static STORE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// The operation a snapshot was taken before
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RollbackOperation {
+    UpdateAllProjects,
+    UpdateModpack,
+    RepairModpack,
+    InstallModpack,
+}
+
+impl RollbackOperation {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::UpdateAllProjects => "update_all_projects",
+            Self::UpdateModpack => "update_modpack",
+            Self::RepairModpack => "repair_modpack",
+            Self::InstallModpack => "install_modpack",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RollbackSnapshot {
+    pub id: String,
+    pub operation: RollbackOperation,
+    pub created: DateTime<Utc>,
+    pub file_count: usize,
+    /// Size of the snapshotted files
+    pub size: u64,
+    /// Bytes this snapshot added to the store
+    pub added_size: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotManifest {
+    #[serde(flatten)]
+    snapshot: RollbackSnapshot,
+    profile: ProfileContent,
+    /// Paths outside of the content folders the snapshot covers, whether or
+    /// not a file existed there
+    #[serde(default)]
+    pack_paths: Vec<String>,
+    files: Vec<SnapshotFile>,
+}
+
+impl snapshot_store::SnapshotManifest for SnapshotManifest {
+    fn created(&self) -> DateTime<Utc> {
+        self.snapshot.created
+    }
+
+    fn objects(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|x| x.hash.as_str())
+    }
+}
+
+/// The profile settings the snapshotted operations change
+#[derive(Serialize, Deserialize, Clone)]
+struct ProfileContent {
+    game_version: String,
+    protocol_version: Option<u32>,
+    loader: ModLoader,
+    loader_version: Option<String>,
+    linked_data: Option<LinkedData>,
+    #[serde(default)]
+    update_policies: HashMap<String, UpdatePolicy>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct SnapshotFile {
+    path: String,
+    size: u64,
+    modified: i64,
+    hash: String,
+}
+
+fn snapshot_store(instance: &Path) -> SnapshotStore {
+    SnapshotStore::new(
+        instance.join("backups").join(STORE_DIR),
+        "rollback snapshot",
+    )
+}
+
+fn top_folder(path: &str) -> &str {
+    path.split('/').next().unwrap_or_default()
+}
+
+/// Size and modification time of every file a snapshot covers, keyed by
+/// their path relative to the instance
+async fn scan_files(
+    instance: &Path,
+    pack_paths: &HashSet<&str>,
+) -> Result<HashMap<String, (u64, i64)>> {
+    async fn file_info(path: &Path) -> Result<(u64, i64)> {
+        let metadata = io::metadata(path).await?;
+        let modified = metadata
+            .modified()
+            .map(|x| DateTime::<Utc>::from(x).timestamp_millis())
+            .unwrap_or_default();
+        Ok((metadata.len(), modified))
+    }
+
+    let mut files = HashMap::new();
+
+    for folder in CONTENT_FOLDERS {
+        let dir = instance.join(folder);
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let mut walker = WalkDir::new(&dir);
+        while let Some(entry) = walker.next().await {
+            let entry = entry.map_err(IOError::from_walkdir)?;
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+
+            let path = entry.path();
+            let relative_path = path
+                .strip_prefix(instance)?
+                .display()
+                .to_string()
+                .replace('\\', "/");
+            files.insert(relative_path, file_info(&path).await?);
+        }
+    }
+
+    for path in pack_paths {
+        let full_path = instance.join(path);
+        if !files.contains_key(*path) && full_path.is_file() {
+            files.insert(path.to_string(), file_info(&full_path).await?);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Takes a snapshot of a profile's content and content settings, to roll
+/// back to if an operation goes wrong
+///
+/// Besides the content folders, the snapshot covers `pack_paths`, the files
+/// a modpack writes or removes elsewhere in the profile. Files whose size
+/// and modification time match the latest snapshot are not read again. Only
+/// the newest snapshots are kept.
+pub async fn create_rollback_snapshot(
+    profile_path: &str,
+    operation: RollbackOperation,
+    pack_paths: impl IntoIterator<Item = String>,
+) -> Result<RollbackSnapshot> {
+    let profile = get(profile_path).await?.ok_or_else(|| {
+        ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    let instance = get_full_path(profile_path).await?;
+
+    let mut pack_paths = pack_paths
+        .into_iter()
+        .filter(|x| !CONTENT_FOLDERS.contains(&top_folder(x)))
+        .collect::<Vec<_>>();
+    pack_paths.sort();
+    pack_paths.dedup();
+
+    let _store_lock = STORE_LOCK.lock().await;
+    let store = snapshot_store(&instance);
+
+    let previous_files = store
+        .read_manifests::<SnapshotManifest>()
+        .await?
+        .into_iter()
+        .next()
+        .map(|x| {
+            x.files
+                .into_iter()
+                .map(|file| (file.path.clone(), file))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    let mut files = Vec::new();
+    let mut size = 0;
+    let mut added_size = 0;
+
+    let covered = pack_paths.iter().map(|x| x.as_str()).collect();
+    for (path, (file_size, modified)) in scan_files(&instance, &covered).await?
+    {
+        size += file_size;
+
+        if let Some(previous) = previous_files.get(&path)
+            && previous.size == file_size
+            && previous.modified == modified
+            && store.object_path(&previous.hash).exists()
+        {
+            files.push(previous.clone());
+            continue;
+        }
+
+        let (hash, added) = if PROJECT_FOLDERS.contains(&top_folder(&path)) {
+            store.link_object(&instance.join(&path)).await?
+        } else {
+            store
+                .write_object(&io::read(instance.join(&path)).await?)
+                .await?
+        };
+        added_size += added;
+
+        files.push(SnapshotFile {
+            path,
+            size: file_size,
+            modified,
+            hash,
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let snapshot = RollbackSnapshot {
+        id: store.new_id(operation.as_str()).await?,
+        operation,
+        created: Utc::now(),
+        file_count: files.len(),
+        size,
+        added_size,
+    };
+    let manifest = SnapshotManifest {
+        snapshot: snapshot.clone(),
+        profile: ProfileContent {
+            game_version: profile.game_version,
+            protocol_version: profile.protocol_version,
+            loader: profile.loader,
+            loader_version: profile.loader_version,
+            linked_data: profile.linked_data,
+            update_policies: profile.update_policies,
+        },
+        pack_paths,
+        files,
+    };
+    store.write_manifest(&snapshot.id, &manifest).await?;
+
+    let old_manifests = store.read_manifests::<SnapshotManifest>().await?;
+    if old_manifests.len() > KEEP_SNAPSHOTS {
+        for manifest in &old_manifests[KEEP_SNAPSHOTS..] {
+            store.remove_manifest(&manifest.snapshot.id).await?;
+        }
+        // The new snapshot is kept even if old objects cannot be freed
+        if let Err(err) = store.collect_garbage::<SnapshotManifest>().await {
+            tracing::warn!("Failed to free old rollback snapshots: {err}");
+        }
+    }
+
+    Ok(snapshot)
+}
+
+/// Lists the rollback snapshots of a profile, newest first
+pub async fn list_rollback_snapshots(
+    profile_path: &str,
+) -> Result<Vec<RollbackSnapshot>> {
+    let instance = get_full_path(profile_path).await?;
+    Ok(snapshot_store(&instance)
+        .read_manifests::<SnapshotManifest>()
+        .await?
+        .into_iter()
+        .map(|x| x.snapshot)
+        .collect())
+}
+
+/// Restores the content and content settings of a profile to a snapshot
+///
+/// Files added to the content folders since the snapshot are removed, and
+/// changed or removed ones are written back. The rest of the profile is left
+/// untouched. Fails while the profile is running.
+pub async fn rollback_profile(
+    profile_path: &str,
+    snapshot_id: &str,
+) -> Result<()> {
+    if !crate::api::process::get_by_profile_path(profile_path)
+        .await?
+        .is_empty()
+    {
+        return Err(ErrorKind::InputError(format!(
+            "Cannot roll back profile {profile_path} while it is running"
+        ))
+        .into());
+    }
+
+    let instance = get_full_path(profile_path).await?;
+
+    let _store_lock = STORE_LOCK.lock().await;
+    let store = snapshot_store(&instance);
+    let manifest: SnapshotManifest = store.read_manifest(snapshot_id).await?;
+
+    if let Some(missing) =
+        store.find_missing(snapshot_store::SnapshotManifest::objects(&manifest))
+    {
+        return Err(ErrorKind::OtherError(format!(
+            "Rollback snapshot {snapshot_id} is damaged, object {missing} is missing"
+        ))
+        .into());
+    }
+
+    let covered = manifest.pack_paths.iter().map(|x| x.as_str()).collect();
+    let current = scan_files(&instance, &covered).await?;
+    let (restore, remove) = plan_rollback(&manifest.files, &current);
+
+    for path in remove {
+        io::remove_file(instance.join(path)).await?;
+    }
+    for file in restore {
+        // Written to a new file, so links to the old one are left untouched
+        let data = store.read_object(&file.hash).await?;
+        let path = instance.join(&file.path);
+        if let Some(parent) = path.parent() {
+            io::create_dir_all(parent).await?;
+        }
+        io::write(&path, data).await?;
+    }
+
+    let Some(mut profile) = get(profile_path).await? else {
+        return Err(
+            ErrorKind::UnmanagedProfileError(profile_path.to_string()).into()
+        );
+    };
+    let content = manifest.profile;
+    let game_changed = profile.game_version != content.game_version
+        || profile.loader != content.loader
+        || profile.loader_version != content.loader_version;
+
+    profile.game_version = content.game_version;
+    profile.protocol_version = content.protocol_version;
+    profile.loader = content.loader;
+    profile.loader_version = content.loader_version;
+    profile.linked_data = content.linked_data;
+    profile.update_policies = content.update_policies;
+
+    let state = crate::State::get().await?;
+    profile.upsert(&state.pool).await?;
+
+    if game_changed {
+        crate::launcher::install_minecraft(&profile, None, false).await?;
+    }
+
+    emit_profile(profile_path, ProfilePayloadType::Edited).await?;
+
+    Ok(())
+}
+
+/// Deletes a rollback snapshot and every original only it referenced
+pub async fn delete_rollback_snapshot(
+    profile_path: &str,
+    snapshot_id: &str,
+) -> Result<u64> {
+    let instance = get_full_path(profile_path).await?;
+
+    let _store_lock = STORE_LOCK.lock().await;
+    let store = snapshot_store(&instance);
+    store.read_manifest::<SnapshotManifest>(snapshot_id).await?;
+
+    store.remove_manifest(snapshot_id).await?;
+    store.collect_garbage::<SnapshotManifest>().await
+}
+
+/// Decides which snapshot files a rollback writes back, and which current
+/// files it removes, given the size and modification time of each current
+/// file. Files that still match their snapshot record are left alone
+fn plan_rollback<'a>(
+    files: &'a [SnapshotFile],
+    current: &HashMap<String, (u64, i64)>,
+) -> (Vec<&'a SnapshotFile>, Vec<String>) {
+    let restore = files
+        .iter()
+        .filter(|file| {
+            current.get(&file.path) != Some(&(file.size, file.modified))
+        })
+        .collect();
+
+    let snapshot_paths = files
+        .iter()
+        .map(|x| x.path.as_str())
+        .collect::<HashSet<_>>();
+    let mut remove = current
+        .keys()
+        .filter(|path| !snapshot_paths.contains(path.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    remove.sort();
+
+    (restore, remove)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size: u64, modified: i64) -> SnapshotFile {
+        SnapshotFile {
+            path: path.to_string(),
+            size,
+            modified,
+            hash: format!("{path}-hash"),
+        }
+    }
+
+    #[test]
+    fn test_plan_rollback() {
+        let files = vec![
+            file("mods/unchanged.jar", 10, 1),
+            file("mods/updated.jar", 10, 1),
+            file("config/removed.toml", 5, 1),
+        ];
+        let current = HashMap::from([
+            ("mods/unchanged.jar".to_string(), (10, 1)),
+            ("mods/updated.jar".to_string(), (12, 2)),
+            ("mods/added.jar".to_string(), (8, 2)),
+        ]);
+
+        let (restore, remove) = plan_rollback(&files, &current);
+        assert_eq!(restore, vec![&files[1], &files[2]]);
+        assert_eq!(remove, vec!["mods/added.jar".to_string()]);
+    }
+}
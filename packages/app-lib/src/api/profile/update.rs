@@ -1,17 +1,28 @@
-use crate::state::CacheBehaviour;
+use crate::state::{CacheBehaviour, ContentProvider, LinkedData};
 use crate::{
     LoadingBarType,
     event::{
         ProfilePayloadType,
         emit::{emit_profile, init_loading},
     },
-    pack::{self, install_from::generate_pack_from_version_id},
-    profile::get,
+    pack::{
+        self,
+        install_curseforge::{
+            ManualDownload, emit_manual_downloads_warning,
+            generate_pack_from_curseforge_id,
+        },
+        install_from::generate_pack_from_version_id,
+    },
+    profile::{
+        get,
+        rollback::{RollbackOperation, create_rollback_snapshot},
+    },
     state::ProfileInstallStage,
 };
 use futures::try_join;
 
 /// Updates a managed modrinth pack to the version specified by new_version_id
+/// Profiles linked to a CurseForge pack are updated to the file with that ID
 #[tracing::instrument]
 
 pub async fn update_managed_modrinth_version(
@@ -32,6 +43,18 @@ pub async fn update_managed_modrinth_version(
     // Extract modrinth pack information, if appropriate
     let linked_data = profile.linked_data.as_ref().ok_or_else(unmanaged_err)?;
 
+    if linked_data.provider == ContentProvider::CurseForge {
+        let new_file_id = new_version_id.parse().map_err(|_| {
+            crate::ErrorKind::InputError(format!(
+                "Invalid CurseForge file ID {new_version_id}"
+            ))
+        })?;
+        let manual_downloads =
+            update_managed_curseforge_version(profile_path, new_file_id)
+                .await?;
+        return emit_manual_downloads_warning(&manual_downloads).await;
+    }
+
     // Replace the pack with the new version
     replace_managed_modrinth(
         profile_path,
@@ -49,6 +72,7 @@ pub async fn update_managed_modrinth_version(
 }
 
 /// Repair a managed modrinth pack by 'updating' it to the current version
+/// Profiles linked to a CurseForge pack are repaired the same way
 #[tracing::instrument]
 
 pub async fn repair_managed_modrinth(profile_path: &str) -> crate::Result<()> {
@@ -63,20 +87,13 @@ pub async fn repair_managed_modrinth(profile_path: &str) -> crate::Result<()> {
         ))
     };
 
-    // For repairing specifically, first we remove all installed projects (to ensure we do remove ones that aren't in the pack)
-    // We do a project removal followed by removing everything in the .mrpack, to ensure we only
-    // remove relevant projects and not things like save files
-    let state = crate::State::get().await?;
-    let projects_map = profile
-        .get_projects(
-            Some(CacheBehaviour::MustRevalidate),
-            &state.pool,
-            &state.api_semaphore,
-        )
-        .await?;
-
-    for (file, _) in projects_map {
-        crate::state::Profile::remove_project(&profile.path, &file).await?;
+    if profile
+        .linked_data
+        .as_ref()
+        .is_some_and(|x| x.provider == ContentProvider::CurseForge)
+    {
+        let manual_downloads = repair_managed_curseforge(profile_path).await?;
+        return emit_manual_downloads_warning(&manual_downloads).await;
     }
 
     // Extract modrinth pack information, if appropriate
@@ -99,7 +116,9 @@ pub async fn repair_managed_modrinth(profile_path: &str) -> crate::Result<()> {
 }
 
 /// Replace a managed modrinth pack with a new version
-/// If new_version_id is None, the pack is 'reinstalled' in-place
+/// If new_version_id is None, the pack is 'reinstalled' in-place, removing
+/// every installed project first
+/// A rollback snapshot is taken before any file changes
 #[tracing::instrument(skip(profile))]
 
 async fn replace_managed_modrinth(
@@ -171,6 +190,37 @@ async fn replace_managed_modrinth(
         (old_pack_creator.clone(), old_pack_creator)
     };
 
+    let mut pack_paths =
+        pack::install_mrpack::get_mrpack_paths(&old_pack_creator.file).await?;
+    let operation = if new_version_id.is_some() {
+        pack_paths.extend(
+            pack::install_mrpack::get_mrpack_paths(&new_pack_creator.file)
+                .await?,
+        );
+        RollbackOperation::UpdateModpack
+    } else {
+        RollbackOperation::RepairModpack
+    };
+    create_rollback_snapshot(profile_path, operation, pack_paths).await?;
+
+    // For repairing specifically, first we remove all installed projects (to ensure we do remove ones that aren't in the pack)
+    // We do a project removal followed by removing everything in the .mrpack, to ensure we only
+    // remove relevant projects and not things like save files
+    if new_version_id.is_none() {
+        let state = crate::State::get().await?;
+        let projects_map = profile
+            .get_projects(
+                Some(CacheBehaviour::MustRevalidate),
+                &state.pool,
+                &state.api_semaphore,
+            )
+            .await?;
+
+        for (file, _) in projects_map {
+            crate::state::Profile::remove_project(&profile.path, &file).await?;
+        }
+    }
+
     // Removal - remove all files that were added by the old pack
     // - remove all installed projects
     // - remove all overrides
@@ -193,3 +243,189 @@ async fn replace_managed_modrinth(
 
     Ok(())
 }
+
+/// Updates a managed CurseForge pack to the file specified by new_file_id
+/// Returns the files of the new version that must be downloaded manually
+#[tracing::instrument]
+pub async fn update_managed_curseforge_version(
+    profile_path: &str,
+    new_file_id: i32,
+) -> crate::Result<Vec<ManualDownload>> {
+    let profile = get(profile_path).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+            .as_error()
+    })?;
+    let (mod_id, file_id) = get_curseforge_link(profile_path, &profile)?;
+
+    // Replace the pack with the new version
+    let manual_downloads = replace_managed_curseforge(
+        profile_path,
+        &profile,
+        mod_id,
+        file_id,
+        Some(new_file_id),
+        true, // switching versions should ignore the lock
+    )
+    .await?;
+
+    emit_profile(profile_path, ProfilePayloadType::Edited).await?;
+
+    Ok(manual_downloads)
+}
+
+/// Repair a managed CurseForge pack by reinstalling its current version
+/// Unlike Modrinth packs, the installed projects are kept, as the files of
+/// the pack are reinstalled over them
+#[tracing::instrument]
+pub async fn repair_managed_curseforge(
+    profile_path: &str,
+) -> crate::Result<Vec<ManualDownload>> {
+    let profile = get(profile_path).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+            .as_error()
+    })?;
+    let (mod_id, file_id) = get_curseforge_link(profile_path, &profile)?;
+
+    // Replace the pack with the same version
+    let manual_downloads = replace_managed_curseforge(
+        profile_path,
+        &profile,
+        mod_id,
+        file_id,
+        None,
+        false, // do not ignore lock, as repairing can reset the lock
+    )
+    .await?;
+
+    emit_profile(profile_path, ProfilePayloadType::Edited).await?;
+
+    Ok(manual_downloads)
+}
+
+/// Reads the CurseForge mod and file ID of the pack a profile is linked to
+fn get_curseforge_link(
+    profile_path: &str,
+    profile: &crate::state::Profile,
+) -> crate::Result<(i32, i32)> {
+    profile
+        .linked_data
+        .as_ref()
+        .filter(|x| x.provider == ContentProvider::CurseForge)
+        .and_then(|LinkedData { project_id, version_id, .. }| {
+            Some((project_id.parse().ok()?, version_id.parse().ok()?))
+        })
+        .ok_or_else(|| {
+            crate::ErrorKind::InputError(format!(
+                "Profile at {profile_path} is not a managed CurseForge pack, or has been disconnected."
+            ))
+            .into()
+        })
+}
+
+/// Replace a managed CurseForge pack with a new version
+/// If new_file_id is None, the pack is 'reinstalled' in-place
+/// A rollback snapshot is taken before any file changes
+#[tracing::instrument(skip(profile))]
+async fn replace_managed_curseforge(
+    profile_path: &str,
+    profile: &crate::state::Profile,
+    mod_id: i32,
+    file_id: i32,
+    new_file_id: Option<i32>,
+    ignore_lock: bool,
+) -> crate::Result<Vec<ManualDownload>> {
+    crate::profile::edit(profile_path, |profile| {
+        profile.install_stage = ProfileInstallStage::MinecraftInstalling;
+        async { Ok(()) }
+    })
+    .await?;
+
+    let (old_pack_creator, new_pack_creator) = if let Some(new_file_id) =
+        new_file_id
+    {
+        let shared_loading_bar = init_loading(
+            LoadingBarType::PackFileDownload {
+                profile_path: crate::api::profile::get_full_path(profile_path)
+                    .await?
+                    .to_string_lossy()
+                    .to_string(),
+                pack_name: profile.name.clone(),
+                icon: None,
+                pack_version: file_id.to_string(),
+            },
+            200.0, // These two downloads will share the same loading bar
+            "Downloading pack file",
+        )
+        .await?;
+
+        // download in parallel, then join.
+        try_join!(
+            generate_pack_from_curseforge_id(
+                mod_id,
+                file_id,
+                profile.name.clone(),
+                None,
+                profile_path.to_string(),
+                Some(shared_loading_bar.clone())
+            ),
+            generate_pack_from_curseforge_id(
+                mod_id,
+                new_file_id,
+                profile.name.clone(),
+                None,
+                profile_path.to_string(),
+                Some(shared_loading_bar)
+            )
+        )?
+    } else {
+        // If new_file_id is None, we don't need to download the new pack, so we clone the old one
+        let mut old_pack_creator = generate_pack_from_curseforge_id(
+            mod_id,
+            file_id,
+            profile.name.clone(),
+            None,
+            profile_path.to_string(),
+            None,
+        )
+        .await?;
+        old_pack_creator.description.existing_loading_bar = None;
+        (old_pack_creator.clone(), old_pack_creator)
+    };
+
+    let mut pack_paths = pack::install_curseforge::get_curseforge_pack_paths(
+        &old_pack_creator.file,
+    )
+    .await?;
+    let operation = if new_file_id.is_some() {
+        pack_paths.extend(
+            pack::install_curseforge::get_curseforge_pack_paths(
+                &new_pack_creator.file,
+            )
+            .await?,
+        );
+        RollbackOperation::UpdateModpack
+    } else {
+        RollbackOperation::RepairModpack
+    };
+    create_rollback_snapshot(profile_path, operation, pack_paths).await?;
+
+    // Removal - remove the files the old pack added that the new one doesn't
+    // have, keeping the ones the user added
+    pack::install_curseforge::remove_related_curseforge_files(
+        profile_path.to_string(),
+        old_pack_creator.file,
+        &new_pack_creator.file,
+    )
+    .await?;
+
+    // Reinstallation - install all files that are added by the new pack,
+    // and edit the profile to update the new data
+    let install =
+        pack::install_curseforge::install_zipped_curseforge_pack_files(
+            new_pack_creator,
+            ignore_lock,
+        )
+        .await?;
+
+    Ok(install.manual_downloads)
+}
@@ -0,0 +1,776 @@
+//! Incremental, deduplicated world backups
+//!
+//! Snapshots live in a content-addressed store at `<instance>/backups/.snapshots`.
+//! Region files are split along their chunk boundaries and every other file
+//! into fixed-size blobs, so a snapshot only adds the data that changed since
+//! the previous one and blobs are shared between all snapshots of an instance.
+
+use crate::api::worlds::{
+    find_available_name, get_world_dir, get_world_session_lock,
+    replace_world_files, try_get_world_session_lock,
+};
+use crate::profile::get_full_path;
+use crate::util::io::{self, IOError};
+use crate::util::snapshot_store::{self, SnapshotStore};
+use crate::{ErrorKind, Result, State};
+use async_walkdir::WalkDir;
+use chrono::{DateTime, Duration, Local, Utc};
+use dashmap::DashMap;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+pub use crate::state::world_backup_schedules::{
+    BackupRetention, WorldBackupSchedule,
+};
+
+const STORE_DIR: &str = ".snapshots";
+const BLOB_SIZE: usize = 4 * 1024 * 1024;
+const REGION_SECTOR_SIZE: usize = 4096;
+const REGION_HEADER_SIZE: usize = 2 * REGION_SECTOR_SIZE;
+
+// Garbage collection must never run while a snapshot is being written, as
+// freshly written blobs are not referenced by any manifest yet
+static STORE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+// Held per profile while its scheduled backups run
+static SCHEDULED_BACKUPS: LazyLock<
+    DashMap<String, Arc<tokio::sync::Mutex<()>>>,
+> = LazyLock::new(DashMap::new);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorldSnapshot {
+    pub id: String,
+    pub world: String,
+    pub created: DateTime<Utc>,
+    pub file_count: usize,
+    /// Size of the world at the time of the snapshot
+    pub size: u64,
+    /// Bytes this snapshot added to the store
+    pub added_size: u64,
+    /// Whether the world was open in game while the snapshot was taken
+    #[serde(default)]
+    pub live: bool,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct PruneResult {
+    pub removed_snapshots: Vec<String>,
+    pub freed_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotManifest {
+    #[serde(flatten)]
+    snapshot: WorldSnapshot,
+    files: Vec<SnapshotFile>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SnapshotFile {
+    path: String,
+    size: u64,
+    modified: i64,
+    blobs: Vec<String>,
+}
+
+impl snapshot_store::SnapshotManifest for SnapshotManifest {
+    fn created(&self) -> DateTime<Utc> {
+        self.snapshot.created
+    }
+
+    fn objects(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().flat_map(|x| &x.blobs).map(|x| x.as_str())
+    }
+}
+
+fn snapshot_store(instance: &Path) -> SnapshotStore {
+    SnapshotStore::new(
+        instance.join("backups").join(STORE_DIR),
+        "world snapshot",
+    )
+}
+
+/// Takes a new snapshot of a singleplayer world
+///
+/// Files whose size and modification time match the latest snapshot of the
+/// same world are not read again.
+pub async fn create_world_snapshot(
+    instance: &Path,
+    world: &str,
+) -> Result<WorldSnapshot> {
+    let world_dir = get_world_dir(instance, world);
+    let _lock = get_world_session_lock(&world_dir).await?;
+    snapshot_world(instance, world, false).await
+}
+
+/// Snapshots a world without taking its session lock. A live snapshot of a
+/// world that is open in game may catch files while they are being written.
+async fn snapshot_world(
+    instance: &Path,
+    world: &str,
+    live: bool,
+) -> Result<WorldSnapshot> {
+    let _store_lock = STORE_LOCK.lock().await;
+    let latest = read_latest_manifest(instance, world).await?;
+    write_world_snapshot(instance, world, live, latest).await
+}
+
+/// Snapshots a world like [`snapshot_world`] if `is_due`, given when its
+/// latest snapshot was taken. The check is made under the store lock, so a
+/// snapshot finishing in the meantime is taken into account.
+async fn snapshot_world_if_due(
+    instance: &Path,
+    world: &str,
+    live: bool,
+    is_due: impl FnOnce(Option<DateTime<Utc>>) -> bool,
+) -> Result<Option<WorldSnapshot>> {
+    let _store_lock = STORE_LOCK.lock().await;
+    let latest = read_latest_manifest(instance, world).await?;
+    if !is_due(latest.as_ref().map(|x| x.snapshot.created)) {
+        return Ok(None);
+    }
+    write_world_snapshot(instance, world, live, latest)
+        .await
+        .map(Some)
+}
+
+async fn read_latest_manifest(
+    instance: &Path,
+    world: &str,
+) -> Result<Option<SnapshotManifest>> {
+    Ok(snapshot_store(instance)
+        .read_manifests::<SnapshotManifest>()
+        .await?
+        .into_iter()
+        .find(|x| x.snapshot.world == world))
+}
+
+/// Writes a snapshot of a world, reusing the blobs of files unchanged since
+/// `previous`. Must be called with the store lock held
+async fn write_world_snapshot(
+    instance: &Path,
+    world: &str,
+    live: bool,
+    previous: Option<SnapshotManifest>,
+) -> Result<WorldSnapshot> {
+    let world_dir = get_world_dir(instance, world);
+    let store = snapshot_store(instance);
+
+    let previous_files = previous
+        .map(|x| {
+            x.files
+                .into_iter()
+                .map(|file| (file.path.clone(), file))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    let mut files = Vec::new();
+    let mut size = 0;
+    let mut added_size = 0;
+
+    let mut walker = WalkDir::new(&world_dir);
+    while let Some(entry) = walker.next().await {
+        let entry = entry.map_err(IOError::from_walkdir)?;
+        if !entry.file_type().await?.is_file() {
+            continue;
+        }
+        if entry.file_name() == "session.lock" {
+            continue;
+        }
+
+        let path = entry.path();
+        let relative_path = path
+            .strip_prefix(&world_dir)?
+            .display()
+            .to_string()
+            .replace('\\', "/");
+        let metadata = io::metadata(&path).await?;
+        let modified = metadata
+            .modified()
+            .map(|x| DateTime::<Utc>::from(x).timestamp_millis())
+            .unwrap_or_default();
+        size += metadata.len();
+
+        if let Some(previous) = previous_files.get(&relative_path)
+            && previous.size == metadata.len()
+            && previous.modified == modified
+            && previous
+                .blobs
+                .iter()
+                .all(|hash| store.object_path(hash).exists())
+        {
+            files.push(previous.clone());
+            continue;
+        }
+
+        let data = io::read(&path).await?;
+        let is_region = is_region_file(&relative_path);
+        let mut blobs = Vec::new();
+        for range in split_file(&data, is_region) {
+            let (hash, added) = store.write_object(&data[range]).await?;
+            added_size += added;
+            blobs.push(hash);
+        }
+
+        files.push(SnapshotFile {
+            path: relative_path,
+            size: data.len() as u64,
+            modified,
+            blobs,
+        });
+    }
+
+    let snapshot = WorldSnapshot {
+        id: store.new_id(world).await?,
+        world: world.to_string(),
+        created: Utc::now(),
+        file_count: files.len(),
+        size,
+        added_size,
+        live,
+    };
+    let manifest = SnapshotManifest {
+        snapshot: snapshot.clone(),
+        files,
+    };
+    store.write_manifest(&snapshot.id, &manifest).await?;
+
+    Ok(snapshot)
+}
+
+/// Lists the snapshots of an instance, newest first, optionally only
+/// those of a single world
+pub async fn list_world_snapshots(
+    instance: &Path,
+    world: Option<&str>,
+) -> Result<Vec<WorldSnapshot>> {
+    Ok(snapshot_store(instance)
+        .read_manifests::<SnapshotManifest>()
+        .await?
+        .into_iter()
+        .map(|x| x.snapshot)
+        .filter(|x| world.is_none_or(|world| x.world == world))
+        .collect())
+}
+
+/// Restores a snapshot, returning the name of the restored world's folder
+///
+/// When `as_new_world` is set the snapshot is restored next to the original
+/// world under a free name, otherwise the original world is replaced.
+pub async fn restore_world_snapshot(
+    instance: &Path,
+    snapshot_id: &str,
+    as_new_world: bool,
+) -> Result<String> {
+    // Held throughout, so no blob can be collected once found
+    let _store_lock = STORE_LOCK.lock().await;
+    let store = snapshot_store(instance);
+    let manifest: SnapshotManifest = store.read_manifest(snapshot_id).await?;
+
+    if let Some(missing) =
+        store.find_missing(snapshot_store::SnapshotManifest::objects(&manifest))
+    {
+        return Err(ErrorKind::OtherError(format!(
+            "World snapshot {snapshot_id} is damaged, blob {missing} is missing"
+        ))
+        .into());
+    }
+
+    let saves_dir = instance.join("saves");
+    let world = if as_new_world {
+        find_available_name(&saves_dir, &manifest.snapshot.world, "")
+    } else {
+        manifest.snapshot.world.clone()
+    };
+    let world_dir = get_world_dir(instance, &world);
+    io::create_dir_all(&world_dir).await?;
+    let _lock = get_world_session_lock(&world_dir).await?;
+
+    // Restore next to the snapshots first, so an unreadable blob never
+    // leaves the world half replaced
+    let backups_dir = instance.join("backups");
+    let staging_dir = tempfile::TempDir::new_in(&backups_dir)
+        .map_err(|e| IOError::with_path(e, &backups_dir))?;
+    for file in &manifest.files {
+        let mut data = Vec::with_capacity(file.size as usize);
+        for hash in &file.blobs {
+            data.extend(store.read_object(hash).await?);
+        }
+        let path = staging_dir.path().join(&file.path);
+        if let Some(parent) = path.parent() {
+            io::create_dir_all(parent).await?;
+        }
+        io::write(path, data).await?;
+    }
+
+    replace_world_files(&world_dir, staging_dir.path()).await?;
+
+    Ok(world)
+}
+
+/// Deletes a snapshot and every blob only it referenced
+pub async fn delete_world_snapshot(
+    instance: &Path,
+    snapshot_id: &str,
+) -> Result<u64> {
+    let _store_lock = STORE_LOCK.lock().await;
+    let store = snapshot_store(instance);
+    store.read_manifest::<SnapshotManifest>(snapshot_id).await?;
+
+    store.remove_manifest(snapshot_id).await?;
+    store.collect_garbage::<SnapshotManifest>().await
+}
+
+/// Deletes all but the newest `keep_last` snapshots of a world, then frees
+/// the blobs no remaining snapshot references. The newest snapshot is
+/// always kept.
+pub async fn prune_world_snapshots(
+    instance: &Path,
+    world: &str,
+    keep_last: usize,
+) -> Result<PruneResult> {
+    apply_retention(
+        instance,
+        world,
+        &BackupRetention {
+            keep_last: Some(keep_last as u32),
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+/// Deletes the snapshots of a world that the retention rules do not keep,
+/// then frees the blobs no remaining snapshot references
+pub async fn apply_retention(
+    instance: &Path,
+    world: &str,
+    retention: &BackupRetention,
+) -> Result<PruneResult> {
+    let _store_lock = STORE_LOCK.lock().await;
+    let store = snapshot_store(instance);
+
+    let manifests = store
+        .read_manifests::<SnapshotManifest>()
+        .await?
+        .into_iter()
+        .filter(|x| x.snapshot.world == world)
+        .collect::<Vec<_>>();
+    let mut keep = select_retained(
+        &manifests
+            .iter()
+            .map(|x| x.snapshot.created)
+            .collect::<Vec<_>>(),
+        retention,
+        Utc::now(),
+    );
+
+    if let Some(max_total_size) = retention.max_total_size {
+        let mut blob_sizes = HashMap::new();
+        for hash in manifests
+            .iter()
+            .flat_map(|x| &x.files)
+            .flat_map(|x| &x.blobs)
+        {
+            if !blob_sizes.contains_key(hash) {
+                let size = io::metadata(store.object_path(hash))
+                    .await
+                    .map(|x| x.len())
+                    .unwrap_or_default();
+                blob_sizes.insert(hash, size);
+            }
+        }
+
+        loop {
+            let kept_size = manifests
+                .iter()
+                .zip(&keep)
+                .filter(|(_, keep)| **keep)
+                .flat_map(|(x, _)| &x.files)
+                .flat_map(|x| &x.blobs)
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .map(|hash| blob_sizes.get(hash).copied().unwrap_or_default())
+                .sum::<u64>();
+            if kept_size <= max_total_size {
+                break;
+            }
+
+            match keep.iter().rposition(|x| *x) {
+                Some(oldest) if oldest > 0 => keep[oldest] = false,
+                _ => break,
+            }
+        }
+    }
+
+    let mut removed_snapshots = Vec::new();
+    for (manifest, keep) in manifests.into_iter().zip(keep) {
+        if keep {
+            continue;
+        }
+        store.remove_manifest(&manifest.snapshot.id).await?;
+        removed_snapshots.push(manifest.snapshot.id);
+    }
+
+    Ok(PruneResult {
+        removed_snapshots,
+        freed_bytes: store.collect_garbage::<SnapshotManifest>().await?,
+    })
+}
+
+/// Marks which snapshots the count based retention rules keep, given their
+/// creation times from newest to oldest
+fn select_retained(
+    created: &[DateTime<Utc>],
+    retention: &BackupRetention,
+    now: DateTime<Utc>,
+) -> Vec<bool> {
+    if retention.keep_last.is_none() && retention.keep_daily_days.is_none() {
+        return vec![true; created.len()];
+    }
+
+    let mut keep = vec![false; created.len()];
+    if let Some(keep_last) = retention.keep_last {
+        keep.iter_mut()
+            .take(keep_last as usize)
+            .for_each(|x| *x = true);
+    }
+    if let Some(days) = retention.keep_daily_days {
+        let mut seen_days = HashSet::new();
+        for (i, created) in created.iter().enumerate() {
+            if now.signed_duration_since(*created) > Duration::days(days as i64)
+            {
+                continue;
+            }
+            if seen_days.insert(created.with_timezone(&Local).date_naive()) {
+                keep[i] = true;
+            }
+        }
+    }
+    if let Some(newest) = keep.first_mut() {
+        *newest = true;
+    }
+
+    keep
+}
+
+/// Gets the backup schedule of a world, or the instance-wide schedule if
+/// `world` is `None`
+pub async fn get_world_backup_schedule(
+    instance: &str,
+    world: Option<&str>,
+) -> Result<Option<WorldBackupSchedule>> {
+    let state = State::get().await?;
+    WorldBackupSchedule::get(instance, world, &state.pool).await
+}
+
+/// Sets or, when `schedule` is `None`, removes the backup schedule of a
+/// world or the instance-wide schedule if `world` is `None`
+pub async fn set_world_backup_schedule(
+    instance: &str,
+    world: Option<&str>,
+    schedule: Option<WorldBackupSchedule>,
+) -> Result<()> {
+    let state = State::get().await?;
+    match schedule {
+        Some(schedule) => schedule.upsert(instance, world, &state.pool).await,
+        None => WorldBackupSchedule::remove(instance, world, &state.pool).await,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BackupTrigger {
+    /// The game of the instance is running
+    Running,
+    /// The game of the instance exited
+    Exited,
+}
+
+/// Takes the snapshots the backup schedules of an instance are due for
+///
+/// While the game is running only the world it has open (whose session lock
+/// is held) is considered. Once it exits, every world saved since
+/// `session_start` is.
+pub(crate) async fn run_scheduled_backups(
+    profile_path: &str,
+    trigger: BackupTrigger,
+    session_start: DateTime<Utc>,
+) -> Result<()> {
+    // A check while the game runs is skipped if the previous one is still
+    // taking its snapshots, the check on exit waits for it
+    let running = SCHEDULED_BACKUPS
+        .entry(profile_path.to_string())
+        .or_default()
+        .clone();
+    let _running = match trigger {
+        BackupTrigger::Running => match running.try_lock_owned() {
+            Ok(guard) => guard,
+            Err(_) => return Ok(()),
+        },
+        BackupTrigger::Exited => running.lock_owned().await,
+    };
+
+    let state = State::get().await?;
+    let schedules =
+        WorldBackupSchedule::get_all_for_instance(profile_path, &state.pool)
+            .await?;
+    if schedules.is_empty() {
+        return Ok(());
+    }
+
+    let instance = get_full_path(profile_path).await?;
+    let saves_dir = instance.join("saves");
+    if !saves_dir.is_dir() {
+        return Ok(());
+    }
+
+    let mut latest_snapshots = HashMap::new();
+    for snapshot in list_world_snapshots(&instance, None).await? {
+        latest_snapshots
+            .entry(snapshot.world)
+            .or_insert(snapshot.created);
+    }
+
+    let now = Utc::now();
+    let mut dir = io::read_dir(&saves_dir).await?;
+    while let Some(entry) = dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, &saves_dir))?
+    {
+        if !entry.file_type().await?.is_dir() {
+            continue;
+        }
+        let world = entry.file_name().to_string_lossy().to_string();
+        let Some(schedule) = schedules
+            .get(&Some(world.clone()))
+            .or_else(|| schedules.get(&None))
+        else {
+            continue;
+        };
+        let Ok(level_dat) = io::metadata(entry.path().join("level.dat")).await
+        else {
+            continue;
+        };
+        let last_saved = level_dat
+            .modified()
+            .map(DateTime::<Utc>::from)
+            .unwrap_or(now);
+
+        let is_due = |latest: Option<DateTime<Utc>>| {
+            let due_daily = schedule.daily
+                && latest.is_none_or(|x| {
+                    now.signed_duration_since(x) >= Duration::days(1)
+                        && last_saved > x
+                });
+            let due_trigger = match trigger {
+                BackupTrigger::Exited => {
+                    schedule.on_exit && last_saved >= session_start
+                }
+                BackupTrigger::Running => {
+                    schedule.interval_minutes.is_some_and(|minutes| {
+                        latest.is_none_or(|x| {
+                            now.signed_duration_since(x)
+                                >= Duration::minutes(minutes as i64)
+                        })
+                    })
+                }
+            };
+            due_daily || due_trigger
+        };
+        // Checked again once the store is locked, this skips the worlds that
+        // are not due without touching them
+        if !is_due(latest_snapshots.get(&world).copied()) {
+            continue;
+        }
+
+        let result = match trigger {
+            BackupTrigger::Exited => {
+                match get_world_session_lock(&entry.path()).await {
+                    Ok(_lock) => {
+                        snapshot_world_if_due(&instance, &world, false, is_due)
+                            .await
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            BackupTrigger::Running => {
+                // Only the world the game has open is snapshotted while it
+                // runs, the others can wait for it to exit
+                if try_get_world_session_lock(&entry.path()).await?.is_some() {
+                    continue;
+                }
+                snapshot_world_if_due(&instance, &world, true, is_due).await
+            }
+        };
+
+        match result {
+            Ok(Some(snapshot)) => {
+                tracing::info!(
+                    "Took scheduled snapshot {} of world {world}",
+                    snapshot.id
+                );
+                if !schedule.retention.is_empty()
+                    && let Err(e) =
+                        apply_retention(&instance, &world, &schedule.retention)
+                            .await
+                {
+                    tracing::warn!(
+                        "Failed to apply backup retention for world {world}: {e}"
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to take scheduled snapshot of world {world}: {e}"
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_region_file(relative_path: &str) -> bool {
+    relative_path.ends_with(".mca") || relative_path.ends_with(".mcr")
+}
+
+/// Splits a file into the byte ranges that are stored as separate blobs
+///
+/// Region files are split into their header, every chunk and the gaps in
+/// between, so unchanged chunks keep their hash. Anything else, including
+/// region files with a damaged header, is split into fixed-size blobs.
+fn split_file(data: &[u8], is_region: bool) -> Vec<Range<usize>> {
+    if is_region && let Some(ranges) = split_region_file(data) {
+        return ranges;
+    }
+
+    (0..data.len())
+        .step_by(BLOB_SIZE)
+        .map(|start| start..(start + BLOB_SIZE).min(data.len()))
+        .collect()
+}
+
+fn split_region_file(data: &[u8]) -> Option<Vec<Range<usize>>> {
+    if data.len() < REGION_HEADER_SIZE {
+        return None;
+    }
+
+    let mut chunks = data[..REGION_SECTOR_SIZE]
+        .chunks_exact(4)
+        .filter_map(|entry| {
+            let offset =
+                u32::from_be_bytes([0, entry[0], entry[1], entry[2]]) as usize;
+            let sectors = entry[3] as usize;
+            if offset == 0 || sectors == 0 {
+                return None;
+            }
+
+            let start = offset * REGION_SECTOR_SIZE;
+            let end = (start + sectors * REGION_SECTOR_SIZE).min(data.len());
+            Some(start..end)
+        })
+        .collect::<Vec<_>>();
+    chunks.sort_by_key(|x| x.start);
+
+    let mut ranges = Vec::with_capacity(chunks.len() * 2 + 1);
+    ranges.push(0..REGION_HEADER_SIZE);
+    let mut position = REGION_HEADER_SIZE;
+    for chunk in chunks {
+        if chunk.start < position || chunk.start >= data.len() {
+            return None;
+        }
+        if chunk.start > position {
+            ranges.push(position..chunk.start);
+        }
+        position = chunk.end;
+        ranges.push(chunk);
+    }
+    if position < data.len() {
+        ranges.push(position..data.len());
+    }
+
+    Some(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn region_file(entries: &[(u32, u8)], sectors: usize) -> Vec<u8> {
+        let mut data = vec![0; sectors * REGION_SECTOR_SIZE];
+        for (i, (offset, count)) in entries.iter().enumerate() {
+            let offset = offset.to_be_bytes();
+            data[i * 4..i * 4 + 4]
+                .copy_from_slice(&[offset[1], offset[2], offset[3], *count]);
+        }
+        data
+    }
+
+    #[test]
+    fn region_file_is_split_on_chunk_boundaries() {
+        let data = region_file(&[(4, 1), (2, 2)], 6);
+        let ranges = split_file(&data, true);
+
+        assert_eq!(
+            ranges,
+            vec![
+                0..REGION_HEADER_SIZE,
+                2 * REGION_SECTOR_SIZE..4 * REGION_SECTOR_SIZE,
+                4 * REGION_SECTOR_SIZE..5 * REGION_SECTOR_SIZE,
+                5 * REGION_SECTOR_SIZE..6 * REGION_SECTOR_SIZE,
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_region_chunks_fall_back_to_fixed_blobs() {
+        let data = region_file(&[(2, 2), (3, 1)], 4);
+        let ranges = split_file(&data, true);
+
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0], 0..data.len());
+    }
+
+    #[test]
+    fn retention_keeps_last_and_one_per_day() {
+        let now = Local
+            .with_ymd_and_hms(2026, 6, 10, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let created = [
+            now,
+            now - Duration::minutes(10),
+            now - Duration::minutes(20),
+            now - Duration::days(2),
+            now - Duration::days(2) - Duration::minutes(10),
+            now - Duration::days(10),
+        ];
+        let retention = BackupRetention {
+            keep_last: Some(2),
+            keep_daily_days: Some(7),
+            max_total_size: None,
+        };
+
+        assert_eq!(
+            select_retained(&created, &retention, now),
+            vec![true, true, false, true, false, false]
+        );
+    }
+
+    #[test]
+    fn ranges_cover_the_whole_file() {
+        let data = vec![1; BLOB_SIZE * 2 + 10];
+        let ranges = split_file(&data, false);
+
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges.last().unwrap().end, data.len());
+    }
+}
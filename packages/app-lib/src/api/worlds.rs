@@ -20,6 +20,7 @@ use either::Either;
 use enumset::{EnumSet, EnumSetType};
 use fs4::tokio::AsyncFileExt;
 use futures::StreamExt;
+use path_util::SafeRelativeUtf8UnixPathBuf;
 use quartz_nbt::{NbtCompound, NbtTag};
 use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
@@ -29,7 +30,9 @@ use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 use tokio::io::AsyncWriteExt;
 use tokio::task::JoinSet;
-use tokio_util::compat::FuturesAsyncWriteCompatExt;
+use tokio_util::compat::{
+    FuturesAsyncReadCompatExt, FuturesAsyncWriteCompatExt,
+};
 use url::Url;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -457,6 +460,11 @@ async fn get_server_worlds_in_profile(
 
 fn attach_world_data_to_world(world: &mut World, data: &AttachedWorldData) {
     world.display_status = data.display_status;
+    // The level data is only saved every so often, so a join reported by the
+    // running game can be more recent
+    if data.last_joined > world.last_played {
+        world.last_played = data.last_joined;
+    }
 }
 
 pub async fn set_world_display_status(
@@ -541,10 +549,7 @@ pub async fn backup_world(instance: &Path, world: &str) -> Result<u64> {
 
     let mut walker = WalkDir::new(&world_dir);
     while let Some(entry) = walker.next().await {
-        let entry = entry.map_err(|e| io::IOError::IOPathError {
-            path: e.path().unwrap().to_string_lossy().to_string(),
-            source: e.into_io().unwrap(),
-        })?;
+        let entry = entry.map_err(io::IOError::from_walkdir)?;
         if !entry.file_type().await?.is_file() {
             continue;
         }
@@ -576,7 +581,205 @@ pub async fn backup_world(instance: &Path, world: &str) -> Result<u64> {
     Ok(io::metadata(output_path).await?.len())
 }
 
-fn find_available_name(dir: &Path, file_name: &str, extension: &str) -> String {
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorldBackup {
+    /// File name of the backup in the instance's `backups` folder
+    pub file_name: String,
+    /// Folder name of the world the backup was made of
+    pub world: String,
+    pub created: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// Lists the zip backups made by [`backup_world`], newest first, optionally
+/// only those of a single world
+pub async fn list_world_backups(
+    instance: &Path,
+    world: Option<&str>,
+) -> Result<Vec<WorldBackup>> {
+    let backups_dir = instance.join("backups");
+    let mut backups = Vec::new();
+    if !backups_dir.is_dir() {
+        return Ok(backups);
+    }
+
+    let mut dir = io::read_dir(&backups_dir).await?;
+    while let Some(entry) = dir.next_entry().await? {
+        let path = entry.path();
+        if !entry.file_type().await?.is_file()
+            || path.extension().is_none_or(|x| x != "zip")
+        {
+            continue;
+        }
+
+        let backup_world = match read_world_backup_root(&path).await {
+            Ok(Some(backup_world)) => backup_world,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to read world backup {}: {e}",
+                    path.display()
+                );
+                continue;
+            }
+        };
+        if world.is_some_and(|world| world != backup_world) {
+            continue;
+        }
+
+        let metadata = entry.metadata().await?;
+        backups.push(WorldBackup {
+            file_name: entry.file_name().to_string_lossy().to_string(),
+            world: backup_world,
+            created: metadata
+                .modified()
+                .map(DateTime::<Utc>::from)
+                .unwrap_or_default(),
+            size: metadata.len(),
+        });
+    }
+
+    backups.sort_by_key(|x| Reverse(x.created));
+    Ok(backups)
+}
+
+/// Restores a zip backup made by [`backup_world`], returning the name of the
+/// restored world's folder
+///
+/// When `as_new_world` is set the backup is restored next to the original
+/// world under a free name, otherwise the original world is replaced. This
+/// fails if the world is currently open in Minecraft.
+pub async fn restore_world_backup(
+    instance: &Path,
+    file_name: &str,
+    as_new_world: bool,
+) -> Result<String> {
+    let backups_dir = instance.join("backups");
+    let backup_path = backups_dir.join(file_name);
+    if Path::new(file_name)
+        .file_name()
+        .is_none_or(|x| x != file_name)
+        || !backup_path.is_file()
+    {
+        return Err(ErrorKind::InputError(format!(
+            "Unknown world backup {file_name}"
+        ))
+        .into());
+    }
+
+    let Some(backup_world) = read_world_backup_root(&backup_path).await? else {
+        return Err(ErrorKind::InputError(format!(
+            "World backup {file_name} does not contain a level.dat"
+        ))
+        .into());
+    };
+
+    let saves_dir = instance.join("saves");
+    let world = if as_new_world {
+        find_available_name(&saves_dir, &backup_world, "")
+    } else {
+        backup_world.clone()
+    };
+    let world_dir = get_world_dir(instance, &world);
+    io::create_dir_all(&world_dir).await?;
+    let Some(_lock) = try_get_world_session_lock(&world_dir).await? else {
+        return Err(ErrorKind::InputError(format!(
+            "World {world} is open in Minecraft, close it before restoring a backup"
+        ))
+        .into());
+    };
+
+    // Extract next to the backups first, so a damaged zip never leaves the
+    // world half replaced
+    let staging_dir = tempfile::TempDir::new_in(&backups_dir)
+        .map_err(|e| io::IOError::with_path(e, &backups_dir))?;
+    let zip =
+        async_zip::tokio::read::fs::ZipFileReader::new(&backup_path).await?;
+    let prefix = format!("{backup_world}/");
+    for (index, entry) in zip.file().entries().iter().enumerate() {
+        let Ok(filename) = entry.filename().as_str() else {
+            continue;
+        };
+        let Some(relative_path) = filename.strip_prefix(&prefix) else {
+            continue;
+        };
+        if relative_path.is_empty()
+            || filename.ends_with('/')
+            || relative_path == "session.lock"
+        {
+            continue;
+        }
+        let relative_path =
+            SafeRelativeUtf8UnixPathBuf::try_from(relative_path.to_string())?;
+
+        let path = staging_dir.path().join(relative_path.as_str());
+        if let Some(parent) = path.parent() {
+            io::create_dir_all(parent).await?;
+        }
+        let mut reader = zip.reader_with_entry(index).await?.compat();
+        let mut writer = tokio::fs::File::create(&path)
+            .await
+            .map_err(|e| io::IOError::with_path(e, &path))?;
+        tokio::io::copy(&mut reader, &mut writer)
+            .await
+            .map_err(|e| io::IOError::with_path(e, &path))?;
+    }
+
+    replace_world_files(&world_dir, staging_dir.path()).await?;
+
+    Ok(world)
+}
+
+/// Replaces a world with the one restored to `staging_dir`. The old world is
+/// moved aside until the new one is in place, and moved back if that fails
+pub(crate) async fn replace_world_files(
+    world_dir: &Path,
+    staging_dir: &Path,
+) -> Result<()> {
+    let mut old_name = world_dir.file_name().unwrap_or_default().to_owned();
+    old_name.push(".restore-old");
+    let old_dir = world_dir.with_file_name(old_name);
+
+    // Left behind by a restore that was interrupted after the swap
+    if old_dir.exists() {
+        io::remove_dir_all(&old_dir).await?;
+    }
+
+    io::rename_or_move(world_dir, &old_dir).await?;
+    if let Err(err) = io::rename_or_move(staging_dir, world_dir).await {
+        if world_dir.exists() {
+            io::remove_dir_all(world_dir).await?;
+        }
+        io::rename_or_move(&old_dir, world_dir).await?;
+        return Err(err.into());
+    }
+
+    // The world is restored at this point, so a leftover copy is only logged
+    if let Err(err) = io::remove_dir_all(&old_dir).await {
+        tracing::warn!(
+            "Failed to remove the replaced world {}: {err}",
+            old_dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Finds the world folder inside a backup zip, which is the folder that
+/// directly contains a `level.dat`
+async fn read_world_backup_root(path: &Path) -> Result<Option<String>> {
+    let zip = async_zip::tokio::read::fs::ZipFileReader::new(path).await?;
+    Ok(zip.file().entries().iter().find_map(|entry| {
+        let (world, file) = entry.filename().as_str().ok()?.split_once('/')?;
+        (file == "level.dat" && !world.is_empty()).then(|| world.to_string())
+    }))
+}
+
+pub(crate) fn find_available_name(
+    dir: &Path,
+    file_name: &str,
+    extension: &str,
+) -> String {
     static RESERVED_WINDOWS_FILENAMES: LazyLock<Regex> = LazyLock::new(|| {
         RegexBuilder::new(r#"^.*\.|(?:COM|CLOCK\$|CON|PRN|AUX|NUL|COM[1-9]|LPT[1-9])(?:\..*)?$"#)
             .case_insensitive(true)
@@ -666,11 +869,13 @@ pub async fn delete_world(instance: &Path, world: &str) -> Result<()> {
     Ok(())
 }
 
-fn get_world_dir(instance: &Path, world: &str) -> PathBuf {
+pub(crate) fn get_world_dir(instance: &Path, world: &str) -> PathBuf {
     instance.join("saves").join(world)
 }
 
-async fn get_world_session_lock(world: &Path) -> Result<tokio::fs::File> {
+pub(crate) async fn get_world_session_lock(
+    world: &Path,
+) -> Result<tokio::fs::File> {
     let lock_path = world.join("session.lock");
     let mut file = tokio::fs::File::options()
         .create(true)
@@ -693,7 +898,7 @@ async fn get_world_session_lock(world: &Path) -> Result<tokio::fs::File> {
     })
 }
 
-async fn try_get_world_session_lock(
+pub(crate) async fn try_get_world_session_lock(
     world: &Path,
 ) -> Result<Option<tokio::fs::File>> {
     let file = tokio::fs::File::options()
@@ -916,3 +1121,90 @@ pub async fn get_server_status(
     )
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn write_zip(path: &Path, files: &[&str]) {
+        let file = tokio::fs::File::create(path).await.unwrap();
+        let mut writer =
+            async_zip::tokio::write::ZipFileWriter::with_tokio(file);
+        for name in files {
+            writer
+                .write_entry_whole(
+                    ZipEntryBuilder::new(
+                        name.to_string().into(),
+                        Compression::Deflate,
+                    ),
+                    b"data",
+                )
+                .await
+                .unwrap();
+        }
+        writer.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn backup_root_is_the_folder_with_level_dat() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let backup = dir.path().join("backup.zip");
+        write_zip(&backup, &["World/region/r.0.0.mca", "World/level.dat"])
+            .await;
+        assert_eq!(
+            read_world_backup_root(&backup).await.unwrap().as_deref(),
+            Some("World")
+        );
+
+        let not_a_world = dir.path().join("not-a-world.zip");
+        write_zip(&not_a_world, &["World/region/r.0.0.mca", "level.dat"]).await;
+        assert_eq!(read_world_backup_root(&not_a_world).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn replacing_a_world_swaps_the_whole_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let world_dir = dir.path().join("World");
+        let staging_dir = dir.path().join("staging");
+        io::create_dir_all(world_dir.join("region")).await.unwrap();
+        io::write(world_dir.join("region/r.0.0.mca"), b"old")
+            .await
+            .unwrap();
+        io::create_dir_all(&staging_dir).await.unwrap();
+        io::write(staging_dir.join("level.dat"), b"new")
+            .await
+            .unwrap();
+
+        replace_world_files(&world_dir, &staging_dir).await.unwrap();
+
+        assert_eq!(
+            io::read(world_dir.join("level.dat")).await.unwrap(),
+            b"new"
+        );
+        assert!(!world_dir.join("region").exists());
+        assert!(!staging_dir.exists());
+        assert!(!dir.path().join("World.restore-old").exists());
+    }
+
+    #[tokio::test]
+    async fn failed_world_replacement_keeps_the_old_world() {
+        let dir = tempfile::tempdir().unwrap();
+        let world_dir = dir.path().join("World");
+        io::create_dir_all(&world_dir).await.unwrap();
+        io::write(world_dir.join("level.dat"), b"old")
+            .await
+            .unwrap();
+
+        assert!(
+            replace_world_files(&world_dir, &dir.path().join("missing"))
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            io::read(world_dir.join("level.dat")).await.unwrap(),
+            b"old"
+        );
+        assert!(!dir.path().join("World.restore-old").exists());
+    }
+}
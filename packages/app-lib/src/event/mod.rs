@@ -163,6 +163,14 @@ pub enum LoadingBarType {
         profile_path: String,
         profile_name: String,
     },
+    ProfileVerify {
+        profile_path: String,
+        profile_name: String,
+    },
+    ProfileExport {
+        profile_path: String,
+        profile_name: String,
+    },
     ZipExtract {
         profile_path: String,
         profile_name: String,
@@ -227,6 +235,27 @@ pub struct ProcessPayload {
 pub enum ProcessPayloadType {
     Launched,
     Finished,
+    /// The game joined a singleplayer world, identified by its folder name
+    WorldJoined {
+        world: String,
+    },
+    /// The game left the world or server it was playing on
+    WorldLeft,
+    ServerJoined {
+        host: String,
+        port: u16,
+    },
+    FpsUpdated {
+        fps: u32,
+    },
+    ModsLoaded {
+        mods: Vec<crate::state::LoadedMod>,
+    },
+    /// A stop of the process finished, either by the game quitting after
+    /// being asked to or by killing it
+    Stopped {
+        method: crate::state::StopMethod,
+    },
 }
 
 #[derive(Serialize, Clone)]
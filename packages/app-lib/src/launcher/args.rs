@@ -2,9 +2,9 @@
 use crate::launcher::quick_play_version::QuickPlayServerVersion;
 use crate::launcher::{QuickPlayVersion, parse_rules};
 use crate::profile::QuickPlayType;
-use crate::state::Credentials;
+use crate::state::{Credentials, MaybeOnlineMinecraftProfile};
 use crate::{
-    state::{MemorySettings, WindowSize},
+    state::{GarbageCollector, JvmPreset, MemorySettings, WindowSize},
     util::{io::IOError, platform::classpath_separator},
 };
 use daedalus::minecraft::LoggingConfiguration;
@@ -108,6 +108,129 @@ pub fn get_lib_path(
     Ok(path.to_string_lossy().to_string())
 }
 
+// Heap sizing used when a profile asks for automatic memory
+const AUTO_MEMORY_BASE: u32 = 2048;
+const AUTO_MEMORY_PER_MOD: u32 = 32;
+const AUTO_MEMORY_STEP: u32 = 512;
+const AUTO_MEMORY_FLOOR: u32 = 1024;
+const AUTO_MEMORY_CEILING: u32 = 16384;
+
+/// Picks a maximum heap size in MB from the number of installed mods, never
+/// using more than three quarters of the system memory.
+pub fn automatic_memory(mod_count: usize, system_memory_mb: u64) -> u32 {
+    let wanted = AUTO_MEMORY_BASE
+        .saturating_add(AUTO_MEMORY_PER_MOD.saturating_mul(mod_count as u32));
+    let wanted = wanted.div_ceil(AUTO_MEMORY_STEP) * AUTO_MEMORY_STEP;
+
+    let available =
+        (system_memory_mb.saturating_mul(3) / 4).min(u32::MAX as u64) as u32;
+
+    wanted
+        .min(AUTO_MEMORY_CEILING)
+        .min(available)
+        .max(AUTO_MEMORY_FLOOR)
+}
+
+// https://docs.papermc.io/paper/aikars-flags
+fn aikar_flags(maximum: u32) -> Vec<String> {
+    let large_heap = maximum >= 12288;
+    let (new_size, max_new_size, region_size, reserve, ihop) = if large_heap {
+        (40, 50, "16M", 15, 20)
+    } else {
+        (30, 40, "8M", 20, 15)
+    };
+
+    [
+        "-XX:+UnlockExperimentalVMOptions".to_string(),
+        "-XX:+ParallelRefProcEnabled".to_string(),
+        "-XX:MaxGCPauseMillis=200".to_string(),
+        "-XX:+DisableExplicitGC".to_string(),
+        "-XX:+AlwaysPreTouch".to_string(),
+        format!("-XX:G1NewSizePercent={new_size}"),
+        format!("-XX:G1MaxNewSizePercent={max_new_size}"),
+        format!("-XX:G1HeapRegionSize={region_size}"),
+        format!("-XX:G1ReservePercent={reserve}"),
+        "-XX:G1HeapWastePercent=5".to_string(),
+        "-XX:G1MixedGCCountTarget=4".to_string(),
+        format!("-XX:InitiatingHeapOccupancyPercent={ihop}"),
+        "-XX:G1MixedGCLiveThresholdPercent=90".to_string(),
+        "-XX:G1RSetUpdatingPauseTimePercent=5".to_string(),
+        "-XX:SurvivorRatio=32".to_string(),
+        "-XX:+PerfDisableSharedMem".to_string(),
+        "-XX:MaxTenuringThreshold=1".to_string(),
+    ]
+    .into()
+}
+
+/// Builds the heap size, garbage collector and preset flags. Collectors the
+/// given Java version doesn't support are left out, so the JVM default is
+/// used instead of failing to start.
+pub fn get_memory_arguments(
+    memory: MemorySettings,
+    java_major_version: u32,
+) -> Vec<String> {
+    let mut arguments = vec![format!("-Xmx{}M", memory.maximum)];
+
+    let minimum = memory
+        .minimum
+        .or(memory.preset.map(|_| memory.maximum))
+        .map(|x| x.min(memory.maximum));
+    if let Some(minimum) = minimum {
+        arguments.push(format!("-Xms{minimum}M"));
+    }
+
+    let garbage_collector = match memory.garbage_collector {
+        Some(GarbageCollector::Zgc) if java_major_version < 15 => {
+            tracing::warn!(
+                "ZGC requires Java 15 or newer, but Java {java_major_version} is being used. Falling back to the default garbage collector"
+            );
+            None
+        }
+        Some(GarbageCollector::Shenandoah) if java_major_version < 12 => {
+            tracing::warn!(
+                "Shenandoah requires Java 12 or newer, but Java {java_major_version} is being used. Falling back to the default garbage collector"
+            );
+            None
+        }
+        x => x,
+    };
+
+    match garbage_collector {
+        Some(GarbageCollector::G1) => {
+            arguments.push("-XX:+UseG1GC".to_string())
+        }
+        Some(GarbageCollector::Zgc) => {
+            arguments.push("-XX:+UseZGC".to_string());
+            // Generational ZGC is opt-in on 21 and 22, and the default after
+            if (21..23).contains(&java_major_version) {
+                arguments.push("-XX:+ZGenerational".to_string());
+            }
+        }
+        Some(GarbageCollector::Shenandoah) => {
+            arguments.push("-XX:+UseShenandoahGC".to_string())
+        }
+        None => {}
+    }
+
+    match memory.preset {
+        Some(JvmPreset::Aikar) => {
+            if matches!(garbage_collector, None | Some(GarbageCollector::G1)) {
+                if garbage_collector.is_none() {
+                    arguments.push("-XX:+UseG1GC".to_string());
+                }
+                arguments.extend(aikar_flags(memory.maximum));
+            } else {
+                tracing::warn!(
+                    "Aikar's flags are tuned for G1, skipping them for the selected garbage collector"
+                );
+            }
+        }
+        None => {}
+    }
+
+    arguments
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn get_jvm_arguments(
     arguments: Option<&[Argument]>,
@@ -120,6 +243,7 @@ pub fn get_jvm_arguments(
     memory: MemorySettings,
     custom_args: Vec<String>,
     java_arch: &str,
+    java_major_version: u32,
     quick_play_type: &QuickPlayType,
     quick_play_version: QuickPlayVersion,
     log_config: Option<&LoggingConfiguration>,
@@ -159,7 +283,7 @@ pub fn get_jvm_arguments(
         parsed_arguments.push(class_paths.to_string());
     }
 
-    parsed_arguments.push(format!("-Xmx{}M", memory.maximum));
+    parsed_arguments.extend(get_memory_arguments(memory, java_major_version));
 
     if let Some(LoggingConfiguration::Log4j2Xml { argument, file }) = log_config
     {
@@ -266,9 +390,14 @@ pub async fn get_minecraft_arguments(
     java_arch: &str,
     quick_play_type: &QuickPlayType,
     quick_play_version: QuickPlayVersion,
+    offline: bool,
 ) -> crate::Result<Vec<String>> {
     let access_token = credentials.access_token.clone();
-    let profile = credentials.maybe_online_profile().await;
+    let profile = if offline {
+        MaybeOnlineMinecraftProfile::Offline(&credentials.offline_profile)
+    } else {
+        credentials.maybe_online_profile().await
+    };
     let mut parsed_arguments = Vec::new();
 
     if let Some(arguments) = arguments {
@@ -551,3 +680,40 @@ pub async fn get_processor_main_class(
 
     Ok(main_class)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_automatic_memory() {
+        // 2048 + 32 * 20 = 2688, rounded up to 3072
+        assert_eq!(automatic_memory(20, 32768), 3072);
+        assert_eq!(automatic_memory(0, 32768), 2048);
+        // capped at three quarters of system memory
+        assert_eq!(automatic_memory(300, 8192), 6144);
+        // never more than the ceiling, never less than the floor
+        assert_eq!(automatic_memory(1000, 131072), 16384);
+        assert_eq!(automatic_memory(0, 1024), 1024);
+    }
+
+    #[test]
+    fn test_memory_arguments() {
+        let mut memory = MemorySettings::with_maximum(4096);
+        assert_eq!(get_memory_arguments(memory, 21), vec!["-Xmx4096M"]);
+
+        memory.garbage_collector = Some(GarbageCollector::Zgc);
+        assert_eq!(
+            get_memory_arguments(memory, 21),
+            vec!["-Xmx4096M", "-XX:+UseZGC", "-XX:+ZGenerational"]
+        );
+        assert_eq!(get_memory_arguments(memory, 8), vec!["-Xmx4096M"]);
+
+        memory.garbage_collector = None;
+        memory.preset = Some(JvmPreset::Aikar);
+        let arguments = get_memory_arguments(memory, 17);
+        assert!(arguments.contains(&"-Xms4096M".to_string()));
+        assert!(arguments.contains(&"-XX:+UseG1GC".to_string()));
+        assert!(arguments.contains(&"-XX:G1HeapRegionSize=8M".to_string()));
+    }
+}
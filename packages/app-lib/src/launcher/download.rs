@@ -331,27 +331,14 @@ pub async fn download_libraries(
                     )
                     .await?;
 
-                    if let Ok(mut archive) =
-                        zip::ZipArchive::new(std::io::Cursor::new(&data))
-                    {
-                        match archive.extract(
-                            st.directories.version_natives_dir(version),
-                        ) {
-                            Ok(_) => tracing::debug!(
-                                "Fetched native {}",
-                                &library.name
-                            ),
-                            Err(err) => tracing::error!(
-                                "Failed extracting native {}. err: {err}",
-                                &library.name
-                            ),
-                        }
-                    } else {
-                        tracing::error!(
-                            "Failed extracting native {}",
-                            &library.name
-                        );
-                    }
+                    // Kept so `verify::verify_minecraft` can check its hash
+                    let path = st
+                        .directories
+                        .libraries_dir()
+                        .join(native_jar_path(library, &parsed_key, native)?);
+                    write(&path, &data, &st.io_semaphore).await?;
+
+                    extract_natives(st, version, &library.name, &data);
                 }
             } else {
                 let artifact_path = d::get_path_from_artifact(&library.name)?;
@@ -438,6 +425,42 @@ pub async fn download_libraries(
     Ok(())
 }
 
+/// Gets the path in the libraries directory a natives jar of a library is
+/// kept at
+pub(crate) fn native_jar_path(
+    library: &Library,
+    classifier: &str,
+    download: &d::minecraft::LibraryDownload,
+) -> crate::Result<String> {
+    match &download.path {
+        Some(path) => Ok(path.clone()),
+        None => Ok(d::get_path_from_artifact(&format!(
+            "{}:{classifier}",
+            library.name
+        ))?),
+    }
+}
+
+/// Extracts the natives jar of a library into the natives directory of a
+/// version
+pub(crate) fn extract_natives(
+    st: &State,
+    version: &str,
+    library_name: &str,
+    data: &[u8],
+) {
+    if let Ok(mut archive) = zip::ZipArchive::new(std::io::Cursor::new(data)) {
+        match archive.extract(st.directories.version_natives_dir(version)) {
+            Ok(_) => tracing::debug!("Fetched native {library_name}"),
+            Err(err) => tracing::error!(
+                "Failed extracting native {library_name}. err: {err}"
+            ),
+        }
+    } else {
+        tracing::error!("Failed extracting native {library_name}");
+    }
+}
+
 #[tracing::instrument(skip_all)]
 pub async fn download_log_config(
     st: &State,
@@ -0,0 +1,216 @@
+//! Launcher-side RPC methods called by the `theseus.jar` agent running in the
+//! game, reporting what the game is doing
+
+use crate::event::emit::{emit_process, emit_profile};
+use crate::event::{ProcessPayloadType, ProfilePayloadType};
+use crate::state::{GameServer, LoadedMod, attached_world_data};
+use crate::util::rpc::{HandlerMethod, RpcServerBuilder};
+use crate::worlds::WorldType;
+use crate::{ErrorKind, State};
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+struct GameProcess {
+    profile_path: String,
+    uuid: Uuid,
+}
+
+/// Registers the methods the game can call for the process with the given
+/// UUID
+pub(crate) fn register_handlers(
+    builder: RpcServerBuilder,
+    profile_path: &str,
+    uuid: Uuid,
+) -> RpcServerBuilder {
+    let process = Arc::new(GameProcess {
+        profile_path: profile_path.to_string(),
+        uuid,
+    });
+
+    builder
+        .handler(
+            "world_joined",
+            handler(process.clone(), |process, (world,)| {
+                world_joined(process, world)
+            }),
+        )
+        .handler(
+            "world_left",
+            handler(process.clone(), |process, ()| world_left(process)),
+        )
+        .handler(
+            "server_joined",
+            handler(process.clone(), |process, (host, port)| {
+                server_joined(process, host, port)
+            }),
+        )
+        .handler(
+            "fps",
+            handler(process.clone(), |process, (fps,)| {
+                fps_updated(process, fps)
+            }),
+        )
+        .handler(
+            "mods",
+            handler(process, |process, (mods,)| mods_loaded(process, mods)),
+        )
+}
+
+/// Wraps a method taking its arguments as a tuple, which is deserialized
+/// from the arguments array of the call
+fn handler<A, F, Fut>(process: Arc<GameProcess>, method: F) -> HandlerMethod
+where
+    A: DeserializeOwned,
+    F: Fn(Arc<GameProcess>, A) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = crate::Result<()>> + Send + 'static,
+{
+    Box::new(move |args| {
+        let args = serde_json::from_value(Value::Array(args));
+        let future = args.map(|args| method(process.clone(), args));
+        Box::pin(async move {
+            future
+                .map_err(|e| {
+                    ErrorKind::RpcError(format!("Invalid arguments: {e}"))
+                })?
+                .await?;
+            Ok(Value::Null)
+        })
+    })
+}
+
+async fn world_joined(
+    process: Arc<GameProcess>,
+    world: String,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    state
+        .process_manager
+        .update_game_status(process.uuid, |status| {
+            status.world = Some(world.clone());
+            status.server = None;
+        });
+
+    attached_world_data::set_last_joined(
+        &process.profile_path,
+        WorldType::Singleplayer,
+        &world,
+        Utc::now(),
+        &state.pool,
+    )
+    .await?;
+
+    emit_process(
+        &process.profile_path,
+        process.uuid,
+        ProcessPayloadType::WorldJoined {
+            world: world.clone(),
+        },
+        &format!("Joined world {world}"),
+    )
+    .await?;
+    emit_profile(
+        &process.profile_path,
+        ProfilePayloadType::WorldUpdated { world },
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn world_left(process: Arc<GameProcess>) -> crate::Result<()> {
+    let state = State::get().await?;
+    state
+        .process_manager
+        .update_game_status(process.uuid, |status| {
+            status.world = None;
+            status.server = None;
+        });
+
+    emit_process(
+        &process.profile_path,
+        process.uuid,
+        ProcessPayloadType::WorldLeft,
+        "Left world",
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn server_joined(
+    process: Arc<GameProcess>,
+    host: String,
+    port: u16,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    state
+        .process_manager
+        .update_game_status(process.uuid, |status| {
+            status.world = None;
+            status.server = Some(GameServer {
+                host: host.clone(),
+                port,
+            });
+        });
+
+    crate::state::record_server_join(
+        &process.profile_path,
+        &host,
+        port,
+        Utc::now(),
+    )
+    .await?;
+
+    emit_process(
+        &process.profile_path,
+        process.uuid,
+        ProcessPayloadType::ServerJoined {
+            host: host.clone(),
+            port,
+        },
+        &format!("Joined server {host}:{port}"),
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn fps_updated(process: Arc<GameProcess>, fps: u32) -> crate::Result<()> {
+    let state = State::get().await?;
+    state
+        .process_manager
+        .update_game_status(process.uuid, |status| status.fps = Some(fps));
+
+    emit_process(
+        &process.profile_path,
+        process.uuid,
+        ProcessPayloadType::FpsUpdated { fps },
+        &format!("{fps} FPS"),
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn mods_loaded(
+    process: Arc<GameProcess>,
+    mods: Vec<LoadedMod>,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    state
+        .process_manager
+        .update_game_status(process.uuid, |status| status.mods = mods.clone());
+
+    emit_process(
+        &process.profile_path,
+        process.uuid,
+        ProcessPayloadType::ModsLoaded { mods: mods.clone() },
+        &format!("Loaded {} mods", mods.len()),
+    )
+    .await?;
+
+    Ok(())
+}
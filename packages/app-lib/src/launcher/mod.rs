@@ -9,26 +9,42 @@ use crate::launcher::quick_play_version::{
 };
 use crate::profile::QuickPlayType;
 use crate::state::{
-    Credentials, JavaVersion, ProcessMetadata, ProfileInstallStage,
+    CacheBehaviour, ContentProvider, Credentials, JavaVersion, ProcessMetadata,
+    ProfileInstallStage, concurrent_logs_subfolder,
 };
+use crate::util::hooks::HookContext;
 use crate::util::io;
 use crate::util::rpc::RpcServerBuilder;
 use crate::{State, get_resource_file, process, state as st};
 use chrono::Utc;
 use daedalus as d;
-use daedalus::minecraft::{LoggingSide, RuleAction, VersionInfo};
+use daedalus::minecraft::{
+    LoggingConfiguration, LoggingSide, RuleAction, VersionInfo, VersionManifest,
+};
 use daedalus::modded::LoaderVersion;
 use regex::Regex;
 use serde::Deserialize;
 use st::Profile;
 use std::fmt::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::process::Command;
+use uuid::Uuid;
 
 mod args;
+mod game_rpc;
 
 pub mod download;
 pub mod quick_play_version;
+pub mod verify;
+
+// Entry points of dedicated servers, which take commands such as `stop` from
+// their console. Clients never read it
+const SERVER_MAIN_CLASSES: &[&str] = &[
+    "net.minecraft.server.Main",
+    "net.minecraft.server.MinecraftServer",
+    "net.fabricmc.loader.impl.launch.knot.KnotServer",
+    "org.quiltmc.loader.impl.launch.knot.KnotServer",
+];
 
 // All nones -> disallowed
 // 1+ true -> allowed
@@ -153,6 +169,16 @@ pub async fn get_loader_version_from_profile(
     game_version: &str,
     loader: ModLoader,
     loader_version: Option<&str>,
+) -> crate::Result<Option<LoaderVersion>> {
+    get_loader_version_with_cache(game_version, loader, loader_version, None)
+        .await
+}
+
+async fn get_loader_version_with_cache(
+    game_version: &str,
+    loader: ModLoader,
+    loader_version: Option<&str>,
+    cache_behaviour: Option<CacheBehaviour>,
 ) -> crate::Result<Option<LoaderVersion>> {
     if loader == ModLoader::Vanilla {
         return Ok(None);
@@ -166,8 +192,11 @@ pub async fn get_loader_version_from_profile(
         id => it.id == *id,
     };
 
-    let versions =
-        crate::api::metadata::get_loader_versions(loader.as_meta_str()).await?;
+    let versions = crate::api::metadata::get_loader_versions_with_cache(
+        loader.as_meta_str(),
+        cache_behaviour,
+    )
+    .await?;
 
     let loaders = versions.game_versions.into_iter().find(|x| {
         x.id.replace(daedalus::modded::DUMMY_REPLACE_STRING, game_version)
@@ -449,19 +478,369 @@ pub async fn read_protocol_version_from_jar(
     Ok(data.protocol_version)
 }
 
+/// Finds the game version and loader version a profile launches with
+async fn get_launch_version(
+    profile: &Profile,
+    cache_behaviour: Option<CacheBehaviour>,
+) -> crate::Result<(VersionManifest, usize, Option<LoaderVersion>)> {
+    let minecraft = crate::api::metadata::get_minecraft_versions_with_cache(
+        cache_behaviour,
+    )
+    .await?;
+    let version_index = minecraft
+        .versions
+        .iter()
+        .position(|it| it.id == profile.game_version)
+        .ok_or(crate::ErrorKind::LauncherError(format!(
+            "Invalid game version: {}",
+            profile.game_version
+        )))?;
+
+    let loader_version = get_loader_version_with_cache(
+        &profile.game_version,
+        profile.loader,
+        profile.loader_version.as_deref(),
+        cache_behaviour,
+    )
+    .await?;
+
+    if profile.loader != ModLoader::Vanilla && loader_version.is_none() {
+        return Err(crate::ErrorKind::LauncherError(format!(
+            "No loader version selected for {}",
+            profile.loader.as_str()
+        ))
+        .into());
+    }
+
+    Ok((minecraft, version_index, loader_version))
+}
+
+/// A library file with a known download that a version needs on this
+/// platform
+pub(crate) struct RequiredLibraryFile<'a> {
+    pub path: PathBuf,
+    pub download: &'a d::minecraft::LibraryDownload,
+    /// Whether the file is a jar the library's natives are extracted from
+    pub natives: bool,
+}
+
+/// Iterates over the library files a version needs on this platform,
+/// following the library rules. Libraries without an artifact URL are left
+/// out, as they are allowed to be missing, see `download::download_libraries`
+pub(crate) fn required_library_files<'a>(
+    libraries_dir: &'a Path,
+    libraries: &'a [d::minecraft::Library],
+    java_arch: &'a str,
+    minecraft_updated: bool,
+) -> impl Iterator<Item = crate::Result<RequiredLibraryFile<'a>>> + 'a {
+    libraries
+        .iter()
+        .filter(move |library| {
+            library.downloadable
+                && library.rules.as_ref().is_none_or(|rules| {
+                    parse_rules(
+                        rules,
+                        java_arch,
+                        &QuickPlayType::None,
+                        minecraft_updated,
+                    )
+                })
+        })
+        .filter_map(move |library| {
+            // When a library has natives, only its natives jar is needed
+            if let Some((os_key, classifiers)) =
+                library.natives_os_key_and_classifiers(java_arch)
+            {
+                let classifier = os_key
+                    .replace("${arch}", crate::util::platform::ARCH_WIDTH);
+                let download = classifiers.get(&classifier)?;
+                return Some(
+                    download::native_jar_path(library, &classifier, download)
+                        .map(|path| RequiredLibraryFile {
+                            path: libraries_dir.join(path),
+                            download,
+                            natives: true,
+                        }),
+                );
+            }
+
+            match &library.downloads {
+                Some(d::minecraft::LibraryDownloads {
+                    artifact: Some(artifact),
+                    ..
+                }) if !artifact.url.is_empty() => Some(
+                    d::get_path_from_artifact(&library.name)
+                        .map(|path| RequiredLibraryFile {
+                            path: libraries_dir.join(path),
+                            download: artifact,
+                            natives: false,
+                        })
+                        .map_err(Into::into),
+                ),
+                _ => None,
+            }
+        })
+}
+
+/// Lists the files that are missing to launch a profile without network
+/// access, only using cached metadata
+///
+/// Natives are extracted into a single directory, which is listed instead of
+/// the individual native libraries when it is missing or empty.
+#[tracing::instrument(skip(profile))]
+pub async fn get_offline_missing_files(
+    profile: &Profile,
+) -> crate::Result<Vec<PathBuf>> {
+    let state = State::get().await?;
+
+    let (minecraft, version_index, loader_version) =
+        get_launch_version(profile, Some(CacheBehaviour::CacheOnly)).await?;
+    let version = &minecraft.versions[version_index];
+    let minecraft_updated = version_index
+        <= minecraft
+            .versions
+            .iter()
+            .position(|x| x.id == "22w16a")
+            .unwrap_or(0);
+    let version_jar =
+        loader_version.as_ref().map_or(version.id.clone(), |it| {
+            format!("{}-{}", version.id.clone(), it.id.clone())
+        });
+
+    let version_info_path = state
+        .directories
+        .version_dir(&version_jar)
+        .join(format!("{version_jar}.json"));
+    if !version_info_path.exists() {
+        return Ok(vec![version_info_path]);
+    }
+    let version_info = download::download_version_info(
+        &state,
+        version,
+        loader_version.as_ref(),
+        None,
+        None,
+    )
+    .await?;
+
+    let mut missing_files = Vec::new();
+    let mut check = |path: PathBuf| {
+        if !path.exists() {
+            missing_files.push(path);
+        }
+    };
+
+    let java_version = get_java_version_from_profile(profile, &version_info)
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::LauncherError(
+                "Missing correct java installation".to_string(),
+            )
+        })?;
+    check(PathBuf::from(&java_version.path));
+
+    check(
+        state
+            .directories
+            .version_dir(&version_jar)
+            .join(format!("{version_jar}.jar")),
+    );
+
+    if let Some(LoggingConfiguration::Log4j2Xml { file, .. }) = version_info
+        .logging
+        .as_ref()
+        .and_then(|x| x.get(&LoggingSide::Client))
+    {
+        check(state.directories.log_configs_dir().join(&file.id));
+    }
+
+    // Natives jars are only needed to extract the natives, so they may be
+    // missing once that is done
+    let mut needs_natives = false;
+    let libraries_dir = state.directories.libraries_dir();
+    for file in required_library_files(
+        &libraries_dir,
+        &version_info.libraries,
+        &java_version.architecture,
+        minecraft_updated,
+    ) {
+        let file = file?;
+        if file.natives {
+            needs_natives = true;
+        } else {
+            check(file.path);
+        }
+    }
+
+    let natives_dir = state.directories.version_natives_dir(&version_jar);
+    if needs_natives && is_empty_dir(&natives_dir).await {
+        missing_files.push(natives_dir);
+    }
+
+    let assets_index_path = state
+        .directories
+        .assets_index_dir()
+        .join(format!("{}.json", version_info.asset_index.id));
+    if assets_index_path.exists() {
+        let index: d::minecraft::AssetsIndex =
+            serde_json::from_slice(&io::read(&assets_index_path).await?)?;
+        let with_legacy = version_info.assets == "legacy";
+
+        for (name, asset) in &index.objects {
+            let object_path = state.directories.object_dir(&asset.hash);
+            if !object_path.exists() {
+                missing_files.push(object_path);
+            }
+
+            if with_legacy {
+                let legacy_path = state.directories.legacy_assets_dir().join(
+                    name.replace('/', &String::from(std::path::MAIN_SEPARATOR)),
+                );
+                if !legacy_path.exists() {
+                    missing_files.push(legacy_path);
+                }
+            }
+        }
+    } else {
+        missing_files.push(assets_index_path);
+    }
+
+    Ok(missing_files)
+}
+
+async fn is_empty_dir(path: &Path) -> bool {
+    match io::read_dir(path).await {
+        Ok(mut entries) => matches!(entries.next_entry().await, Ok(None)),
+        Err(_) => true,
+    }
+}
+
+fn offline_missing_files_error(
+    profile: &Profile,
+    missing_files: &[PathBuf],
+) -> crate::Error {
+    const MAX_LISTED: usize = 20;
+
+    let mut message = format!(
+        "Profile {} cannot be launched offline, {} file(s) are missing:",
+        profile.path,
+        missing_files.len()
+    );
+    for path in missing_files.iter().take(MAX_LISTED) {
+        write!(&mut message, "\n{}", path.display()).unwrap();
+    }
+    if missing_files.len() > MAX_LISTED {
+        write!(
+            &mut message,
+            "\n...and {} more",
+            missing_files.len() - MAX_LISTED
+        )
+        .unwrap();
+    }
+
+    crate::ErrorKind::LauncherError(message).into()
+}
+
+/// Replaces the maximum heap size of automatic memory settings with one sized
+/// from the profile's installed mods and the system memory
+async fn resolve_memory(
+    memory: st::MemorySettings,
+    instance_path: &Path,
+) -> crate::Result<st::MemorySettings> {
+    if !memory.automatic {
+        return Ok(memory);
+    }
+
+    let mods_dir = instance_path.join("mods");
+    let mut mod_count = 0;
+    if mods_dir.exists() {
+        let mut entries = io::read_dir(&mods_dir).await?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &mods_dir))?
+        {
+            if entry.path().extension().is_some_and(|ext| ext == "jar") {
+                mod_count += 1;
+            }
+        }
+    }
+
+    let system_memory = crate::api::jre::get_max_memory().await? / 1024;
+    let maximum = args::automatic_memory(mod_count, system_memory);
+    tracing::info!(
+        "Using {maximum} MB of memory for {mod_count} mods (automatic)"
+    );
+
+    Ok(st::MemorySettings { maximum, ..memory })
+}
+
+/// Overwrites entries of the options.txt file of an instance, adding the
+/// ones that are missing. Uses 'a:b' syntax which is not quite yaml
+pub(crate) async fn set_game_options(
+    instance_path: &Path,
+    options: &[(String, String)],
+) -> crate::Result<()> {
+    if options.is_empty() {
+        return Ok(());
+    }
+
+    let options_path = instance_path.join("options.txt");
+
+    let (mut options_string, input_encoding) = if options_path.exists() {
+        io::read_any_encoding_to_string(&options_path).await?
+    } else {
+        (String::new(), encoding_rs::UTF_8)
+    };
+
+    // UTF-16 encodings may be successfully detected and read, but we cannot encode
+    // them back, and it's technically possible that the game client strongly expects
+    // such encoding
+    if input_encoding != input_encoding.output_encoding() {
+        return Err(crate::ErrorKind::LauncherError(format!(
+            "The instance options.txt file uses an unsupported encoding: {}. \
+            Please either turn off instance options that need to modify this file, \
+            or convert the file to an encoding that both the game and this app support, \
+            such as UTF-8.",
+            input_encoding.name()
+        ))
+        .into());
+    }
+
+    for (key, value) in options {
+        let re = Regex::new(&format!(r"(?m)^{}:.*$", regex::escape(key)))?;
+        // check if the regex exists in the file
+        if !re.is_match(&options_string) {
+            // The key was not found in the file, so append it
+            write!(&mut options_string, "\n{key}:{value}").unwrap();
+        } else {
+            let replaced_string = re
+                .replace_all(&options_string, &format!("{key}:{value}"))
+                .to_string();
+            options_string = replaced_string;
+        }
+    }
+
+    io::write(&options_path, input_encoding.encode(&options_string).0).await?;
+
+    Ok(())
+}
+
 #[tracing::instrument(skip_all)]
 #[allow(clippy::too_many_arguments)]
 pub async fn launch_minecraft(
     java_args: &[String],
     env_args: &[(String, String)],
     mc_set_options: &[(String, String)],
-    wrapper: &Option<String>,
+    pre_launch_hook: Option<&str>,
+    wrapper: Option<&str>,
     memory: &st::MemorySettings,
     resolution: &st::WindowSize,
     credentials: &Credentials,
     post_exit_hook: Option<String>,
     profile: &Profile,
     mut quick_play_type: QuickPlayType,
+    offline: bool,
 ) -> crate::Result<ProcessMetadata> {
     if profile.install_stage == ProfileInstallStage::PackInstalling
         || profile.install_stage == ProfileInstallStage::MinecraftInstalling
@@ -473,6 +852,14 @@ pub async fn launch_minecraft(
     }
 
     if profile.install_stage != ProfileInstallStage::Installed {
+        if offline {
+            return Err(crate::ErrorKind::LauncherError(format!(
+                "Profile {} is not installed, so it cannot be launched offline",
+                profile.path
+            ))
+            .into());
+        }
+
         install_minecraft(profile, None, false).await?;
     }
 
@@ -481,15 +868,9 @@ pub async fn launch_minecraft(
     let instance_path =
         crate::api::profile::get_full_path(&profile.path).await?;
 
-    let minecraft = crate::api::metadata::get_minecraft_versions().await?;
-    let version_index = minecraft
-        .versions
-        .iter()
-        .position(|it| it.id == profile.game_version)
-        .ok_or(crate::ErrorKind::LauncherError(format!(
-            "Invalid game version: {}",
-            profile.game_version
-        )))?;
+    let cache_behaviour = offline.then_some(CacheBehaviour::CacheOnly);
+    let (minecraft, version_index, loader_version) =
+        get_launch_version(profile, cache_behaviour).await?;
     let version = &minecraft.versions[version_index];
     let minecraft_updated = version_index
         <= minecraft
@@ -498,26 +879,18 @@ pub async fn launch_minecraft(
             .position(|x| x.id == "22w16a")
             .unwrap_or(0);
 
-    let loader_version = get_loader_version_from_profile(
-        &profile.game_version,
-        profile.loader,
-        profile.loader_version.as_deref(),
-    )
-    .await?;
-
-    if profile.loader != ModLoader::Vanilla && loader_version.is_none() {
-        return Err(crate::ErrorKind::LauncherError(format!(
-            "No loader version selected for {}",
-            profile.loader.as_str()
-        ))
-        .into());
-    }
-
     let version_jar =
         loader_version.as_ref().map_or(version.id.clone(), |it| {
             format!("{}-{}", version.id.clone(), it.id.clone())
         });
 
+    if offline {
+        let missing_files = get_offline_missing_files(profile).await?;
+        if !missing_files.is_empty() {
+            return Err(offline_missing_files_error(profile, &missing_files));
+        }
+    }
+
     let mut version_info = download::download_version_info(
         &state,
         version,
@@ -526,7 +899,7 @@ pub async fn launch_minecraft(
         None,
     )
     .await?;
-    if version_info.logging.is_none() {
+    if version_info.logging.is_none() && !offline {
         let requires_logging_info = version_index
             <= minecraft
                 .versions
@@ -545,7 +918,9 @@ pub async fn launch_minecraft(
         }
     }
 
-    download_log_config(&state, &version_info, None, false).await?;
+    if !offline {
+        download_log_config(&state, &version_info, None, false).await?;
+    }
 
     let java_version = get_java_version_from_profile(profile, &version_info)
         .await?
@@ -564,22 +939,41 @@ pub async fn launch_minecraft(
         .version_dir(&version_jar)
         .join(format!("{version_jar}.jar"));
 
+    let process_uuid = Uuid::new_v4();
+    let hook_context = HookContext::new(
+        profile,
+        instance_path.clone(),
+        java_version.path.clone(),
+        process_uuid,
+    );
+
+    if let Some(hook) = pre_launch_hook
+        && let Some(mut command) = hook_context.command(hook, "pre-launch")?
+    {
+        let result = command
+            .spawn()
+            .map_err(|e| IOError::with_path(e, &instance_path))?
+            .wait()
+            .await
+            .map_err(IOError::from)?;
+
+        if !result.success() {
+            return Err(crate::ErrorKind::LauncherError(format!(
+                "Non-zero exit code for pre-launch hook: {}",
+                result.code().unwrap_or(-1)
+            ))
+            .as_error());
+        }
+    }
+
     let args = version_info.arguments.clone().unwrap_or_default();
     let mut command = match wrapper {
         Some(hook) => {
-            let mut cmd = shlex::split(hook)
-                .ok_or_else(|| {
-                    crate::ErrorKind::LauncherError(format!(
-                        "Invalid wrapper command: {hook}",
-                    ))
-                })?
-                .into_iter();
-            let mut command = Command::new(cmd.next().ok_or(
+            let mut command = hook_context.command(hook, "wrapper")?.ok_or(
                 crate::ErrorKind::LauncherError(
                     "Empty wrapper command".to_owned(),
                 ),
-            )?);
-            command.args(cmd);
+            )?;
             command.arg(&java_version.path);
             command
         }
@@ -588,17 +982,51 @@ pub async fn launch_minecraft(
 
     let env_args = Vec::from(env_args);
 
-    // Check if profile has a running profile, and reject running the command if it does
+    // Check if profile has a running profile, and reject running the command if it does,
+    // unless the profile allows concurrent instances
     // Done late so a quick double call doesn't launch two instances
     let existing_processes =
         process::get_by_profile_path(&profile.path).await?;
-    if let Some(process) = existing_processes.first() {
+    if let Some(process) = existing_processes.first()
+        && !profile.allow_concurrent_instances
+    {
         return Err(crate::ErrorKind::LauncherError(format!(
             "Profile {} is already running at path: {}",
             profile.path, process.uuid
         ))
         .as_error());
     }
+    let concurrent_reservation = state
+        .process_manager
+        .reserve_concurrent_index(&profile.path);
+    let concurrent_index = concurrent_reservation.index();
+
+    // Extra copies write the game log to their own subfolder of logs/, with
+    // a copy of the log config whose paths point there
+    let log_config = version_info
+        .logging
+        .as_ref()
+        .and_then(|x| x.get(&LoggingSide::Client));
+    let log_configs_dir =
+        match (concurrent_logs_subfolder(concurrent_index), log_config) {
+            (
+                Some(subfolder),
+                Some(LoggingConfiguration::Log4j2Xml { file, .. }),
+            ) => {
+                let copy_dir =
+                    state.directories.log_configs_dir().join(&subfolder);
+                let config = io::read_any_encoding_to_string(
+                    state.directories.log_configs_dir().join(&file.id),
+                )
+                .await?
+                .0
+                .replace("\"logs/", &format!("\"logs/{subfolder}/"));
+                io::create_dir_all(&copy_dir).await?;
+                io::write(copy_dir.join(&file.id), config).await?;
+                copy_dir
+            }
+            _ => state.directories.log_configs_dir(),
+        };
 
     let natives_dir = state.directories.version_natives_dir(&version_jar);
     if !natives_dir.exists() {
@@ -620,7 +1048,13 @@ pub async fn launch_minecraft(
     let (main_class_keep_alive, main_class_path) =
         get_resource_file!(env "JAVA_JARS_DIR" / "theseus.jar")?;
 
-    let rpc_server = RpcServerBuilder::new().launch().await?;
+    let rpc_server = game_rpc::register_handlers(
+        RpcServerBuilder::new(),
+        &profile.path,
+        process_uuid,
+    )
+    .launch()
+    .await?;
 
     command.args(
         args::get_jvm_arguments(
@@ -628,7 +1062,7 @@ pub async fn launch_minecraft(
                 .map(|x| x.as_slice()),
             &natives_dir,
             &state.directories.libraries_dir(),
-            &state.directories.log_configs_dir(),
+            &log_configs_dir,
             &args::get_class_paths(
                 &state.directories.libraries_dir(),
                 version_info.libraries.as_slice(),
@@ -638,15 +1072,13 @@ pub async fn launch_minecraft(
             )?,
             &main_class_path,
             &version_jar,
-            *memory,
+            resolve_memory(*memory, &instance_path).await?,
             Vec::from(java_args),
             &java_version.architecture,
+            java_version.parsed_version,
             &quick_play_type,
             quick_play_version,
-            version_info
-                .logging
-                .as_ref()
-                .and_then(|x| x.get(&LoggingSide::Client)),
+            log_config,
             rpc_server.address(),
         )?
         .into_iter(),
@@ -681,6 +1113,7 @@ pub async fn launch_minecraft(
                 &java_version.architecture,
                 &quick_play_type,
                 quick_play_version,
+                offline,
             )
             .await?
             .into_iter(),
@@ -697,48 +1130,7 @@ pub async fn launch_minecraft(
 
     command.envs(env_args);
 
-    // Overwrites the minecraft options.txt file with the settings from the profile
-    // Uses 'a:b' syntax which is not quite yaml
-    if !mc_set_options.is_empty() {
-        let options_path = instance_path.join("options.txt");
-
-        let (mut options_string, input_encoding) = if options_path.exists() {
-            io::read_any_encoding_to_string(&options_path).await?
-        } else {
-            (String::new(), encoding_rs::UTF_8)
-        };
-
-        // UTF-16 encodings may be successfully detected and read, but we cannot encode
-        // them back, and it's technically possible that the game client strongly expects
-        // such encoding
-        if input_encoding != input_encoding.output_encoding() {
-            return Err(crate::ErrorKind::LauncherError(format!(
-                "The instance options.txt file uses an unsupported encoding: {}. \
-                Please either turn off instance options that need to modify this file, \
-                or convert the file to an encoding that both the game and this app support, \
-                such as UTF-8.",
-                input_encoding.name()
-            ))
-            .into());
-        }
-
-        for (key, value) in mc_set_options {
-            let re = Regex::new(&format!(r"(?m)^{}:.*$", regex::escape(key)))?;
-            // check if the regex exists in the file
-            if !re.is_match(&options_string) {
-                // The key was not found in the file, so append it
-                write!(&mut options_string, "\n{key}:{value}").unwrap();
-            } else {
-                let replaced_string = re
-                    .replace_all(&options_string, &format!("{key}:{value}"))
-                    .to_string();
-                options_string = replaced_string;
-            }
-        }
-
-        io::write(&options_path, input_encoding.encode(&options_string).0)
-            .await?;
-    }
+    set_game_options(&instance_path, mc_set_options).await?;
 
     crate::api::profile::edit(&profile.path, |prof| {
         prof.last_played = Some(Utc::now());
@@ -771,28 +1163,39 @@ pub async fn launch_minecraft(
     state
         .process_manager
         .insert_new_process(
+            process_uuid,
             &profile.path,
+            concurrent_reservation,
+            Some(credentials.offline_profile.id),
             command,
             post_exit_hook,
-            state.directories.profile_logs_dir(&profile.path),
+            hook_context,
+            state
+                .directories
+                .process_logs_dir(&profile.path, concurrent_index),
             version_info.logging.is_some(),
+            SERVER_MAIN_CLASSES.contains(&version_info.main_class.as_str()),
             main_class_keep_alive,
             rpc_server,
             async |process: &ProcessMetadata, rpc_server| {
                 let process_start_time = process.start_time.to_rfc3339();
                 let profile_created_time = profile.created.to_rfc3339();
                 let profile_modified_time = profile.modified.to_rfc3339();
+                let modrinth_link = profile
+                    .linked_data
+                    .as_ref()
+                    .filter(|x| x.provider == ContentProvider::Modrinth);
                 let system_properties = [
                     ("modrinth.process.startTime", Some(&process_start_time)),
                     ("modrinth.profile.created", Some(&profile_created_time)),
                     ("modrinth.profile.icon", profile.icon_path.as_ref()),
                     (
                         "modrinth.profile.link.project",
-                        profile.linked_data.as_ref().map(|x| &x.project_id),
+                        modrinth_link.map(|x| &x.project_id),
                     ),
                     (
                         "modrinth.profile.link.version",
-                        profile.linked_data.as_ref().map(|x| &x.version_id),
+                        modrinth_link.map(|x| &x.version_id),
                     ),
                     ("modrinth.profile.modified", Some(&profile_modified_time)),
                     ("modrinth.profile.name", Some(&profile.name)),
@@ -811,3 +1214,110 @@ pub async fn launch_minecraft(
         )
         .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use daedalus::minecraft::{Library, Os};
+
+    fn library(name: &str, extra: serde_json::Value) -> Library {
+        let mut library = serde_json::json!({
+            "name": name,
+            "downloads": {
+                "artifact": {
+                    "sha1": "artifact-sha1",
+                    "size": 1,
+                    "url": format!("https://example.com/{name}.jar")
+                },
+                "classifiers": {
+                    "natives-test": {
+                        "path": "natives-test.jar",
+                        "sha1": "natives-sha1",
+                        "size": 1,
+                        "url": "https://example.com/natives-test.jar"
+                    }
+                }
+            }
+        });
+        library
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(library).unwrap()
+    }
+
+    #[test]
+    fn test_required_library_files() {
+        let os = serde_json::to_value(Os::native_arch("x86_64")).unwrap();
+        let libraries = vec![
+            library("a:plain:1", serde_json::json!({})),
+            library(
+                "a:allowed:1",
+                serde_json::json!({
+                    "rules": [{ "action": "allow", "os": { "name": os } }]
+                }),
+            ),
+            library(
+                "a:disallowed:1",
+                serde_json::json!({
+                    "rules": [
+                        { "action": "allow" },
+                        { "action": "disallow", "os": { "name": os } }
+                    ]
+                }),
+            ),
+            library(
+                "a:not-downloadable:1",
+                serde_json::json!({ "downloadable": false }),
+            ),
+            library(
+                "a:no-url:1",
+                serde_json::json!({
+                    "downloads": {
+                        "artifact": { "sha1": "", "size": 0, "url": "" }
+                    }
+                }),
+            ),
+            library(
+                "a:natives:1",
+                serde_json::json!({
+                    "natives": { os.as_str().unwrap(): "natives-test" }
+                }),
+            ),
+        ];
+
+        let files = required_library_files(
+            Path::new("libraries"),
+            &libraries,
+            "x86_64",
+            true,
+        )
+        .collect::<crate::Result<Vec<_>>>()
+        .unwrap();
+        let files = files
+            .iter()
+            .map(|x| (x.path.clone(), x.download.sha1.as_str(), x.natives))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            files,
+            vec![
+                (
+                    PathBuf::from("libraries/a/plain/1/plain-1.jar"),
+                    "artifact-sha1",
+                    false
+                ),
+                (
+                    PathBuf::from("libraries/a/allowed/1/allowed-1.jar"),
+                    "artifact-sha1",
+                    false
+                ),
+                (
+                    PathBuf::from("libraries/natives-test.jar"),
+                    "natives-sha1",
+                    true
+                ),
+            ]
+        );
+    }
+}
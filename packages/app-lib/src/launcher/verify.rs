@@ -0,0 +1,313 @@
+//! Integrity verification and repair of downloaded Minecraft data
+
+use crate::event::LoadingBarType;
+use crate::event::emit::{
+    emit_loading, init_loading, loading_try_for_each_concurrent,
+};
+use crate::launcher::{
+    download, get_java_version_from_profile, get_launch_version,
+    required_library_files,
+};
+use crate::state::{Profile, State};
+use crate::util::fetch::{fetch, sha1_async, write};
+use crate::util::io;
+use daedalus::{
+    self as d,
+    minecraft::{AssetsIndex, LoggingConfiguration, LoggingSide},
+};
+use dashmap::DashMap;
+use futures::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// The outcome of verifying the game files of a profile
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VerificationSummary {
+    /// The number of files whose hash was checked
+    pub checked_files: usize,
+    /// Files that were missing and have been downloaded
+    pub missing_files: Vec<PathBuf>,
+    /// Files whose hash did not match and have been downloaded again
+    pub corrupted_files: Vec<PathBuf>,
+    /// Whether the natives were missing or came from a corrupted jar, and
+    /// have been extracted again
+    pub natives_extracted: bool,
+}
+
+/// A downloaded file with a known SHA-1 hash
+struct KnownFile {
+    sha1: String,
+    url: String,
+}
+
+enum FileIssue {
+    Missing,
+    Corrupted,
+}
+
+/// Checks the version jar, libraries, log config and asset objects of a
+/// profile's game version against their SHA-1 hashes, and downloads again
+/// only the files that are missing or corrupted
+///
+/// Natives are extracted from their jars when installing, so they are only
+/// extracted again if the natives directory is missing or empty, or their
+/// jar was downloaded again. A missing natives jar is fine as long as the
+/// natives directory is not empty.
+#[tracing::instrument(skip(profile))]
+pub async fn verify_minecraft(
+    profile: &Profile,
+) -> crate::Result<VerificationSummary> {
+    let state = State::get().await?;
+
+    let loading_bar = init_loading(
+        LoadingBarType::ProfileVerify {
+            profile_path: profile.path.clone(),
+            profile_name: profile.name.clone(),
+        },
+        100.0,
+        "Verifying game files",
+    )
+    .await?;
+
+    let (minecraft, version_index, loader_version) =
+        get_launch_version(profile, None).await?;
+    let version = &minecraft.versions[version_index];
+    let minecraft_updated = version_index
+        <= minecraft
+            .versions
+            .iter()
+            .position(|x| x.id == "22w16a")
+            .unwrap_or(0);
+
+    // An unreadable version info file is downloaded again
+    let version_info = match download::download_version_info(
+        &state,
+        version,
+        loader_version.as_ref(),
+        None,
+        None,
+    )
+    .await
+    {
+        Ok(version_info) => version_info,
+        Err(err) => {
+            tracing::warn!(
+                "Failed to read version info of {}, downloading it again: {err}",
+                version.id
+            );
+            download::download_version_info(
+                &state,
+                version,
+                loader_version.as_ref(),
+                Some(true),
+                None,
+            )
+            .await?
+        }
+    };
+    emit_loading(&loading_bar, 5.0, None)?;
+
+    let java_version = get_java_version_from_profile(profile, &version_info)
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::LauncherError(
+                "Missing correct java installation".to_string(),
+            )
+        })?;
+    let java_arch = &java_version.architecture;
+
+    // The assets index is stored re-serialized, so its hash never matches
+    // the one in the version info. It is only checked for being readable
+    let assets_index_path = state
+        .directories
+        .assets_index_dir()
+        .join(format!("{}.json", version_info.asset_index.id));
+    let assets_index = match io::read(&assets_index_path)
+        .err_into::<crate::Error>()
+        .await
+        .and_then(|x| Ok(serde_json::from_slice::<AssetsIndex>(&x)?))
+    {
+        Ok(index) => index,
+        Err(_) => {
+            download::download_assets_index(&state, &version_info, None, true)
+                .await?
+        }
+    };
+    emit_loading(&loading_bar, 5.0, None)?;
+
+    let mut files = HashMap::new();
+
+    if let Some(client) = version_info
+        .downloads
+        .get(&d::minecraft::DownloadType::Client)
+    {
+        files.insert(
+            state
+                .directories
+                .version_dir(&version_info.id)
+                .join(format!("{}.jar", version_info.id)),
+            KnownFile {
+                sha1: client.sha1.clone(),
+                url: client.url.clone(),
+            },
+        );
+    }
+
+    if let Some(LoggingConfiguration::Log4j2Xml { file, .. }) = version_info
+        .logging
+        .as_ref()
+        .and_then(|x| x.get(&LoggingSide::Client))
+    {
+        files.insert(
+            state.directories.log_configs_dir().join(&file.id),
+            KnownFile {
+                sha1: file.sha1.clone(),
+                url: file.url.clone(),
+            },
+        );
+    }
+
+    let natives_dir = state.directories.version_natives_dir(&version_info.id);
+    let natives_missing = match io::read_dir(&natives_dir).await {
+        Ok(mut entries) => entries.next_entry().await?.is_none(),
+        Err(_) => true,
+    };
+
+    // Libraries without a known artifact (such as those generated by Forge
+    // processors) cannot be verified
+    let mut natives_jars = Vec::new();
+    let libraries_dir = state.directories.libraries_dir();
+    for file in required_library_files(
+        &libraries_dir,
+        &version_info.libraries,
+        java_arch,
+        minecraft_updated,
+    ) {
+        let file = file?;
+        if file.natives {
+            // Natives jars used to be removed after extracting them, so
+            // installs with extracted natives may not have them
+            if !natives_missing && !file.path.exists() {
+                continue;
+            }
+            natives_jars.push(file.path.clone());
+        }
+        files.insert(
+            file.path,
+            KnownFile {
+                sha1: file.download.sha1.clone(),
+                url: file.download.url.clone(),
+            },
+        );
+    }
+
+    let with_legacy = version_info.assets == "legacy";
+    for (name, asset) in &assets_index.objects {
+        let file = || KnownFile {
+            sha1: asset.hash.clone(),
+            url: format!(
+                "https://resources.download.minecraft.net/{}/{}",
+                &asset.hash[..2],
+                asset.hash
+            ),
+        };
+
+        files.insert(state.directories.object_dir(&asset.hash), file());
+        if with_legacy {
+            files.insert(
+                state.directories.legacy_assets_dir().join(
+                    name.replace('/', &String::from(std::path::MAIN_SEPARATOR)),
+                ),
+                file(),
+            );
+        }
+    }
+
+    let checked_files = files.len();
+    let issues = DashMap::new();
+    loading_try_for_each_concurrent(
+        stream::iter(files).map(Ok::<_, crate::Error>),
+        None,
+        Some(&loading_bar),
+        80.0,
+        checked_files,
+        None,
+        |(path, file)| {
+            let state = &state;
+            let issues = &issues;
+
+            async move {
+                let issue = if path.exists() {
+                    let data = {
+                        let _permit = state.io_semaphore.0.acquire().await?;
+                        io::read(&path).await?
+                    };
+
+                    (sha1_async(data.into()).await? != file.sha1)
+                        .then_some(FileIssue::Corrupted)
+                } else {
+                    Some(FileIssue::Missing)
+                };
+
+                if let Some(issue) = issue {
+                    tracing::info!("Downloading {} again", path.display());
+                    let bytes = fetch(
+                        &file.url,
+                        Some(&file.sha1),
+                        &state.fetch_semaphore,
+                        &state.pool,
+                    )
+                    .await?;
+                    write(&path, &bytes, &state.io_semaphore).await?;
+                    issues.insert(path, issue);
+                }
+
+                Ok(())
+            }
+        },
+    )
+    .await?;
+
+    let mut natives_extracted = false;
+    for path in natives_jars {
+        if natives_missing || issues.contains_key(&path) {
+            tracing::info!("Extracting natives of {} again", path.display());
+            download::extract_natives(
+                &state,
+                &version_info.id,
+                &path.to_string_lossy(),
+                &io::read(&path).await?,
+            );
+            natives_extracted = true;
+        }
+    }
+
+    let mut missing_files = Vec::new();
+    let mut corrupted_files = Vec::new();
+    for (path, issue) in issues {
+        match issue {
+            FileIssue::Missing => missing_files.push(path),
+            FileIssue::Corrupted => corrupted_files.push(path),
+        }
+    }
+    missing_files.sort();
+    corrupted_files.sort();
+
+    emit_loading(
+        &loading_bar,
+        10.0,
+        Some(&format!(
+            "Verified {checked_files} files: {} missing, {} corrupted",
+            missing_files.len(),
+            corrupted_files.len()
+        )),
+    )?;
+
+    Ok(VerificationSummary {
+        checked_files,
+        missing_files,
+        corrupted_files,
+        natives_extracted,
+    })
+}
@@ -1,10 +1,13 @@
 use crate::worlds::{DisplayStatus, WorldType};
+use chrono::{DateTime, TimeZone, Utc};
 use paste::paste;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Default)]
 pub struct AttachedWorldData {
     pub display_status: DisplayStatus,
+    /// When the running game last reported joining the world
+    pub last_joined: Option<DateTime<Utc>>,
 }
 
 impl AttachedWorldData {
@@ -18,7 +21,7 @@ impl AttachedWorldData {
 
         let attached_data = sqlx::query!(
             "
-            SELECT display_status
+            SELECT display_status, last_joined
             FROM attached_world_data
             WHERE profile_path = $1 and world_type = $2 and world_id = $3
             ",
@@ -31,6 +34,7 @@ impl AttachedWorldData {
 
         Ok(attached_data.map(|x| AttachedWorldData {
             display_status: DisplayStatus::from_string(&x.display_status),
+            last_joined: x.last_joined.and_then(parse_timestamp),
         }))
     }
 
@@ -40,7 +44,7 @@ impl AttachedWorldData {
     ) -> crate::Result<HashMap<(WorldType, String), Self>> {
         let attached_data = sqlx::query!(
             "
-            SELECT world_type, world_id, display_status
+            SELECT world_type, world_id, display_status, last_joined
             FROM attached_world_data
             WHERE profile_path = $1
             ",
@@ -57,7 +61,10 @@ impl AttachedWorldData {
                     DisplayStatus::from_string(&x.display_status);
                 (
                     (world_type, x.world_id),
-                    AttachedWorldData { display_status },
+                    AttachedWorldData {
+                        display_status,
+                        last_joined: x.last_joined.and_then(parse_timestamp),
+                    },
                 )
             })
             .collect())
@@ -87,6 +94,10 @@ impl AttachedWorldData {
     }
 }
 
+fn parse_timestamp(timestamp: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(timestamp, 0).single()
+}
+
 macro_rules! attached_data_setter {
     ($parameter:ident: $parameter_type:ty, $column:expr $(=> $adapter:expr)?) => {
         paste! {
@@ -120,3 +131,4 @@ macro_rules! attached_data_setter {
 }
 
 attached_data_setter!(display_status: DisplayStatus, "display_status" => display_status.as_str());
+attached_data_setter!(last_joined: DateTime<Utc>, "last_joined" => last_joined.timestamp());
@@ -1,4 +1,4 @@
-use crate::state::ProjectType;
+use crate::state::{ProjectType, UpdateChannel};
 use crate::util::fetch::{FetchSemaphore, fetch_json, sha1_async};
 use chrono::{DateTime, Utc};
 use dashmap::DashSet;
@@ -41,6 +41,7 @@ pub enum CacheValueType {
     CfSearchResults,
     CfFingerprint,
     CfCategories,
+    CfFileUpdate,
 }
 
 impl CacheValueType {
@@ -69,6 +70,7 @@ impl CacheValueType {
             CacheValueType::CfSearchResults => "cf_search_results",
             CacheValueType::CfFingerprint => "cf_fingerprint",
             CacheValueType::CfCategories => "cf_categories",
+            CacheValueType::CfFileUpdate => "cf_file_update",
         }
     }
 
@@ -97,6 +99,7 @@ impl CacheValueType {
             "cf_search_results" => CacheValueType::CfSearchResults,
             "cf_fingerprint" => CacheValueType::CfFingerprint,
             "cf_categories" => CacheValueType::CfCategories,
+            "cf_file_update" => CacheValueType::CfFileUpdate,
             _ => CacheValueType::Project,
         }
     }
@@ -147,7 +150,8 @@ impl CacheValueType {
             | CacheValueType::CfAuthor
             | CacheValueType::CfSearchResults
             | CacheValueType::CfFingerprint
-            | CacheValueType::CfCategories => None,
+            | CacheValueType::CfCategories
+            | CacheValueType::CfFileUpdate => None,
         }
     }
 }
@@ -188,6 +192,7 @@ pub enum CacheValue {
     CfSearchResults(CfSearchResults),
     CfFingerprint(CfCachedFingerprint),
     CfCategories(Vec<CfCachedCategory>),
+    CfFileUpdate(CfCachedFileUpdate),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -234,6 +239,8 @@ pub struct CachedFileUpdate {
     pub hash: String,
     pub game_version: String,
     pub loaders: Vec<String>,
+    #[serde(default)]
+    pub channel: UpdateChannel,
     pub update_version_id: String,
 }
 
@@ -243,6 +250,10 @@ pub struct CachedFileHash {
     pub size: u64,
     pub hash: String,
     pub project_type: Option<ProjectType>,
+    /// CurseForge fingerprint of the file, computed when it is first
+    /// needed to look the file up on CurseForge
+    #[serde(default)]
+    pub fingerprint: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -347,6 +358,18 @@ pub struct CfCachedFingerprint {
     pub file_id: i32,
 }
 
+/// Newest CurseForge file of a mod for a loader and game version
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CfCachedFileUpdate {
+    pub mod_id: i32,
+    /// The profile loader, or `any` for content that is not a mod
+    pub loader: String,
+    pub game_version: String,
+    #[serde(default)]
+    pub channel: UpdateChannel,
+    pub update_file_id: i32,
+}
+
 /// Cached CurseForge category
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CfCachedCategory {
@@ -674,6 +697,7 @@ impl CacheValue {
             CacheValue::CfSearchResults(_) => CacheValueType::CfSearchResults,
             CacheValue::CfFingerprint(_) => CacheValueType::CfFingerprint,
             CacheValue::CfCategories(_) => CacheValueType::CfCategories,
+            CacheValue::CfFileUpdate(_) => CacheValueType::CfFileUpdate,
         }
     }
 
@@ -707,9 +731,10 @@ impl CacheValue {
             }
             CacheValue::FileUpdate(hash) => {
                 format!(
-                    "{}-{}-{}",
+                    "{}-{}-{}-{}",
                     hash.hash,
                     hash.loaders.join("+"),
+                    hash.channel.as_str(),
                     hash.game_version
                 )
             }
@@ -721,6 +746,15 @@ impl CacheValue {
             CacheValue::CfSearchResults(search) => search.query.clone(),
             CacheValue::CfFingerprint(fp) => fp.fingerprint.to_string(),
             CacheValue::CfCategories(_) => DEFAULT_ID.to_string(),
+            CacheValue::CfFileUpdate(update) => {
+                format!(
+                    "{}-{}-{}-{}",
+                    update.mod_id,
+                    update.loader,
+                    update.channel.as_str(),
+                    update.game_version
+                )
+            }
         }
     }
 
@@ -751,7 +785,8 @@ impl CacheValue {
             | CacheValue::CfAuthor(_)
             | CacheValue::CfSearchResults(_)
             | CacheValue::CfFingerprint(_)
-            | CacheValue::CfCategories(_) => None,
+            | CacheValue::CfCategories(_)
+            | CacheValue::CfFileUpdate(_) => None,
 
             CacheValue::CfProject(project) => Some(project.slug.clone()),
         }
@@ -773,6 +808,9 @@ pub enum CacheBehaviour {
     MustRevalidate,
     // Ignore cache- always fetch updated data from origin
     Bypass,
+    /// Only serve cached data, even if expired. Never fetches from origin,
+    /// so missing data is simply not returned
+    CacheOnly,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -867,7 +905,8 @@ impl_cache_methods!(
     (CfFile, CfCachedFile),
     (CfAuthor, CfCachedAuthor),
     (CfSearchResults, CfSearchResults),
-    (CfFingerprint, CfCachedFingerprint)
+    (CfFingerprint, CfCachedFingerprint),
+    (CfFileUpdate, CfCachedFileUpdate)
 );
 
 impl_cache_method_singular!(
@@ -996,7 +1035,9 @@ impl CachedEntry {
             }
         }
 
-        if !remaining_keys.is_empty() {
+        if !remaining_keys.is_empty()
+            && cache_behaviour != CacheBehaviour::CacheOnly
+        {
             let res = Self::fetch_many(
                 type_,
                 remaining_keys.clone(),
@@ -1489,6 +1530,7 @@ impl CachedEntry {
                             project_type: ProjectType::get_from_parent_folder(
                                 &full_path,
                             ),
+                            fingerprint: None,
                         })
                         .get_entry(),
                         true,
@@ -1511,20 +1553,25 @@ impl CachedEntry {
                 let mut vals = Vec::new();
 
                 // TODO: switch to update individual once back-end route exists
-                let mut filtered_keys: Vec<((String, String), Vec<String>)> =
-                    Vec::new();
+                let mut filtered_keys: Vec<(
+                    (String, UpdateChannel, String),
+                    Vec<String>,
+                )> = Vec::new();
                 keys.iter().for_each(|x| {
                     let string = x.key().to_string();
-                    let key = string.splitn(3, '-').collect::<Vec<_>>();
+                    let key = string.splitn(4, '-').collect::<Vec<_>>();
 
-                    if key.len() == 3 {
+                    if key.len() == 4 {
                         let hash = key[0];
                         let loaders_key = key[1];
-                        let game_version = key[2];
+                        let channel = UpdateChannel::from_string(key[2]);
+                        let game_version = key[3];
 
                         if let Some(values) =
                             filtered_keys.iter_mut().find(|x| {
-                                x.0.0 == loaders_key && x.0.1 == game_version
+                                x.0.0 == loaders_key
+                                    && x.0.1 == channel
+                                    && x.0.2 == game_version
                             })
                         {
                             values.1.push(hash.to_string());
@@ -1532,6 +1579,7 @@ impl CachedEntry {
                             filtered_keys.push((
                                 (
                                     loaders_key.to_string(),
+                                    channel,
                                     game_version.to_string(),
                                 ),
                                 vec![hash.to_string()],
@@ -1547,7 +1595,7 @@ impl CachedEntry {
 
                 let variations =
                     futures::future::try_join_all(filtered_keys.iter().map(
-                        |((loaders_key, game_version), hashes)| {
+                        |((loaders_key, channel, game_version), hashes)| {
                             fetch_json::<HashMap<String, Version>>(
                                 Method::POST,
                                 concat!(env!("MODRINTH_API_URL"), "version_files/update"),
@@ -1556,7 +1604,8 @@ impl CachedEntry {
                                     "algorithm": "sha1",
                                     "hashes": hashes,
                                     "loaders": loaders_key.split('+').collect::<Vec<_>>(),
-                                    "game_versions": [game_version]
+                                    "game_versions": [game_version],
+                                    "version_types": channel.version_types()
                                 })),
                                 fetch_semaphore,
                                 pool,
@@ -1567,7 +1616,7 @@ impl CachedEntry {
 
                 for (index, mut variation) in variations.into_iter().enumerate()
                 {
-                    let ((loaders_key, game_version), hashes) =
+                    let ((loaders_key, channel, game_version), hashes) =
                         &filtered_keys[index];
 
                     for hash in hashes {
@@ -1588,6 +1637,7 @@ impl CachedEntry {
                                         .split('+')
                                         .map(|x| x.to_string())
                                         .collect(),
+                                    channel: *channel,
                                     update_version_id: version_id,
                                 })
                                 .get_entry(),
@@ -1597,7 +1647,8 @@ impl CachedEntry {
                             vals.push((
                                 CacheValueType::FileUpdate.get_empty_entry(
                                     format!(
-                                        "{hash}-{loaders_key}-{game_version}"
+                                        "{hash}-{loaders_key}-{}-{game_version}",
+                                        channel.as_str()
                                     ),
                                 ),
                                 true,
@@ -1851,6 +1902,88 @@ impl CachedEntry {
 
                 vec![(CacheValue::CfCategories(cached).get_entry(), true)]
             }
+
+            CacheValueType::CfFileUpdate => {
+                use crate::api::curseforge::{self, ModLoaderType};
+
+                let updates = futures::future::try_join_all(keys.iter().map(
+                    |key| async move {
+                        let key = key.to_string();
+                        let parts = key.splitn(4, '-').collect::<Vec<_>>();
+
+                        let [mod_id, loader, channel, game_version] = parts[..]
+                        else {
+                            return Ok((
+                                CacheValueType::CfFileUpdate
+                                    .get_empty_entry(key.clone()),
+                                None,
+                            ));
+                        };
+                        let Ok(mod_id) = mod_id.parse::<i32>() else {
+                            return Ok((
+                                CacheValueType::CfFileUpdate
+                                    .get_empty_entry(key.clone()),
+                                None,
+                            ));
+                        };
+
+                        let files = curseforge::get_mod_files(
+                            mod_id,
+                            Some(game_version),
+                            Some(ModLoaderType::from_str(loader)),
+                            None,
+                            Some(50),
+                            fetch_semaphore,
+                            pool,
+                        )
+                        .await?;
+
+                        let channel = UpdateChannel::from_string(channel);
+                        let newest = files
+                            .data
+                            .into_iter()
+                            .filter(|x| {
+                                x.is_available
+                                    && channel
+                                        .allows_cf_release_type(x.release_type)
+                            })
+                            .max_by_key(|x| x.file_date);
+
+                        Ok::<_, crate::Error>(match newest {
+                            Some(file) => (
+                                CacheValue::CfFileUpdate(CfCachedFileUpdate {
+                                    mod_id,
+                                    loader: loader.to_string(),
+                                    game_version: game_version.to_string(),
+                                    channel,
+                                    update_file_id: file.id,
+                                })
+                                .get_entry(),
+                                Some(CfCachedFile::from_cf_file(&file)),
+                            ),
+                            None => (
+                                CacheValueType::CfFileUpdate
+                                    .get_empty_entry(key.clone()),
+                                None,
+                            ),
+                        })
+                    },
+                ))
+                .await?;
+
+                let mut values = vec![];
+                for (entry, file) in updates {
+                    values.push((entry, true));
+                    if let Some(file) = file {
+                        values.push((
+                            CacheValue::CfFile(file).get_entry(),
+                            false,
+                        ));
+                    }
+                }
+
+                values
+            }
         })
     }
 
@@ -1915,6 +2048,8 @@ pub async fn cache_file_hash(
 ) -> crate::Result<()> {
     let size = bytes.len();
 
+    let fingerprint =
+        i64::from(crate::api::curseforge::compute_fingerprint(&bytes));
     let hash = if let Some(known_hash) = known_hash {
         known_hash.to_string()
     } else {
@@ -1927,6 +2062,7 @@ pub async fn cache_file_hash(
             size: size as u64,
             hash,
             project_type,
+            fingerprint: Some(fingerprint),
         })
         .get_entry()],
         exec,
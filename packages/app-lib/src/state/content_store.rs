@@ -0,0 +1,130 @@
+//! Hash-addressed store of project files shared by all profiles
+//!
+//! Downloaded mods, resource packs and other project files are written once
+//! to `caches/content`, keyed by their SHA-1 hash, and hard linked into the
+//! profiles that use them. Where hard links are not supported, such as
+//! across file systems, the file is copied into the profile instead.
+
+use crate::State;
+use crate::state::{CacheBehaviour, Profile};
+use crate::util::fetch::{FetchSemaphore, IoSemaphore, sha1_async};
+use crate::util::io::{self, IOError};
+use async_walkdir::WalkDir;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::LazyLock;
+
+// Linking takes a shared lock and garbage collection an exclusive one, as a
+// freshly written blob is not referenced by any profile until it is linked
+static STORE_LOCK: LazyLock<tokio::sync::RwLock<()>> =
+    LazyLock::new(|| tokio::sync::RwLock::new(()));
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ContentStoreGcResult {
+    pub removed_files: usize,
+    pub freed_bytes: u64,
+}
+
+/// Writes a project file to the content store if it is not there yet, and
+/// links it to `dest`, replacing any file already at that path
+#[tracing::instrument(skip(bytes, io_semaphore))]
+pub(crate) async fn link_bytes(
+    bytes: bytes::Bytes,
+    hash: Option<&str>,
+    dest: &Path,
+    io_semaphore: &IoSemaphore,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    let hash = match hash {
+        Some(hash) => hash.to_string(),
+        None => sha1_async(bytes.clone()).await?,
+    };
+    let blob = state.directories.content_store_file(&hash);
+
+    let _lock = STORE_LOCK.read().await;
+    let _permit = io_semaphore.0.acquire().await?;
+
+    // A blob with the wrong hash was left behind by an interrupted write or
+    // edited through one of its links, so it is written again. The write
+    // replaces the file, which leaves existing links untouched
+    let blob_valid = match io::read(&blob).await {
+        Ok(data) => sha1_async(data.into()).await? == hash,
+        Err(_) => false,
+    };
+    if !blob_valid {
+        if let Some(parent) = blob.parent() {
+            io::create_dir_all(parent).await?;
+        }
+        io::write(&blob, &bytes).await?;
+    }
+
+    if let Some(parent) = dest.parent() {
+        io::create_dir_all(parent).await?;
+    }
+    if dest.exists() {
+        io::remove_file(dest).await?;
+    }
+
+    if let Err(err) = tokio::fs::hard_link(&blob, dest).await {
+        tracing::debug!(
+            "Could not hard link {} to {}, copying instead: {err}",
+            blob.display(),
+            dest.display()
+        );
+        io::write(dest, &bytes).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes every file from the content store that is not used by any
+/// profile. Files are matched by hash, so copies made where hard links are
+/// not supported still count as uses
+#[tracing::instrument]
+pub async fn collect_garbage(
+    pool: &SqlitePool,
+    fetch_semaphore: &FetchSemaphore,
+) -> crate::Result<ContentStoreGcResult> {
+    let state = State::get().await?;
+    let store_dir = state.directories.content_store_dir();
+
+    let mut result = ContentStoreGcResult::default();
+    if !store_dir.exists() {
+        return Ok(result);
+    }
+
+    let _lock = STORE_LOCK.write().await;
+
+    let mut referenced = HashSet::new();
+    for profile in Profile::get_all(pool).await? {
+        let projects = profile
+            .get_projects(
+                Some(CacheBehaviour::CacheOnly),
+                pool,
+                fetch_semaphore,
+            )
+            .await?;
+        referenced.extend(projects.into_iter().map(|(_, file)| file.hash));
+    }
+
+    let mut walker = WalkDir::new(&store_dir);
+    while let Some(entry) = walker.next().await {
+        let entry = entry.map_err(IOError::from_walkdir)?;
+        if !entry.file_type().await?.is_file() {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if referenced.contains(&file_name) {
+            continue;
+        }
+
+        result.freed_bytes += io::metadata(entry.path()).await?.len();
+        result.removed_files += 1;
+        io::remove_file(entry.path()).await?;
+    }
+
+    Ok(result)
+}
@@ -147,6 +147,21 @@ impl DirectoryInfo {
         self.profiles_dir().join(profile_path).join("logs")
     }
 
+    /// Gets the logs dir of a running copy of a profile. The first copy logs
+    /// to the profile's logs dir, and extra copies to their own subfolder
+    #[inline]
+    pub fn process_logs_dir(
+        &self,
+        profile_path: &str,
+        concurrent_index: u32,
+    ) -> PathBuf {
+        let logs_dir = self.profile_logs_dir(profile_path);
+        match super::concurrent_logs_subfolder(concurrent_index) {
+            Some(subfolder) => logs_dir.join(subfolder),
+            None => logs_dir,
+        }
+    }
+
     /// Gets the crash reports dir for a given profile
     #[inline]
     pub fn crash_reports_dir(&self, profile_path: &str) -> PathBuf {
@@ -165,6 +180,18 @@ impl DirectoryInfo {
         self.config_dir.join(CACHES_FOLDER_NAME)
     }
 
+    /// Get the hash-addressed store of project files shared by profiles
+    #[inline]
+    pub fn content_store_dir(&self) -> PathBuf {
+        self.caches_dir().join("content")
+    }
+
+    /// Get the path of a file in the content store by its SHA-1 hash
+    #[inline]
+    pub fn content_store_file(&self, hash: &str) -> PathBuf {
+        self.content_store_dir().join(&hash[..2]).join(hash)
+    }
+
     /// Get path from environment variable
     #[inline]
     fn env_path(name: &str) -> Option<PathBuf> {
@@ -0,0 +1,234 @@
+use crate::state::GameServer;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A finished run of a game process
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GameSession {
+    /// The UUID of the game process
+    pub id: Uuid,
+    pub profile_path: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    /// `None` if the process was ended by a signal
+    pub exit_code: Option<i32>,
+    /// The UUID of the account the game was launched with
+    pub account: Option<Uuid>,
+    /// Folder names of the singleplayer worlds joined, in join order
+    pub worlds: Vec<String>,
+    /// Servers joined, in join order
+    pub servers: Vec<GameServer>,
+    /// Whether the game exited with an error it was not killed for, or
+    /// wrote a crash report while running
+    pub crashed: bool,
+}
+
+/// Session totals of a single day, in local time
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DailySessionStats {
+    pub date: NaiveDate,
+    pub sessions: u32,
+    /// Seconds played over all sessions started that day
+    pub play_time: u64,
+    pub crashes: u32,
+}
+
+/// Session totals of a profile over its whole history
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProfileSessionStats {
+    pub profile_path: String,
+    pub sessions: u32,
+    /// Seconds played over all sessions
+    pub play_time: u64,
+    /// Average session length in seconds
+    pub average_length: u64,
+    /// Length of the longest session in seconds
+    pub longest_session: u64,
+    pub crashes: u32,
+    pub last_session: DateTime<Utc>,
+}
+
+struct GameSessionQueryResult {
+    id: String,
+    profile_path: String,
+    start_time: i64,
+    end_time: i64,
+    exit_code: Option<i64>,
+    account: Option<String>,
+    worlds: serde_json::Value,
+    servers: serde_json::Value,
+    crashed: i64,
+}
+
+impl TryFrom<GameSessionQueryResult> for GameSession {
+    type Error = crate::Error;
+
+    fn try_from(x: GameSessionQueryResult) -> crate::Result<Self> {
+        Ok(Self {
+            id: Uuid::parse_str(&x.id)?,
+            profile_path: x.profile_path,
+            start_time: timestamp(x.start_time),
+            end_time: timestamp(x.end_time),
+            exit_code: x.exit_code.map(|x| x as i32),
+            account: x.account.and_then(|x| Uuid::parse_str(&x).ok()),
+            worlds: serde_json::from_value(x.worlds).unwrap_or_default(),
+            servers: serde_json::from_value(x.servers).unwrap_or_default(),
+            crashed: x.crashed == 1,
+        })
+    }
+}
+
+fn timestamp(seconds: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(seconds, 0)
+        .single()
+        .unwrap_or_else(Utc::now)
+}
+
+impl GameSession {
+    pub async fn insert(
+        &self,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite>,
+    ) -> crate::Result<()> {
+        let id = self.id.as_hyphenated().to_string();
+        let start_time = self.start_time.timestamp();
+        let end_time = self.end_time.timestamp();
+        let account = self.account.map(|x| x.as_hyphenated().to_string());
+        let worlds = serde_json::to_string(&self.worlds)?;
+        let servers = serde_json::to_string(&self.servers)?;
+
+        sqlx::query!(
+            "
+            INSERT INTO game_sessions (
+                id, profile_path, start_time, end_time, exit_code, account,
+                worlds, servers, crashed
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, jsonb($7), jsonb($8), $9)
+            ",
+            id,
+            self.profile_path,
+            start_time,
+            end_time,
+            self.exit_code,
+            account,
+            worlds,
+            servers,
+            self.crashed,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the newest sessions of a profile, or of all profiles if
+    /// `profile_path` is `None`
+    pub async fn get_recent(
+        profile_path: Option<&str>,
+        limit: u32,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite>,
+    ) -> crate::Result<Vec<Self>> {
+        sqlx::query_as!(
+            GameSessionQueryResult,
+            r#"
+            SELECT
+                id, profile_path, start_time, end_time, exit_code, account,
+                json(worlds) as "worlds!: serde_json::Value",
+                json(servers) as "servers!: serde_json::Value",
+                crashed
+            FROM game_sessions
+            WHERE $1 IS NULL OR profile_path = $1
+            ORDER BY start_time DESC
+            LIMIT $2
+            "#,
+            profile_path,
+            limit
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(Self::try_from)
+        .collect()
+    }
+}
+
+/// Gets per-day session totals since a point in time, for a profile or for
+/// all profiles if `profile_path` is `None`. Days without sessions are left
+/// out
+pub async fn get_daily_stats(
+    profile_path: Option<&str>,
+    since: DateTime<Utc>,
+    exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite>,
+) -> crate::Result<Vec<DailySessionStats>> {
+    let since = since.timestamp();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            date(start_time, 'unixepoch', 'localtime') as "date!: String",
+            COUNT(*) as "sessions!: i64",
+            SUM(end_time - start_time) as "play_time!: i64",
+            SUM(crashed) as "crashes!: i64"
+        FROM game_sessions
+        WHERE ($1 IS NULL OR profile_path = $1) AND start_time >= $2
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+        profile_path,
+        since
+    )
+    .fetch_all(exec)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|x| {
+            Some(DailySessionStats {
+                date: NaiveDate::parse_from_str(&x.date, "%Y-%m-%d").ok()?,
+                sessions: x.sessions as u32,
+                play_time: x.play_time.max(0) as u64,
+                crashes: x.crashes as u32,
+            })
+        })
+        .collect())
+}
+
+/// Gets the session totals of every profile with at least one session,
+/// profiles with the most crashes first
+pub async fn get_profile_stats(
+    exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite>,
+) -> crate::Result<Vec<ProfileSessionStats>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            profile_path as "profile_path!",
+            COUNT(*) as "sessions!: i64",
+            SUM(end_time - start_time) as "play_time!: i64",
+            MAX(end_time - start_time) as "longest_session!: i64",
+            SUM(crashed) as "crashes!: i64",
+            MAX(start_time) as "last_session!: i64"
+        FROM game_sessions
+        GROUP BY profile_path
+        ORDER BY 5 DESC, 2 DESC
+        "#
+    )
+    .fetch_all(exec)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|x| {
+            let play_time = x.play_time.max(0) as u64;
+            let sessions = x.sessions as u32;
+            ProfileSessionStats {
+                profile_path: x.profile_path,
+                sessions,
+                play_time,
+                average_length: play_time / u64::from(sessions.max(1)),
+                longest_session: x.longest_session.max(0) as u64,
+                crashes: x.crashes as u32,
+                last_session: timestamp(x.last_session),
+            }
+        })
+        .collect())
+}
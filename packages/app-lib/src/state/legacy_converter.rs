@@ -4,10 +4,11 @@ use crate::prelude::ModLoader;
 use crate::state;
 use crate::state::{
     CacheValue, CachedEntry, CachedFile, CachedFileHash, CachedFileUpdate,
-    Credentials, DefaultPage, DependencyType, DeviceToken, DeviceTokenKey,
-    DeviceTokenPair, FileType, Hooks, LauncherFeatureVersion, LinkedData,
-    MemorySettings, ModrinthCredentials, Profile, ProfileInstallStage,
-    TeamMember, Theme, VersionFile, WindowSize,
+    ContentProvider, Credentials, DefaultPage, DependencyType, DeviceToken,
+    DeviceTokenKey, DeviceTokenPair, FileType, Hooks, LauncherFeatureVersion,
+    LinkedData, MemorySettings, ModrinthCredentials, Profile,
+    ProfileInstallStage, TeamMember, Theme, UpdateChannel, VersionFile,
+    WindowSize,
 };
 use crate::util::fetch::{IoSemaphore, read_json};
 use chrono::{DateTime, Utc};
@@ -225,6 +226,7 @@ where
                                         ProjectType::get_from_parent_folder(
                                             &full_path,
                                         ),
+                                    fingerprint: None,
                                 },
                             ));
                         }
@@ -248,6 +250,7 @@ where
                                     loaders: vec![
                                         mod_loader.as_str().to_string(),
                                     ],
+                                    channel: UpdateChannel::Alpha,
                                     update_version_id: update_version
                                         .id
                                         .clone(),
@@ -325,6 +328,7 @@ where
                             && let Some(locked) = x.locked
                         {
                             return Some(LinkedData {
+                                provider: ContentProvider::Modrinth,
                                 project_id,
                                 version_id,
                                 locked,
@@ -352,7 +356,7 @@ where
                         .and_then(|x| x.custom_env_args),
                     memory: profile
                         .memory
-                        .map(|x| MemorySettings { maximum: x.maximum }),
+                        .map(|x| MemorySettings::with_maximum(x.maximum)),
                     force_fullscreen: profile.fullscreen,
                     game_resolution: profile
                         .resolution
@@ -368,6 +372,10 @@ where
                             .and_then(|x| x.wrapper.clone()),
                         post_exit: profile.hooks.and_then(|x| x.post_exit),
                     },
+                    preferred_account: None,
+                    allow_concurrent_instances: false,
+                    game_options: Vec::new(),
+                    update_policies: HashMap::new(),
                 }
                 .upsert(exec)
                 .await?;
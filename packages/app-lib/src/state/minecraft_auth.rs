@@ -193,6 +193,34 @@ pub struct Credentials {
     pub active: bool,
 }
 
+struct CredentialsQueryResult {
+    uuid: String,
+    active: i64,
+    username: String,
+    access_token: String,
+    refresh_token: String,
+    expires: i64,
+}
+
+impl From<CredentialsQueryResult> for Credentials {
+    fn from(x: CredentialsQueryResult) -> Self {
+        Self {
+            offline_profile: MinecraftProfile {
+                id: Uuid::parse_str(&x.uuid).unwrap_or_default(),
+                name: x.username,
+                ..MinecraftProfile::default()
+            },
+            access_token: x.access_token,
+            refresh_token: x.refresh_token,
+            expires: Utc
+                .timestamp_opt(x.expires, 0)
+                .single()
+                .unwrap_or_else(Utc::now),
+            active: x.active == 1,
+        }
+    }
+}
+
 /// An entry in the player profile cache, keyed by player UUID.
 pub(super) enum ProfileCacheEntry {
     /// A cached profile that is valid, even though it may be stale.
@@ -381,37 +409,73 @@ impl Credentials {
     ) -> crate::Result<Option<Credentials>> {
         let credentials = Self::get_active(exec).await?;
 
-        if let Some(mut creds) = credentials {
-            let res = creds.refresh(exec).await;
-
-            match res {
-                Ok(_) => Ok(Some(creds)),
-                Err(err) => {
-                    if let ErrorKind::MinecraftAuthenticationError(
-                        MinecraftAuthenticationError::Request {
-                            ref source,
-                            ..
-                        },
-                    ) = *err.raw
-                        && (source.is_connect() || source.is_timeout())
-                    {
-                        return Ok(Some(creds));
-                    }
+        if let Some(creds) = credentials {
+            Ok(Some(creds.refresh_for_launch(exec).await?))
+        } else {
+            Ok(None)
+        }
+    }
 
-                    Err(err)
-                }
-            }
+    /// Like [`get_default_credential`](Self::get_default_credential), but
+    /// for the account with the given UUID instead of the selected one.
+    #[tracing::instrument]
+    pub async fn get_credential(
+        uuid: Uuid,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite> + Copy,
+    ) -> crate::Result<Option<Credentials>> {
+        let credentials = Self::get_offline_by_uuid(uuid, exec).await?;
+
+        if let Some(creds) = credentials {
+            Ok(Some(creds.refresh_for_launch(exec).await?))
         } else {
             Ok(None)
         }
     }
 
+    /// Refreshes the credentials, failing unless the refresh succeeds or
+    /// the network is unreachable or times out.
+    async fn refresh_for_launch(
+        mut self,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite> + Copy,
+    ) -> crate::Result<Self> {
+        match self.refresh(exec).await {
+            Ok(_) => Ok(self),
+            Err(err) => {
+                if let ErrorKind::MinecraftAuthenticationError(
+                    MinecraftAuthenticationError::Request {
+                        ref source, ..
+                    },
+                ) = *err.raw
+                    && (source.is_connect() || source.is_timeout())
+                {
+                    return Ok(self);
+                }
+
+                Err(err)
+            }
+        }
+    }
+
     /// Fetches the currently selected credentials from the database, attempting
     /// to refresh them if they are expired.
     pub async fn get_active(
         exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite> + Copy,
     ) -> crate::Result<Option<Self>> {
-        let res = sqlx::query!(
+        let mut credentials = Self::get_active_offline(exec).await?;
+        if let Some(credentials) = &mut credentials {
+            credentials.refresh(exec).await.ok();
+        }
+
+        Ok(credentials)
+    }
+
+    /// Fetches the currently selected credentials from the database as they
+    /// were last stored, without refreshing them.
+    pub async fn get_active_offline(
+        exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite> + Copy,
+    ) -> crate::Result<Option<Self>> {
+        let res = sqlx::query_as!(
+            CredentialsQueryResult,
             "
             SELECT
                 uuid, active, username, access_token, refresh_token, expires
@@ -422,33 +486,37 @@ impl Credentials {
         .fetch_optional(exec)
         .await?;
 
-        Ok(match res {
-            Some(x) => {
-                let mut credentials = Self {
-                    offline_profile: MinecraftProfile {
-                        id: Uuid::parse_str(&x.uuid).unwrap_or_default(),
-                        name: x.username,
-                        ..MinecraftProfile::default()
-                    },
-                    access_token: x.access_token,
-                    refresh_token: x.refresh_token,
-                    expires: Utc
-                        .timestamp_opt(x.expires, 0)
-                        .single()
-                        .unwrap_or_else(Utc::now),
-                    active: x.active == 1,
-                };
-                credentials.refresh(exec).await.ok();
-                Some(credentials)
-            }
-            None => None,
-        })
+        Ok(res.map(Self::from))
+    }
+
+    /// Fetches the credentials of the account with the given UUID from the
+    /// database as they were last stored, without refreshing them.
+    pub async fn get_offline_by_uuid(
+        uuid: Uuid,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite> + Copy,
+    ) -> crate::Result<Option<Self>> {
+        let uuid = uuid.as_hyphenated().to_string();
+        let res = sqlx::query_as!(
+            CredentialsQueryResult,
+            "
+            SELECT
+                uuid, active, username, access_token, refresh_token, expires
+            FROM minecraft_users
+            WHERE uuid = $1
+            ",
+            uuid
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(res.map(Self::from))
     }
 
     pub async fn get_all(
         exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite> + Copy,
     ) -> crate::Result<DashMap<Uuid, Self>> {
-        let res = sqlx::query!(
+        let res = sqlx::query_as!(
+            CredentialsQueryResult,
             "
             SELECT
                 uuid, active, username, access_token, refresh_token, expires
@@ -457,21 +525,8 @@ impl Credentials {
         )
         .fetch(exec)
         .try_fold(DashMap::new(), |acc, x| {
-            let uuid = Uuid::parse_str(&x.uuid).unwrap_or_default();
-            let mut credentials = Self {
-                offline_profile: MinecraftProfile {
-                    id: uuid,
-                    name: x.username,
-                    ..MinecraftProfile::default()
-                },
-                access_token: x.access_token,
-                refresh_token: x.refresh_token,
-                expires: Utc
-                    .timestamp_opt(x.expires, 0)
-                    .single()
-                    .unwrap_or_else(Utc::now),
-                active: x.active == 1,
-            };
+            let mut credentials = Self::from(x);
+            let uuid = credentials.offline_profile.id;
 
             async move {
                 credentials.refresh(exec).await.ok();
@@ -42,7 +42,10 @@ pub use self::mr_auth::*;
 mod legacy_converter;
 
 pub mod attached_world_data;
+pub mod content_store;
+pub mod game_sessions;
 pub mod server_join_log;
+pub mod world_backup_schedules;
 
 // Global state
 // RwLock on state only has concurrent reads, except for config dir change which takes control of the State
@@ -1,28 +1,81 @@
 use crate::event::emit::{emit_process, emit_profile};
 use crate::event::{ProcessPayloadType, ProfilePayloadType};
 use crate::profile;
+use crate::state::game_sessions::GameSession;
+use crate::util::hooks::HookContext;
 use crate::util::io::IOError;
 use crate::util::rpc::RpcServer;
-use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use crate::world_backups::{BackupTrigger, run_scheduled_backups};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use dashmap::DashMap;
 use quick_xml::Reader;
 use quick_xml::events::Event;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::ExitStatus;
+use std::sync::Mutex;
+use std::time::Duration;
 use tempfile::TempDir;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 const LAUNCHER_LOG_PATH: &str = "launcher_log.txt";
+/// The JSON lines sidecar of `launcher_log.txt`, with one [`Log4jEvent`] per
+/// line. Only written for game versions that log with log4j XML events
+pub(crate) const LAUNCHER_LOG_EVENTS_PATH: &str = "launcher_log.jsonl";
+// Subscribers lagging further behind miss the oldest events
+const LOG_EVENTS_CAPACITY: usize = 1024;
+pub(crate) const CONCURRENT_LOGS_PREFIX: &str = "copy-";
+/// How long the game gets to quit after being asked to stop, by default
+pub const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(30);
+// How long to wait for the game to answer a stop call over the launcher RPC
+const STOP_RPC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Gets the subfolder of `logs/` an extra copy of a running profile logs to,
+/// or `None` for the first copy
+pub fn concurrent_logs_subfolder(concurrent_index: u32) -> Option<String> {
+    (concurrent_index > 0)
+        .then(|| format!("{CONCURRENT_LOGS_PREFIX}{}", concurrent_index + 1))
+}
 
 pub struct ProcessManager {
     processes: DashMap<Uuid, Process>,
+    // Concurrent indices of processes that are still being launched, by
+    // profile path
+    reserved_indices: Mutex<HashSet<(String, u32)>>,
+    log_events: broadcast::Sender<ProcessLogEvent>,
+}
+
+/// A concurrent index held for a copy of a profile that is still being
+/// launched. Released once the process is inserted or when dropped, so
+/// copies launched at the same time never get the same index
+pub struct ConcurrentIndexReservation<'a> {
+    manager: &'a ProcessManager,
+    profile_path: String,
+    index: u32,
+}
+
+impl ConcurrentIndexReservation<'_> {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl Drop for ConcurrentIndexReservation<'_> {
+    fn drop(&mut self) {
+        self.manager
+            .reserved_indices
+            .lock()
+            .unwrap()
+            .remove(&(std::mem::take(&mut self.profile_path), self.index));
+    }
 }
 
 impl Default for ProcessManager {
@@ -35,17 +88,24 @@ impl ProcessManager {
     pub fn new() -> Self {
         Self {
             processes: DashMap::new(),
+            reserved_indices: Mutex::new(HashSet::new()),
+            log_events: broadcast::channel(LOG_EVENTS_CAPACITY).0,
         }
     }
 
     #[allow(clippy::too_many_arguments)]
     pub async fn insert_new_process(
         &self,
+        uuid: Uuid,
         profile_path: &str,
+        concurrent_index: ConcurrentIndexReservation<'_>,
+        account: Option<Uuid>,
         mut mc_command: Command,
         post_exit_command: Option<String>,
+        hook_context: HookContext,
         logs_folder: PathBuf,
         xml_logging: bool,
+        reads_console: bool,
         main_class_keep_alive: TempDir,
         rpc_server: RpcServer,
         post_process_init: impl AsyncFnOnce(
@@ -64,12 +124,19 @@ impl ProcessManager {
 
         let mut process = Process {
             metadata: ProcessMetadata {
-                uuid: Uuid::new_v4(),
+                uuid,
                 start_time: Utc::now(),
                 profile_path: profile_path.to_string(),
+                concurrent_index: concurrent_index.index(),
+                account,
             },
             child: mc_proc,
             rpc_server,
+            game_status: GameStatus::default(),
+            worlds_joined: Vec::new(),
+            servers_joined: Vec::new(),
+            killed: false,
+            reads_console,
             _main_class_keep_alive: main_class_keep_alive,
         };
 
@@ -112,13 +179,26 @@ impl ProcessManager {
             writeln!(log_file).map_err(|e| IOError::with_path(e, &log_path))?;
         }
 
+        // The sidecar only holds the events of the latest launch
+        let log_events_path = logs_folder.join(LAUNCHER_LOG_EVENTS_PATH);
+        if log_events_path.exists() {
+            tokio::fs::remove_file(&log_events_path)
+                .await
+                .map_err(|e| IOError::with_path(e, &log_events_path))?;
+        }
+        let log_events = std::sync::Arc::new(LogEventSink {
+            uuid: metadata.uuid,
+            profile_path: metadata.profile_path.clone(),
+            sender: self.log_events.clone(),
+        });
+
         if let Some(stdout) = stdout {
             let log_path_clone = log_path.clone();
 
-            let profile_path = metadata.profile_path.clone();
+            let log_events = log_events.clone();
             tokio::spawn(async move {
                 Process::process_output(
-                    &profile_path,
+                    &log_events,
                     stdout,
                     log_path_clone,
                     xml_logging,
@@ -130,10 +210,10 @@ impl ProcessManager {
         if let Some(stderr) = stderr {
             let log_path_clone = log_path.clone();
 
-            let profile_path = metadata.profile_path.clone();
+            let log_events = log_events.clone();
             tokio::spawn(async move {
                 Process::process_output(
-                    &profile_path,
+                    &log_events,
                     stderr,
                     log_path_clone,
                     xml_logging,
@@ -145,10 +225,14 @@ impl ProcessManager {
         tokio::spawn(Process::sequential_process_manager(
             profile_path.to_string(),
             post_exit_command,
+            hook_context,
             metadata.uuid,
+            log_path,
         ));
 
         self.processes.insert(process.metadata.uuid, process);
+        // The running process holds the index from now on
+        drop(concurrent_index);
 
         emit_process(
             profile_path,
@@ -169,6 +253,40 @@ impl ProcessManager {
         self.processes.get(&id).map(|x| x.rpc_server.clone())
     }
 
+    /// Subscribes to the log4j events of every running process, starting
+    /// with the next event logged
+    pub fn subscribe_log_events(&self) -> broadcast::Receiver<ProcessLogEvent> {
+        self.log_events.subscribe()
+    }
+
+    pub fn get_game_status(&self, id: Uuid) -> Option<GameStatus> {
+        self.processes.get(&id).map(|x| x.game_status.clone())
+    }
+
+    /// Updates what the running game last reported about itself, doing
+    /// nothing if the process is not running anymore
+    pub(crate) fn update_game_status(
+        &self,
+        id: Uuid,
+        update: impl FnOnce(&mut GameStatus),
+    ) {
+        if let Some(mut process) = self.processes.get_mut(&id) {
+            let process = &mut *process;
+            update(&mut process.game_status);
+
+            if let Some(world) = &process.game_status.world
+                && !process.worlds_joined.contains(world)
+            {
+                process.worlds_joined.push(world.clone());
+            }
+            if let Some(server) = &process.game_status.server
+                && !process.servers_joined.contains(server)
+            {
+                process.servers_joined.push(server.clone());
+            }
+        }
+    }
+
     pub fn get_all(&self) -> Vec<ProcessMetadata> {
         self.processes
             .iter()
@@ -176,6 +294,56 @@ impl ProcessManager {
             .collect()
     }
 
+    /// Reserves the lowest concurrent index not taken by a running or
+    /// launching copy of the profile
+    pub fn reserve_concurrent_index(
+        &self,
+        profile_path: &str,
+    ) -> ConcurrentIndexReservation<'_> {
+        let mut reserved = self.reserved_indices.lock().unwrap();
+        let taken = self
+            .processes
+            .iter()
+            .filter(|x| x.metadata.profile_path == profile_path)
+            .map(|x| x.metadata.concurrent_index)
+            .chain(
+                reserved
+                    .iter()
+                    .filter(|(path, _)| path == profile_path)
+                    .map(|(_, index)| *index),
+            )
+            .collect::<HashSet<_>>();
+
+        let index = (0..).find(|x| !taken.contains(x)).unwrap_or_default();
+        reserved.insert((profile_path.to_string(), index));
+
+        ConcurrentIndexReservation {
+            manager: self,
+            profile_path: profile_path.to_string(),
+            index,
+        }
+    }
+
+    /// Whether the process is the longest running copy of its profile, which
+    /// is the one that tracks playtime while several copies are running
+    fn is_oldest_of_profile(&self, id: Uuid) -> bool {
+        let Some(metadata) = self.get(id) else {
+            return false;
+        };
+
+        !self.processes.iter().any(|x| {
+            x.metadata.profile_path == metadata.profile_path
+                && x.metadata.start_time < metadata.start_time
+        })
+    }
+
+    /// Whether any copy of the profile is running
+    fn is_profile_running(&self, profile_path: &str) -> bool {
+        self.processes
+            .iter()
+            .any(|x| x.metadata.profile_path == profile_path)
+    }
+
     pub fn try_wait(
         &self,
         id: Uuid,
@@ -194,17 +362,156 @@ impl ProcessManager {
         Ok(())
     }
 
+    /// Asks the game to quit cleanly, through the launcher RPC or else by
+    /// writing `stop` to its console, and kills it if it is still running
+    /// once `timeout` has passed. A game that can be asked neither way is
+    /// killed right away
+    pub async fn stop(
+        &self,
+        id: Uuid,
+        timeout: Duration,
+    ) -> crate::Result<StopMethod> {
+        let Some(metadata) = self.get(id) else {
+            return Err(crate::ErrorKind::LauncherError(format!(
+                "No running process with UUID {id}"
+            ))
+            .into());
+        };
+
+        let requested = match self.request_stop(id).await {
+            Ok(method) => method,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to ask process {id} to stop, killing it: {e}"
+                );
+                None
+            }
+        };
+
+        let method = match requested {
+            Some(method) if self.wait_for_exit(id, timeout).await => method,
+            _ => {
+                self.kill(id).await?;
+                StopMethod::Killed
+            }
+        };
+
+        emit_process(
+            &metadata.profile_path,
+            id,
+            ProcessPayloadType::Stopped { method },
+            match method {
+                StopMethod::Rpc => "Stopped Minecraft through the launcher RPC",
+                StopMethod::Stdin => "Stopped Minecraft through stdin",
+                StopMethod::Killed => "Killed Minecraft",
+            },
+        )
+        .await?;
+
+        Ok(method)
+    }
+
+    /// Asks the game to quit, returning how, or `None` if it has no way to
+    /// be asked
+    async fn request_stop(
+        &self,
+        id: Uuid,
+    ) -> crate::Result<Option<StopMethod>> {
+        if let Some(rpc) = self.get_rpc(id) {
+            // The game only handles the call once the agent or a mod told the
+            // launcher RPC how to quit, and might not have connected to the
+            // RPC yet
+            match tokio::time::timeout(
+                STOP_RPC_TIMEOUT,
+                rpc.call_method::<bool>("stop"),
+            )
+            .await
+            {
+                Ok(Ok(true)) => return Ok(Some(StopMethod::Rpc)),
+                Ok(Ok(false)) => {}
+                Ok(Err(e)) => {
+                    tracing::debug!("Stop through the launcher RPC failed: {e}")
+                }
+                Err(_) => {
+                    tracing::debug!("Stop through the launcher RPC timed out")
+                }
+            }
+        }
+
+        let stdin = self
+            .processes
+            .get_mut(&id)
+            .filter(|process| process.reads_console)
+            .and_then(|mut process| process.child.stdin.take());
+        let Some(mut stdin) = stdin else {
+            return Ok(None);
+        };
+        stdin.write_all(b"stop\n").await?;
+        stdin.flush().await?;
+
+        Ok(Some(StopMethod::Stdin))
+    }
+
+    /// Waits for the process to exit, returning whether it did in time
+    async fn wait_for_exit(&self, id: Uuid, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while tokio::time::Instant::now() < deadline {
+            if !self.processes.contains_key(&id) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        !self.processes.contains_key(&id)
+    }
+
     pub async fn kill(&self, id: Uuid) -> crate::Result<()> {
         if let Some(mut process) = self.processes.get_mut(&id) {
+            process.killed = true;
             process.child.kill().await?;
         }
 
         Ok(())
     }
 
-    fn remove(&self, id: Uuid) {
-        self.processes.remove(&id);
+    fn remove(&self, id: Uuid) -> Option<Process> {
+        self.processes.remove(&id).map(|(_, process)| process)
+    }
+}
+
+/// Adds a server join to the join log of a profile, whether it was read from
+/// the game logs or reported through the launcher RPC
+pub(crate) async fn record_server_join(
+    profile_path: &str,
+    host: &str,
+    port: u16,
+    timestamp: DateTime<Utc>,
+) -> crate::Result<()> {
+    let state = crate::State::get().await?;
+    crate::state::server_join_log::JoinLogEntry {
+        profile_path: profile_path.to_owned(),
+        host: host.to_string(),
+        port,
+        join_time: timestamp,
     }
+    .upsert(&state.pool)
+    .await?;
+    {
+        let profile_path = profile_path.to_owned();
+        let host = host.to_owned();
+        tokio::spawn(async move {
+            let _ = emit_profile(
+                &profile_path,
+                ProfilePayloadType::ServerJoined {
+                    host,
+                    port,
+                    timestamp,
+                },
+            )
+            .await;
+        });
+    }
+
+    Ok(())
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -212,6 +519,51 @@ pub struct ProcessMetadata {
     pub uuid: Uuid,
     pub profile_path: String,
     pub start_time: DateTime<Utc>,
+    /// 0 for the first running copy of the profile, and 1 and up for extra
+    /// copies launched alongside it
+    #[serde(default)]
+    pub concurrent_index: u32,
+    /// The UUID of the account the game was launched with
+    #[serde(default)]
+    pub account: Option<Uuid>,
+}
+
+/// How a process was ended by `ProcessManager::stop`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopMethod {
+    /// The game quit when asked through the launcher RPC
+    Rpc,
+    /// The game quit after `stop` was written to its console, which only
+    /// servers read
+    Stdin,
+    /// The game did not quit in time, or could not be asked to, and was
+    /// killed
+    Killed,
+}
+
+/// What the game running in a process reported through the launcher RPC
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct GameStatus {
+    /// The folder name of the singleplayer world being played
+    pub world: Option<String>,
+    pub server: Option<GameServer>,
+    pub fps: Option<u32>,
+    pub mods: Vec<LoadedMod>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct GameServer {
+    pub host: String,
+    pub port: u16,
+}
+
+/// A mod loaded by the mod loader of the running game
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LoadedMod {
+    pub id: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
 }
 
 #[derive(Debug)]
@@ -220,20 +572,47 @@ struct Process {
     child: Child,
     _main_class_keep_alive: TempDir,
     rpc_server: RpcServer,
+    game_status: GameStatus,
+    // Everything joined over the whole run, for the session history
+    worlds_joined: Vec<String>,
+    servers_joined: Vec<GameServer>,
+    // Whether the launcher killed the process, so its exit is no crash
+    killed: bool,
+    // Whether the game takes commands from stdin, which only servers do
+    reads_console: bool,
 }
 
-#[derive(Debug, Default)]
-struct Log4jEvent {
-    timestamp: Option<String>,
-    logger: Option<String>,
-    level: Option<String>,
-    thread: Option<String>,
-    message: Option<String>,
+/// A log event written by the game with its log4j XML layout
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct Log4jEvent {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub logger: Option<String>,
+    pub level: Option<String>,
+    pub thread: Option<String>,
+    pub message: Option<String>,
+    /// The formatted stack trace of the exception logged with the event
+    pub throwable: Option<String>,
+}
+
+/// A log event of a running process, as sent to live subscribers
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ProcessLogEvent {
+    pub uuid: Uuid,
+    pub profile_path: String,
+    #[serde(flatten)]
+    pub event: Log4jEvent,
+}
+
+/// Where the output readers of a process send its log events
+struct LogEventSink {
+    uuid: Uuid,
+    profile_path: String,
+    sender: broadcast::Sender<ProcessLogEvent>,
 }
 
 impl Process {
     async fn process_output<R>(
-        profile_path: &str,
+        log_events: &LogEventSink,
         reader: R,
         log_path: impl AsRef<Path>,
         xml_logging: bool,
@@ -295,7 +674,7 @@ impl Process {
                                         }
                                         "timestamp" => {
                                             current_event.timestamp =
-                                                Some(value)
+                                                Process::parse_timestamp(&value)
                                         }
                                         _ => {}
                                     }
@@ -312,139 +691,50 @@ impl Process {
                             _ => {}
                         }
                     }
-                    Ok(Event::End(e)) => {
-                        match e.name().as_ref() {
-                            b"log4j:Message" => {
-                                in_message = false;
-                                current_event.message =
+                    Ok(Event::End(e)) => match e.name().as_ref() {
+                        b"log4j:Message" => {
+                            in_message = false;
+                            current_event.message =
+                                Some(current_content.clone());
+                        }
+                        b"log4j:Throwable" => {
+                            in_throwable = false;
+                            if !current_content.is_empty() {
+                                current_event.throwable =
                                     Some(current_content.clone());
                             }
-                            b"log4j:Throwable" => {
-                                in_throwable = false;
-                                // Process and write the log entry
-                                let thread = current_event
-                                    .thread
-                                    .as_deref()
-                                    .unwrap_or("");
-                                let level = current_event
-                                    .level
-                                    .as_deref()
-                                    .unwrap_or("");
-                                let logger = current_event
-                                    .logger
-                                    .as_deref()
-                                    .unwrap_or("");
-
-                                if let Some(message) = &current_event.message {
-                                    let formatted_time =
-                                        Process::format_timestamp(
-                                            current_event.timestamp.as_deref(),
-                                        );
-                                    let formatted_log = format!(
-                                        "{} [{}] [{}{}]: {}\n",
-                                        formatted_time,
-                                        thread,
-                                        if !logger.is_empty() {
-                                            format!("{logger}/")
-                                        } else {
-                                            String::new()
-                                        },
-                                        level,
-                                        message.trim()
-                                    );
-
-                                    // Write the log message
-                                    if let Err(e) = Process::append_to_log_file(
-                                        &log_path,
-                                        &formatted_log,
-                                    ) {
-                                        tracing::error!(
-                                            "Failed to write to log file: {}",
-                                            e
-                                        );
-                                    }
-
-                                    // Write the throwable if present
-                                    if !current_content.is_empty()
-                                        && let Err(e) =
-                                            Process::append_to_log_file(
-                                                &log_path,
-                                                &current_content,
-                                            )
-                                    {
-                                        tracing::error!(
-                                            "Failed to write throwable to log file: {}",
-                                            e
-                                        );
-                                    }
-                                }
-                            }
-                            b"log4j:Event" => {
-                                in_event = false;
-                                // If no throwable was present, write the log entry at the end of the event
-                                if current_event.message.is_some()
-                                    && !in_throwable
+                        }
+                        b"log4j:Event" => {
+                            in_event = false;
+                            if current_event.message.is_some() {
+                                Process::write_log_event(
+                                    &current_event,
+                                    log_path.as_ref(),
+                                    log_events,
+                                );
+
+                                if let Some(timestamp) = current_event.timestamp
+                                    && let Err(e) =
+                                        Self::parse_and_insert_server_join(
+                                            log_events.uuid,
+                                            &log_events.profile_path,
+                                            current_event
+                                                .message
+                                                .as_deref()
+                                                .unwrap_or("")
+                                                .trim(),
+                                            timestamp,
+                                        )
+                                        .await
                                 {
-                                    let thread = current_event
-                                        .thread
-                                        .as_deref()
-                                        .unwrap_or("");
-                                    let level = current_event
-                                        .level
-                                        .as_deref()
-                                        .unwrap_or("");
-                                    let logger = current_event
-                                        .logger
-                                        .as_deref()
-                                        .unwrap_or("");
-                                    let message = current_event
-                                        .message
-                                        .as_deref()
-                                        .unwrap_or("")
-                                        .trim();
-
-                                    let formatted_time =
-                                        Process::format_timestamp(
-                                            current_event.timestamp.as_deref(),
-                                        );
-                                    let formatted_log = format!(
-                                        "{} [{}] [{}{}]: {}\n",
-                                        formatted_time,
-                                        thread,
-                                        if !logger.is_empty() {
-                                            format!("{logger}/")
-                                        } else {
-                                            String::new()
-                                        },
-                                        level,
-                                        message
+                                    tracing::error!(
+                                        "Failed to handle server join logging: {e}"
                                     );
-
-                                    // Write the log message
-                                    if let Err(e) = Process::append_to_log_file(
-                                        &log_path,
-                                        &formatted_log,
-                                    ) {
-                                        tracing::error!(
-                                            "Failed to write to log file: {}",
-                                            e
-                                        );
-                                    }
-
-                                    if let Some(timestamp) =
-                                        current_event.timestamp.as_deref()
-                                        && let Err(e) = Self::maybe_handle_server_join_logging(
-                                            profile_path,
-                                            timestamp,
-                                            message
-                                        ).await {
-                                            tracing::error!("Failed to handle server join logging: {e}");
-                                        }
                                 }
                             }
-                            _ => {}
                         }
-                    }
+                        _ => {}
+                    },
                     Ok(Event::Text(mut e)) => {
                         if in_message || in_throwable {
                             if let Ok(text) = e.xml_content() {
@@ -490,7 +780,8 @@ impl Process {
                         tracing::warn!("Failed to write to log file: {}", e);
                     }
                     if let Err(e) = Self::maybe_handle_old_server_join_logging(
-                        profile_path,
+                        log_events.uuid,
+                        &log_events.profile_path,
                         line.trim_ascii_end(),
                     )
                     .await
@@ -506,30 +797,84 @@ impl Process {
         }
     }
 
-    fn format_timestamp(timestamp: Option<&str>) -> String {
-        if let Some(timestamp_str) = timestamp {
-            if let Ok(timestamp_val) = timestamp_str.parse::<i64>() {
-                let datetime_utc = if timestamp_val > i32::MAX as i64 {
-                    let secs = timestamp_val / 1000;
-                    let nsecs = ((timestamp_val % 1000) * 1_000_000) as u32;
+    fn parse_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+        let timestamp = timestamp.parse::<i64>().ok()?;
+        if timestamp > i32::MAX as i64 {
+            let secs = timestamp / 1000;
+            let nsecs = ((timestamp % 1000) * 1_000_000) as u32;
 
-                    chrono::DateTime::<Utc>::from_timestamp(secs, nsecs)
-                        .unwrap_or_default()
-                } else {
-                    chrono::DateTime::<Utc>::from_timestamp_secs(timestamp_val)
-                        .unwrap_or_default()
-                };
+            DateTime::<Utc>::from_timestamp(secs, nsecs)
+        } else {
+            DateTime::<Utc>::from_timestamp_secs(timestamp)
+        }
+    }
 
-                let datetime_local = datetime_utc.with_timezone(&chrono::Local);
-                format!("[{}]", datetime_local.format("%H:%M:%S"))
-            } else {
-                "[??:??:??]".to_string()
-            }
+    fn format_timestamp(timestamp: Option<DateTime<Utc>>) -> String {
+        if let Some(timestamp) = timestamp {
+            let datetime_local = timestamp.with_timezone(&chrono::Local);
+            format!("[{}]", datetime_local.format("%H:%M:%S"))
         } else {
             "[??:??:??]".to_string()
         }
     }
 
+    /// Writes a log4j event to the text log and its JSON lines sidecar, and
+    /// sends it to the live subscribers
+    fn write_log_event(
+        event: &Log4jEvent,
+        log_path: &Path,
+        log_events: &LogEventSink,
+    ) {
+        let logger = event.logger.as_deref().unwrap_or("");
+        let formatted_log = format!(
+            "{} [{}] [{}{}]: {}\n",
+            Process::format_timestamp(event.timestamp),
+            event.thread.as_deref().unwrap_or(""),
+            if !logger.is_empty() {
+                format!("{logger}/")
+            } else {
+                String::new()
+            },
+            event.level.as_deref().unwrap_or(""),
+            event.message.as_deref().unwrap_or("").trim()
+        );
+
+        // Write the log message
+        if let Err(e) = Process::append_to_log_file(log_path, &formatted_log) {
+            tracing::error!("Failed to write to log file: {}", e);
+        }
+
+        // Write the throwable if present
+        if let Some(throwable) = &event.throwable
+            && let Err(e) = Process::append_to_log_file(log_path, throwable)
+        {
+            tracing::error!("Failed to write throwable to log file: {}", e);
+        }
+
+        match serde_json::to_string(event) {
+            Ok(mut json) => {
+                json.push('\n');
+                if let Err(e) = Process::append_to_log_file(
+                    log_path.with_file_name(LAUNCHER_LOG_EVENTS_PATH),
+                    &json,
+                ) {
+                    tracing::error!(
+                        "Failed to write to log events file: {}",
+                        e
+                    );
+                }
+            }
+            Err(e) => tracing::error!("Failed to serialize log event: {e}"),
+        }
+
+        // Sending only fails when nobody is subscribed
+        let _ = log_events.sender.send(ProcessLogEvent {
+            uuid: log_events.uuid,
+            profile_path: log_events.profile_path.clone(),
+            event: event.clone(),
+        });
+    }
+
     fn append_to_log_file(
         path: impl AsRef<Path>,
         line: &str,
@@ -541,31 +886,8 @@ impl Process {
         Ok(())
     }
 
-    async fn maybe_handle_server_join_logging(
-        profile_path: &str,
-        timestamp: &str,
-        message: &str,
-    ) -> crate::Result<()> {
-        let timestamp = timestamp
-            .parse::<i64>()
-            .map(|x| x / 1000)
-            .map_err(|x| {
-                crate::ErrorKind::OtherError(format!(
-                    "Failed to parse timestamp: {x}"
-                ))
-            })
-            .and_then(|x| {
-                Utc.timestamp_opt(x, 0).single().ok_or_else(|| {
-                    crate::ErrorKind::OtherError(
-                        "Failed to convert timestamp to DateTime".to_string(),
-                    )
-                })
-            })?;
-        Self::parse_and_insert_server_join(profile_path, message, timestamp)
-            .await
-    }
-
     async fn maybe_handle_old_server_join_logging(
+        uuid: Uuid,
         profile_path: &str,
         line: &str,
     ) -> crate::Result<()> {
@@ -577,15 +899,26 @@ impl Process {
                     .map(|x| x.to_utc())
                     .single()
                     .unwrap_or_else(Utc::now);
-            Self::parse_and_insert_server_join(profile_path, message, timestamp)
-                .await
+            Self::parse_and_insert_server_join(
+                uuid,
+                profile_path,
+                message,
+                timestamp,
+            )
+            .await
         } else {
-            Self::parse_and_insert_server_join(profile_path, line, Utc::now())
-                .await
+            Self::parse_and_insert_server_join(
+                uuid,
+                profile_path,
+                line,
+                Utc::now(),
+            )
+            .await
         }
     }
 
     async fn parse_and_insert_server_join(
+        uuid: Uuid,
         profile_path: &str,
         message: &str,
         timestamp: DateTime<Utc>,
@@ -602,32 +935,57 @@ impl Process {
             return Ok(());
         };
 
+        // Games this old have no launcher RPC to report the server through
         let state = crate::State::get().await?;
-        crate::state::server_join_log::JoinLogEntry {
-            profile_path: profile_path.to_owned(),
-            host: host.to_string(),
-            port,
-            join_time: timestamp,
-        }
-        .upsert(&state.pool)
-        .await?;
-        {
-            let profile_path = profile_path.to_owned();
-            let host = host.to_owned();
-            tokio::spawn(async move {
-                let _ = emit_profile(
-                    &profile_path,
-                    ProfilePayloadType::ServerJoined {
-                        host,
-                        port,
-                        timestamp,
-                    },
-                )
-                .await;
+        state.process_manager.update_game_status(uuid, |status| {
+            status.world = None;
+            status.server = Some(GameServer {
+                host: host.to_string(),
+                port,
             });
-        }
+        });
 
-        Ok(())
+        record_server_join(profile_path, host, port, timestamp).await
+    }
+
+    /// Writes a finished run of the process to the session history
+    async fn record_session(
+        process: Process,
+        exit_status: ExitStatus,
+    ) -> crate::Result<()> {
+        let Process {
+            metadata,
+            worlds_joined,
+            servers_joined,
+            killed,
+            ..
+        } = process;
+
+        // Crash reports are shared by every running copy of the profile, so
+        // one only counts for a process that exited with an error itself
+        let end_time = Utc::now();
+        let start = metadata.start_time.timestamp().max(0) as u64;
+        let end = end_time.timestamp().max(0) as u64;
+        let crash_reported = !exit_status.success()
+            && crate::api::crash::get_crash_files(&metadata.profile_path)
+                .await?
+                .iter()
+                .any(|file| (start..=end).contains(&file.age));
+
+        let state = crate::State::get().await?;
+        GameSession {
+            id: metadata.uuid,
+            profile_path: metadata.profile_path,
+            start_time: metadata.start_time,
+            end_time,
+            exit_code: exit_status.code(),
+            account: metadata.account,
+            worlds: worlds_joined,
+            servers: servers_joined,
+            crashed: crash_reported || (!exit_status.success() && !killed),
+        }
+        .insert(&state.pool)
+        .await
     }
 
     // Spawns a new child process and inserts it into the hashmap
@@ -636,17 +994,24 @@ impl Process {
     async fn sequential_process_manager(
         profile_path: String,
         post_exit_command: Option<String>,
+        mut hook_context: HookContext,
         uuid: Uuid,
+        log_path: PathBuf,
     ) -> crate::Result<()> {
+        // Only the oldest running copy of a profile counts playtime, so
+        // concurrent copies do not count the same time more than once
         async fn update_playtime(
             last_updated_playtime: &mut DateTime<Utc>,
             profile_path: &str,
+            counts_playtime: bool,
             force_update: bool,
         ) {
             let diff = Utc::now()
                 .signed_duration_since(*last_updated_playtime)
                 .num_seconds();
-            if diff >= 60 || force_update {
+            if !counts_playtime {
+                *last_updated_playtime = Utc::now();
+            } else if diff >= 60 || force_update {
                 if let Err(e) = profile::edit(profile_path, |prof| {
                     prof.recent_time_played += diff as u64;
                     async { Ok(()) }
@@ -666,8 +1031,13 @@ impl Process {
         // Wait on current Minecraft Child
         let mc_exit_status;
         let mut last_updated_playtime = Utc::now();
+        let mut last_backup_check = Utc::now();
 
         let state = crate::State::get().await?;
+        let start_time = state
+            .process_manager
+            .get(uuid)
+            .map_or_else(Utc::now, |x| x.start_time);
         loop {
             if let Some(process) = state.process_manager.try_wait(uuid)? {
                 if let Some(t) = process {
@@ -683,11 +1053,33 @@ impl Process {
             tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
 
             // Auto-update playtime every minute
-            update_playtime(&mut last_updated_playtime, &profile_path, false)
-                .await;
+            let is_oldest = state.process_manager.is_oldest_of_profile(uuid);
+            update_playtime(
+                &mut last_updated_playtime,
+                &profile_path,
+                is_oldest,
+                false,
+            )
+            .await;
+
+            // Check for due scheduled world backups every minute
+            if is_oldest
+                && Utc::now()
+                    .signed_duration_since(last_backup_check)
+                    .num_seconds()
+                    >= 60
+            {
+                last_backup_check = Utc::now();
+                spawn_scheduled_backups(
+                    &profile_path,
+                    BackupTrigger::Running,
+                    start_time,
+                );
+            }
         }
 
-        state.process_manager.remove(uuid);
+        let counts_playtime = state.process_manager.is_oldest_of_profile(uuid);
+        let process = state.process_manager.remove(uuid);
         emit_process(
             &profile_path,
             uuid,
@@ -697,7 +1089,24 @@ impl Process {
         .await?;
 
         // Now fully complete- update playtime one last time
-        update_playtime(&mut last_updated_playtime, &profile_path, true).await;
+        update_playtime(
+            &mut last_updated_playtime,
+            &profile_path,
+            counts_playtime,
+            true,
+        )
+        .await;
+
+        // Copies that are still running hold their worlds open, so only the
+        // last one to exit backs up the worlds. Worlds saved by copies that
+        // exited earlier were saved after this one started
+        if !state.process_manager.is_profile_running(&profile_path) {
+            spawn_scheduled_backups(
+                &profile_path,
+                BackupTrigger::Exited,
+                start_time,
+            );
+        }
 
         // Publish play time update
         // Allow failure, it will be stored locally and sent next time
@@ -713,8 +1122,14 @@ impl Process {
             }
         });
 
-        let logs_folder = state.directories.profile_logs_dir(&profile_path);
-        let log_path = logs_folder.join(LAUNCHER_LOG_PATH);
+        if let Some(process) = process
+            && let Err(e) =
+                Process::record_session(process, mc_exit_status).await
+        {
+            tracing::warn!(
+                "Failed to record the session of profile {profile_path}: {e}"
+            );
+        }
 
         if log_path.exists()
             && let Err(e) = Process::append_to_log_file(
@@ -737,28 +1152,36 @@ impl Process {
             }
         }
 
-        if mc_exit_status.success() {
-            // We do not wait on the post exist command to finish running! We let it spawn + run on its own.
-            // This behaviour may be changed in the future
-            if let Some(hook) = post_exit_command {
-                let mut cmd = shlex::split(&hook)
-                    .ok_or_else(|| {
-                        crate::ErrorKind::LauncherError(format!(
-                            "Invalid post-exit command: {hook}",
-                        ))
-                    })?
-                    .into_iter();
-
-                if let Some(command) = cmd.next() {
-                    let mut command = Command::new(command);
-                    command.args(cmd).current_dir(
-                        profile::get_full_path(&profile_path).await?,
-                    );
-                    command.spawn().map_err(IOError::from)?;
-                }
+        // We do not wait on the post exist command to finish running! We let it spawn + run on its own.
+        // It only runs after a successful exit, so not after crashes or kills
+        if mc_exit_status.success()
+            && let Some(hook) = post_exit_command
+        {
+            hook_context.exit_code = Some(mc_exit_status.code().unwrap_or(0));
+            if let Some(mut command) =
+                hook_context.command(&hook, "post-exit")?
+            {
+                command.spawn().map_err(IOError::from)?;
             }
         }
 
         Ok(())
     }
 }
+
+fn spawn_scheduled_backups(
+    profile_path: &str,
+    trigger: BackupTrigger,
+    session_start: DateTime<Utc>,
+) {
+    let profile_path = profile_path.to_string();
+    tokio::spawn(async move {
+        if let Err(e) =
+            run_scheduled_backups(&profile_path, trigger, session_start).await
+        {
+            tracing::warn!(
+                "Failed to run scheduled world backups for profile {profile_path}: {e}"
+            );
+        }
+    });
+}
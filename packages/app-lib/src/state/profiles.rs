@@ -1,8 +1,11 @@
-use super::settings::{Hooks, MemorySettings, WindowSize};
+use super::settings::{
+    GarbageCollector, Hooks, JvmPreset, MemorySettings, WindowSize,
+};
 use crate::profile::get_full_path;
 use crate::state::server_join_log::JoinLogEntry;
 use crate::state::{
-    CacheBehaviour, CachedEntry, CachedFileHash, cache_file_hash,
+    CacheBehaviour, CacheValue, CachedEntry, CachedFileHash,
+    CfCachedFingerprint, ContentProvider, cache_file_hash, content_store,
 };
 use crate::util;
 use crate::util::fetch::{FetchSemaphore, IoSemaphore, write_cached_icon};
@@ -12,7 +15,7 @@ use dashmap::DashMap;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::path::Path;
@@ -20,6 +23,7 @@ use std::sync::LazyLock;
 use tokio::fs::DirEntry;
 use tokio::io::{AsyncBufReadExt, AsyncRead};
 use tokio::task::JoinSet;
+use uuid::Uuid;
 
 // Represent a Minecraft instance.
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -50,11 +54,24 @@ pub struct Profile {
     pub java_path: Option<String>,
     pub extra_launch_args: Option<Vec<String>>,
     pub custom_env_vars: Option<Vec<(String, String)>>,
+    /// Entries set in options.txt before launch, after the global ones
+    #[serde(default)]
+    pub game_options: Vec<(String, String)>,
+    /// How files are kept up to date, keyed by their path relative to the
+    /// profile without the .disabled extension
+    #[serde(default)]
+    pub update_policies: HashMap<String, UpdatePolicy>,
 
     pub memory: Option<MemorySettings>,
     pub force_fullscreen: Option<bool>,
     pub game_resolution: Option<WindowSize>,
     pub hooks: Hooks,
+
+    /// The account used to launch the profile instead of the default one
+    pub preferred_account: Option<Uuid>,
+    /// Whether extra copies of the profile can be launched while it is
+    /// already running, each logging to its own subfolder of `logs/`
+    pub allow_concurrent_instances: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
@@ -131,9 +148,78 @@ impl LauncherFeatureVersion {
     }
 }
 
+/// How a file of a profile is kept up to date. Files without one are
+/// updated to the newest version of any channel for the profile's game
+/// version
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct UpdatePolicy {
+    /// Never update the file
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub channel: UpdateChannel,
+    /// Look for versions of this game version instead of the profile's
+    #[serde(default)]
+    pub game_version: Option<String>,
+}
+
+/// The least stable kind of version a file may be updated to
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    Release,
+    Beta,
+    #[default]
+    Alpha,
+}
+
+impl UpdateChannel {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Release => "release",
+            Self::Beta => "beta",
+            Self::Alpha => "alpha",
+        }
+    }
+
+    pub fn from_string(val: &str) -> Self {
+        match val {
+            "release" => Self::Release,
+            "beta" => Self::Beta,
+            _ => Self::Alpha,
+        }
+    }
+
+    /// The Modrinth version types of this channel
+    pub fn version_types(&self) -> &'static [&'static str] {
+        match *self {
+            Self::Release => &["release"],
+            Self::Beta => &["release", "beta"],
+            Self::Alpha => &["release", "beta", "alpha"],
+        }
+    }
+
+    /// Whether a CurseForge file of a release type (1 is a release, 2 a
+    /// beta and 3 an alpha) belongs to this channel
+    pub fn allows_cf_release_type(&self, release_type: i32) -> bool {
+        match *self {
+            Self::Release => release_type == 1,
+            Self::Beta => release_type == 1 || release_type == 2,
+            Self::Alpha => true,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct LinkedData {
+    /// Where the pack comes from, which decides what the IDs refer to
+    #[serde(default)]
+    pub provider: ContentProvider,
+    /// The Modrinth project ID, or the CurseForge mod ID
     pub project_id: String,
+    /// The Modrinth version ID, or the CurseForge file ID
     pub version_id: String,
 
     pub locked: bool,
@@ -194,7 +280,12 @@ pub struct ProfileFile {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileMetadata {
+    /// Where the file was recognized, which decides what the IDs refer to
+    #[serde(default)]
+    pub provider: ContentProvider,
+    /// The Modrinth project ID, or the CurseForge mod ID
     pub project_id: String,
+    /// The Modrinth version ID, or the CurseForge file ID
     pub version_id: String,
 }
 
@@ -295,6 +386,7 @@ struct ProfileQueryResult {
     linked_project_id: Option<String>,
     linked_version_id: Option<String>,
     locked: Option<i64>,
+    linked_provider: String,
     created: i64,
     modified: i64,
     last_played: Option<i64>,
@@ -303,7 +395,13 @@ struct ProfileQueryResult {
     override_java_path: Option<String>,
     override_extra_launch_args: serde_json::Value,
     override_custom_env_vars: serde_json::Value,
+    game_options: serde_json::Value,
+    update_policies: serde_json::Value,
     override_mc_memory_max: Option<i64>,
+    override_mc_memory_min: Option<i64>,
+    override_mc_garbage_collector: Option<String>,
+    override_mc_jvm_preset: Option<String>,
+    override_mc_memory_auto: i64,
     override_mc_force_fullscreen: Option<i64>,
     override_mc_game_resolution_x: Option<i64>,
     override_mc_game_resolution_y: Option<i64>,
@@ -312,6 +410,8 @@ struct ProfileQueryResult {
     override_hook_post_exit: Option<String>,
     protocol_version: Option<i64>,
     launcher_feature_version: String,
+    preferred_account: Option<String>,
+    allow_concurrent_instances: i64,
 }
 
 impl TryFrom<ProfileQueryResult> for Profile {
@@ -334,6 +434,9 @@ impl TryFrom<ProfileQueryResult> for Profile {
             linked_data: if let Some(project_id) = x.linked_project_id {
                 if let Some(version_id) = x.linked_version_id {
                     x.locked.map(|locked| LinkedData {
+                        provider: ContentProvider::from_string(
+                            &x.linked_provider,
+                        ),
                         project_id,
                         version_id,
                         locked: locked == 1,
@@ -364,9 +467,23 @@ impl TryFrom<ProfileQueryResult> for Profile {
             .ok(),
             custom_env_vars: serde_json::from_value(x.override_custom_env_vars)
                 .ok(),
-            memory: x
-                .override_mc_memory_max
-                .map(|x| MemorySettings { maximum: x as u32 }),
+            game_options: serde_json::from_value(x.game_options)
+                .unwrap_or_default(),
+            update_policies: serde_json::from_value(x.update_policies)
+                .unwrap_or_default(),
+            memory: x.override_mc_memory_max.map(|max| MemorySettings {
+                maximum: max as u32,
+                minimum: x.override_mc_memory_min.map(|x| x as u32),
+                garbage_collector: x
+                    .override_mc_garbage_collector
+                    .as_deref()
+                    .and_then(GarbageCollector::from_string),
+                preset: x
+                    .override_mc_jvm_preset
+                    .as_deref()
+                    .and_then(JvmPreset::from_string),
+                automatic: x.override_mc_memory_auto == 1,
+            }),
             force_fullscreen: x.override_mc_force_fullscreen.map(|x| x == 1),
             game_resolution: if let Some(x_res) =
                 x.override_mc_game_resolution_x
@@ -381,6 +498,10 @@ impl TryFrom<ProfileQueryResult> for Profile {
                 wrapper: x.override_hook_wrapper,
                 post_exit: x.override_hook_post_exit,
             },
+            preferred_account: x
+                .preferred_account
+                .and_then(|x| Uuid::parse_str(&x).ok()),
+            allow_concurrent_instances: x.allow_concurrent_instances == 1,
         })
     }
 }
@@ -394,13 +515,17 @@ macro_rules! select_profiles_with_predicate {
                 path, install_stage, launcher_feature_version, name, icon_path,
                 game_version, protocol_version, mod_loader, mod_loader_version,
                 json(groups) as "groups!: serde_json::Value",
-                linked_project_id, linked_version_id, locked,
+                linked_project_id, linked_version_id, locked, linked_provider,
                 created, modified, last_played,
                 submitted_time_played, recent_time_played,
                 override_java_path,
                 json(override_extra_launch_args) as "override_extra_launch_args!: serde_json::Value", json(override_custom_env_vars) as "override_custom_env_vars!: serde_json::Value",
+                json(game_options) as "game_options!: serde_json::Value",
+                json(update_policies) as "update_policies!: serde_json::Value",
                 override_mc_memory_max, override_mc_force_fullscreen, override_mc_game_resolution_x, override_mc_game_resolution_y,
-                override_hook_pre_launch, override_hook_wrapper, override_hook_post_exit
+                override_mc_memory_min, override_mc_garbage_collector, override_mc_jvm_preset, override_mc_memory_auto,
+                override_hook_pre_launch, override_hook_wrapper, override_hook_post_exit,
+                preferred_account, allow_concurrent_instances
             FROM profiles
             "#
                 + $predicate,
@@ -465,6 +590,11 @@ impl Profile {
         let linked_data_version_id =
             self.linked_data.as_ref().map(|x| x.version_id.clone());
         let linked_data_locked = self.linked_data.as_ref().map(|x| x.locked);
+        let linked_data_provider = self
+            .linked_data
+            .as_ref()
+            .map_or(ContentProvider::Modrinth, |x| x.provider)
+            .as_str();
 
         let created = self.created.timestamp();
         let modified = self.modified.timestamp();
@@ -474,12 +604,25 @@ impl Profile {
         let recent_time_played = self.recent_time_played as i64;
 
         let memory_max = self.memory.map(|x| x.maximum);
+        let memory_min = self.memory.and_then(|x| x.minimum);
+        let garbage_collector = self
+            .memory
+            .and_then(|x| x.garbage_collector)
+            .map(|x| x.as_str());
+        let jvm_preset = self.memory.and_then(|x| x.preset).map(|x| x.as_str());
+        let memory_auto = self.memory.is_some_and(|x| x.automatic);
 
         let game_resolution_x = self.game_resolution.map(|x| x.0);
         let game_resolution_y = self.game_resolution.map(|x| x.1);
 
         let extra_launch_args = serde_json::to_string(&self.extra_launch_args)?;
         let custom_env_vars = serde_json::to_string(&self.custom_env_vars)?;
+        let game_options = serde_json::to_string(&self.game_options)?;
+        let update_policies = serde_json::to_string(&self.update_policies)?;
+
+        let preferred_account = self
+            .preferred_account
+            .map(|x| x.as_hyphenated().to_string());
 
         sqlx::query!(
             "
@@ -493,7 +636,10 @@ impl Profile {
                 override_java_path, override_extra_launch_args, override_custom_env_vars,
                 override_mc_memory_max, override_mc_force_fullscreen, override_mc_game_resolution_x, override_mc_game_resolution_y,
                 override_hook_pre_launch, override_hook_wrapper, override_hook_post_exit,
-                protocol_version, launcher_feature_version
+                protocol_version, launcher_feature_version,
+                preferred_account, allow_concurrent_instances,
+                override_mc_memory_min, override_mc_garbage_collector, override_mc_jvm_preset, override_mc_memory_auto,
+                game_options, linked_provider, update_policies
             )
             VALUES (
                 $1, $2, $3, $4,
@@ -505,7 +651,10 @@ impl Profile {
                 $17, jsonb($18), jsonb($19),
                 $20, $21, $22, $23,
                 $24, $25, $26,
-                $27, $28
+                $27, $28,
+                $29, $30,
+                $31, $32, $33, $34,
+                jsonb($35), $36, jsonb($37)
             )
             ON CONFLICT (path) DO UPDATE SET
                 install_stage = $2,
@@ -542,7 +691,19 @@ impl Profile {
                 override_hook_post_exit = $26,
 
                 protocol_version = $27,
-                launcher_feature_version = $28
+                launcher_feature_version = $28,
+
+                preferred_account = $29,
+                allow_concurrent_instances = $30,
+
+                override_mc_memory_min = $31,
+                override_mc_garbage_collector = $32,
+                override_mc_jvm_preset = $33,
+                override_mc_memory_auto = $34,
+
+                game_options = jsonb($35),
+                linked_provider = $36,
+                update_policies = jsonb($37)
             ",
             self.path,
             install_stage,
@@ -571,7 +732,16 @@ impl Profile {
             self.hooks.wrapper,
             self.hooks.post_exit,
             self.protocol_version,
-            launcher_feature_version
+            launcher_feature_version,
+            preferred_account,
+            self.allow_concurrent_instances,
+            memory_min,
+            garbage_collector,
+            jvm_preset,
+            memory_auto,
+            game_options,
+            linked_data_provider,
+            update_policies
         )
             .execute(exec)
             .await?;
@@ -715,7 +885,7 @@ impl Profile {
             .filter_map(|file| {
                 all.iter()
                     .find(|prof| file.path.contains(&prof.path))
-                    .map(|profile| Self::get_cache_key(file, profile))
+                    .and_then(|profile| Self::get_cache_key(file, profile))
             })
             .collect::<Vec<_>>();
 
@@ -969,7 +1139,7 @@ impl Profile {
 
         let file_updates = file_hashes
             .iter()
-            .map(|x| Self::get_cache_key(x, self))
+            .filter_map(|x| Self::get_cache_key(x, self))
             .collect::<Vec<_>>();
 
         let file_hashes_ref =
@@ -991,11 +1161,120 @@ impl Profile {
             )
         )?;
 
+        // Files Modrinth does not know may come from CurseForge, which
+        // recognizes files by their fingerprint
+        let mut fingerprints = HashMap::new();
+        let mut fingerprinted = Vec::new();
+        for hash in &file_hashes {
+            if file_info.iter().any(|x| x.hash == hash.hash) {
+                continue;
+            }
+
+            let fingerprint = if let Some(fingerprint) = hash.fingerprint {
+                fingerprint
+            } else if let Some(initial_file) = keys
+                .iter()
+                .find(|x| x.path == hash.path.trim_end_matches(".disabled"))
+            {
+                let file_path = path
+                    .join(initial_file.project_type.get_folder())
+                    .join(&initial_file.file_name);
+                let bytes = match io::read(&file_path).await {
+                    Ok(bytes) => bytes,
+                    Err(err) => {
+                        tracing::warn!(
+                            "Unable to fingerprint {}: {err}",
+                            file_path.display()
+                        );
+                        continue;
+                    }
+                };
+                let fingerprint = i64::from(
+                    crate::api::curseforge::compute_fingerprint(&bytes),
+                );
+                fingerprinted.push(
+                    CacheValue::FileHash(CachedFileHash {
+                        fingerprint: Some(fingerprint),
+                        ..hash.clone()
+                    })
+                    .get_entry(),
+                );
+                fingerprint
+            } else {
+                continue;
+            };
+
+            fingerprints.insert(hash.hash.clone(), (fingerprint, hash));
+        }
+        if !fingerprinted.is_empty() {
+            CachedEntry::upsert_many(&fingerprinted, pool).await?;
+        }
+
+        // CurseForge being unreachable should not hide the Modrinth files
+        let fingerprint_keys = fingerprints
+            .values()
+            .map(|(fingerprint, _)| fingerprint.to_string())
+            .collect::<Vec<_>>();
+        let cf_files = CachedEntry::get_cf_fingerprint_many(
+            &fingerprint_keys.iter().map(|x| &**x).collect::<Vec<_>>(),
+            cache_behaviour,
+            pool,
+            fetch_semaphore,
+        )
+        .await
+        .unwrap_or_else(|err| {
+            tracing::warn!("Unable to look up files on CurseForge: {err}");
+            Vec::new()
+        });
+
+        let cf_update_keys = fingerprints
+            .values()
+            .filter_map(|(fingerprint, hash)| {
+                let cf_file =
+                    cf_files.iter().find(|x| x.fingerprint == *fingerprint)?;
+                Self::get_cf_update_key(cf_file.mod_id, hash, self)
+            })
+            .collect::<Vec<_>>();
+        let cf_updates = CachedEntry::get_cf_file_update_many(
+            &cf_update_keys.iter().map(|x| &**x).collect::<Vec<_>>(),
+            cache_behaviour,
+            pool,
+            fetch_semaphore,
+        )
+        .await
+        .unwrap_or_else(|err| {
+            tracing::warn!(
+                "Unable to check CurseForge files for updates: {err}"
+            );
+            Vec::new()
+        });
+
+        let cf_files = fingerprints
+            .into_iter()
+            .filter_map(|(hash, (fingerprint, _))| {
+                let cf_file = cf_files
+                    .iter()
+                    .find(|x| x.fingerprint == fingerprint)?
+                    .clone();
+                Some((hash, cf_file))
+            })
+            .collect::<HashMap<_, _>>();
+
         let files = DashMap::new();
 
-        for hash in file_hashes {
+        for hash in &file_hashes {
             let info_index = file_info.iter().position(|x| x.hash == hash.hash);
             let file = info_index.map(|x| file_info.remove(x));
+            let cf_file = cf_files.get(&hash.hash);
+            // The channel and game version updates were looked up for
+            let update_target = Self::get_update_policy(hash, self)
+                .map(|x| (x.channel, self.get_update_game_version(&x)));
+            let is_update_target =
+                |channel: UpdateChannel, game_version: &str| {
+                    update_target
+                        .as_ref()
+                        .is_some_and(|x| x.0 == channel && x.1 == game_version)
+                };
 
             if let Some(initial_file_index) = keys
                 .iter()
@@ -1011,7 +1290,10 @@ impl Profile {
 
                 let update_version_id = if let Some(update) = file_updates
                     .iter()
-                    .find(|x| x.hash == hash.hash)
+                    .find(|x| {
+                        x.hash == hash.hash
+                            && is_update_target(x.channel, &x.game_version)
+                    })
                     .map(|x| x.update_version_id.clone())
                 {
                     if let Some(metadata) = &file {
@@ -1023,19 +1305,39 @@ impl Profile {
                     } else {
                         None
                     }
+                } else if let Some(cf_file) = cf_file {
+                    cf_updates
+                        .iter()
+                        .find(|x| {
+                            x.mod_id == cf_file.mod_id
+                                && is_update_target(x.channel, &x.game_version)
+                        })
+                        .filter(|x| x.update_file_id != cf_file.file_id)
+                        .map(|x| x.update_file_id.to_string())
                 } else {
                     None
                 };
 
+                let metadata = if let Some(file) = file {
+                    Some(FileMetadata {
+                        provider: ContentProvider::Modrinth,
+                        project_id: file.project_id,
+                        version_id: file.version_id,
+                    })
+                } else {
+                    cf_file.map(|x| FileMetadata {
+                        provider: ContentProvider::CurseForge,
+                        project_id: x.mod_id.to_string(),
+                        version_id: x.file_id.to_string(),
+                    })
+                };
+
                 let file = ProfileFile {
                     update_version_id,
-                    hash: hash.hash,
+                    hash: hash.hash.clone(),
                     file_name: initial_file.file_name,
                     size: initial_file.size,
-                    metadata: file.map(|x| FileMetadata {
-                        project_id: x.project_id,
-                        version_id: x.version_id,
-                    }),
+                    metadata,
                     project_type: initial_file.project_type,
                 };
                 files.insert(path, file);
@@ -1045,9 +1347,38 @@ impl Profile {
         Ok(files)
     }
 
-    fn get_cache_key(file: &CachedFileHash, profile: &Profile) -> String {
-        format!(
-            "{}-{}-{}",
+    /// The update policy of a file, or None if it is pinned
+    fn get_update_policy(
+        file: &CachedFileHash,
+        profile: &Profile,
+    ) -> Option<UpdatePolicy> {
+        let policy = file
+            .path
+            .strip_prefix(&format!("{}/", profile.path))
+            .and_then(|x| {
+                profile.update_policies.get(x.trim_end_matches(".disabled"))
+            })
+            .cloned()
+            .unwrap_or_default();
+
+        (!policy.pinned).then_some(policy)
+    }
+
+    fn get_update_game_version(&self, policy: &UpdatePolicy) -> String {
+        policy
+            .game_version
+            .clone()
+            .unwrap_or_else(|| self.game_version.clone())
+    }
+
+    fn get_cache_key(
+        file: &CachedFileHash,
+        profile: &Profile,
+    ) -> Option<String> {
+        let policy = Self::get_update_policy(file, profile)?;
+
+        Some(format!(
+            "{}-{}-{}-{}",
             file.hash,
             file.project_type
                 .filter(|x| *x != ProjectType::Mod)
@@ -1055,8 +1386,26 @@ impl Profile {
                     || profile.loader.as_str().to_string(),
                     |x| x.get_loaders().join("+")
                 ),
-            profile.game_version
-        )
+            policy.channel.as_str(),
+            profile.get_update_game_version(&policy)
+        ))
+    }
+
+    fn get_cf_update_key(
+        mod_id: i32,
+        file: &CachedFileHash,
+        profile: &Profile,
+    ) -> Option<String> {
+        let policy = Self::get_update_policy(file, profile)?;
+
+        Some(format!(
+            "{mod_id}-{}-{}-{}",
+            file.project_type
+                .filter(|x| *x != ProjectType::Mod)
+                .map_or(profile.loader.as_str(), |_| "any"),
+            policy.channel.as_str(),
+            profile.get_update_game_version(&policy)
+        ))
     }
 
     #[tracing::instrument(skip(pool))]
@@ -1172,6 +1521,20 @@ impl Profile {
         )
         .await?;
 
+        // The file is known, so get_projects does not have to match it
+        if let Some(fingerprint) = file.file_fingerprint {
+            CachedEntry::upsert_many(
+                &[CacheValue::CfFingerprint(CfCachedFingerprint {
+                    fingerprint,
+                    mod_id,
+                    file_id,
+                })
+                .get_entry()],
+                pool,
+            )
+            .await?;
+        }
+
         Ok(path)
     }
 
@@ -1256,8 +1619,13 @@ impl Profile {
         )
         .await?;
 
-        util::fetch::write(&path.join(&project_path), &bytes, io_semaphore)
-            .await?;
+        content_store::link_bytes(
+            bytes,
+            hash,
+            &path.join(&project_path),
+            io_semaphore,
+        )
+        .await?;
 
         Ok(project_path)
     }
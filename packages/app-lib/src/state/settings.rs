@@ -55,6 +55,10 @@ pub struct Settings {
 
     pub extra_launch_args: Vec<String>,
     pub custom_env_vars: Vec<(String, String)>,
+    /// Entries set in the options.txt of every profile before launch
+    pub game_options: Vec<(String, String)>,
+    /// Entries written to the options.txt of new profiles
+    pub default_game_options: Vec<(String, String)>,
     pub memory: MemorySettings,
     pub force_fullscreen: bool,
     pub game_resolution: WindowSize,
@@ -101,7 +105,9 @@ impl Settings {
                 discord_rpc, developer_mode, telemetry, personalized_ads,
                 onboarded,
                 json(extra_launch_args) extra_launch_args, json(custom_env_vars) custom_env_vars,
-                mc_memory_max, mc_force_fullscreen, mc_game_resolution_x, mc_game_resolution_y, hide_on_process_start,
+                json(game_options) game_options, json(default_game_options) default_game_options,
+                mc_memory_max, mc_memory_min, mc_garbage_collector, mc_jvm_preset, mc_memory_auto,
+                mc_force_fullscreen, mc_game_resolution_x, mc_game_resolution_y, hide_on_process_start,
                 hook_pre_launch, hook_wrapper, hook_post_exit,
                 custom_dir, prev_custom_dir, migrated, json(feature_flags) feature_flags, toggle_sidebar,
                 skipped_update, pending_update_toast_for_version, auto_download_updates,
@@ -140,8 +146,28 @@ impl Settings {
                 .as_ref()
                 .and_then(|x| serde_json::from_str(x).ok())
                 .unwrap_or_default(),
+            game_options: res
+                .game_options
+                .as_ref()
+                .and_then(|x| serde_json::from_str(x).ok())
+                .unwrap_or_default(),
+            default_game_options: res
+                .default_game_options
+                .as_ref()
+                .and_then(|x| serde_json::from_str(x).ok())
+                .unwrap_or_default(),
             memory: MemorySettings {
                 maximum: res.mc_memory_max as u32,
+                minimum: res.mc_memory_min.map(|x| x as u32),
+                garbage_collector: res
+                    .mc_garbage_collector
+                    .as_deref()
+                    .and_then(GarbageCollector::from_string),
+                preset: res
+                    .mc_jvm_preset
+                    .as_deref()
+                    .and_then(JvmPreset::from_string),
+                automatic: res.mc_memory_auto == 1,
             },
             force_fullscreen: res.mc_force_fullscreen == 1,
             game_resolution: WindowSize(
@@ -186,8 +212,14 @@ impl Settings {
         let extra_launch_args = serde_json::to_string(&self.extra_launch_args)?;
         let custom_env_vars = serde_json::to_string(&self.custom_env_vars)?;
         let feature_flags = serde_json::to_string(&self.feature_flags)?;
+        let game_options = serde_json::to_string(&self.game_options)?;
+        let default_game_options =
+            serde_json::to_string(&self.default_game_options)?;
         let default_content_provider = self.default_content_provider.as_str();
         let version = self.version as i64;
+        let garbage_collector =
+            self.memory.garbage_collector.map(|x| x.as_str());
+        let jvm_preset = self.memory.preset.map(|x| x.as_str());
 
         sqlx::query!(
             "
@@ -238,7 +270,15 @@ impl Settings {
                 curseforge_api_key = $34,
                 default_content_provider = $35,
 
-                version = $36
+                version = $36,
+
+                mc_memory_min = $37,
+                mc_garbage_collector = $38,
+                mc_jvm_preset = $39,
+                mc_memory_auto = $40,
+
+                game_options = jsonb($41),
+                default_game_options = jsonb($42)
             ",
             max_concurrent_writes,
             max_concurrent_downloads,
@@ -276,6 +316,12 @@ impl Settings {
             self.curseforge_api_key,
             default_content_provider,
             version,
+            self.memory.minimum,
+            garbage_collector,
+            jvm_preset,
+            self.memory.automatic,
+            game_options,
+            default_game_options,
         )
         .execute(exec)
         .await?;
@@ -424,6 +470,83 @@ impl AccentColor {
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct MemorySettings {
     pub maximum: u32,
+    /// The initial heap size in MB. Defaults to `maximum` with a preset,
+    /// and to the JVM default otherwise
+    #[serde(default)]
+    pub minimum: Option<u32>,
+    /// The garbage collector to use, or the JVM default if unset
+    #[serde(default)]
+    pub garbage_collector: Option<GarbageCollector>,
+    #[serde(default)]
+    pub preset: Option<JvmPreset>,
+    /// Size the heap from the number of mods and the system memory at
+    /// launch, instead of using `maximum`
+    #[serde(default)]
+    pub automatic: bool,
+}
+
+impl MemorySettings {
+    pub fn with_maximum(maximum: u32) -> Self {
+        Self {
+            maximum,
+            minimum: None,
+            garbage_collector: None,
+            preset: None,
+            automatic: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GarbageCollector {
+    G1,
+    /// Requires Java 15 or newer
+    Zgc,
+    /// Requires Java 12 or newer, and a build of Java that includes it
+    Shenandoah,
+}
+
+impl GarbageCollector {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::G1 => "g1",
+            Self::Zgc => "zgc",
+            Self::Shenandoah => "shenandoah",
+        }
+    }
+
+    pub fn from_string(string: &str) -> Option<Self> {
+        match string {
+            "g1" => Some(Self::G1),
+            "zgc" => Some(Self::Zgc),
+            "shenandoah" => Some(Self::Shenandoah),
+            _ => None,
+        }
+    }
+}
+
+/// A named set of JVM tuning flags
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JvmPreset {
+    /// Aikar's G1 flags, tuned for short garbage collection pauses
+    Aikar,
+}
+
+impl JvmPreset {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Aikar => "aikar",
+        }
+    }
+
+    pub fn from_string(string: &str) -> Option<Self> {
+        match string {
+            "aikar" => Some(Self::Aikar),
+            _ => None,
+        }
+    }
 }
 
 /// Game window size
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// When automatic snapshots of a world are taken
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WorldBackupSchedule {
+    /// Snapshot every world that was played once the game exits
+    pub on_exit: bool,
+    /// Snapshot the world that is currently open every N minutes
+    pub interval_minutes: Option<u32>,
+    /// Snapshot played worlds at most once a day
+    pub daily: bool,
+    pub retention: BackupRetention,
+}
+
+/// Which snapshots of a world are kept after a scheduled backup
+///
+/// Snapshots matched by neither `keep_last` nor `keep_daily_days` are
+/// removed, unless both are unset. The newest snapshot is always kept.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct BackupRetention {
+    /// Keep the newest N snapshots
+    pub keep_last: Option<u32>,
+    /// Keep the newest snapshot of each of the last N days
+    pub keep_daily_days: Option<u32>,
+    /// Remove the oldest snapshots until the world's snapshots take up at
+    /// most this many bytes
+    pub max_total_size: Option<u64>,
+}
+
+impl BackupRetention {
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.is_none()
+            && self.keep_daily_days.is_none()
+            && self.max_total_size.is_none()
+    }
+}
+
+struct WorldBackupScheduleQueryResult {
+    world_id: String,
+    on_exit: i64,
+    interval_minutes: Option<i64>,
+    daily: i64,
+    keep_last: Option<i64>,
+    keep_daily_days: Option<i64>,
+    max_total_size: Option<i64>,
+}
+
+impl From<WorldBackupScheduleQueryResult> for WorldBackupSchedule {
+    fn from(x: WorldBackupScheduleQueryResult) -> Self {
+        Self {
+            on_exit: x.on_exit == 1,
+            interval_minutes: x.interval_minutes.map(|x| x as u32),
+            daily: x.daily == 1,
+            retention: BackupRetention {
+                keep_last: x.keep_last.map(|x| x as u32),
+                keep_daily_days: x.keep_daily_days.map(|x| x as u32),
+                max_total_size: x.max_total_size.map(|x| x as u64),
+            },
+        }
+    }
+}
+
+impl WorldBackupSchedule {
+    /// Gets the schedule stored for a world, or the instance-wide schedule
+    /// if `world_id` is `None`
+    pub async fn get(
+        instance: &str,
+        world_id: Option<&str>,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite>,
+    ) -> crate::Result<Option<Self>> {
+        let world_id = world_id.unwrap_or_default();
+
+        let schedule = sqlx::query_as!(
+            WorldBackupScheduleQueryResult,
+            "
+            SELECT world_id, on_exit, interval_minutes, daily, keep_last, keep_daily_days, max_total_size
+            FROM world_backup_schedules
+            WHERE profile_path = $1 and world_id = $2
+            ",
+            instance,
+            world_id
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(schedule.map(Self::from))
+    }
+
+    /// Gets every schedule of an instance, keyed by world ID. The
+    /// instance-wide schedule is keyed by `None`
+    pub async fn get_all_for_instance(
+        instance: &str,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite>,
+    ) -> crate::Result<HashMap<Option<String>, Self>> {
+        let schedules = sqlx::query_as!(
+            WorldBackupScheduleQueryResult,
+            "
+            SELECT world_id, on_exit, interval_minutes, daily, keep_last, keep_daily_days, max_total_size
+            FROM world_backup_schedules
+            WHERE profile_path = $1
+            ",
+            instance
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(schedules
+            .into_iter()
+            .map(|x| {
+                let world_id =
+                    Some(x.world_id.clone()).filter(|x| !x.is_empty());
+                (world_id, Self::from(x))
+            })
+            .collect())
+    }
+
+    pub async fn upsert(
+        &self,
+        instance: &str,
+        world_id: Option<&str>,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite>,
+    ) -> crate::Result<()> {
+        let world_id = world_id.unwrap_or_default();
+        let interval_minutes = self.interval_minutes.map(|x| x as i64);
+        let keep_last = self.retention.keep_last.map(|x| x as i64);
+        let keep_daily_days = self.retention.keep_daily_days.map(|x| x as i64);
+        let max_total_size = self.retention.max_total_size.map(|x| x as i64);
+
+        sqlx::query!(
+            "
+            INSERT INTO world_backup_schedules (
+                profile_path, world_id, on_exit, interval_minutes, daily,
+                keep_last, keep_daily_days, max_total_size
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (profile_path, world_id) DO UPDATE SET
+                on_exit = $3,
+                interval_minutes = $4,
+                daily = $5,
+                keep_last = $6,
+                keep_daily_days = $7,
+                max_total_size = $8
+            ",
+            instance,
+            world_id,
+            self.on_exit,
+            interval_minutes,
+            self.daily,
+            keep_last,
+            keep_daily_days,
+            max_total_size,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(
+        instance: &str,
+        world_id: Option<&str>,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Sqlite>,
+    ) -> crate::Result<()> {
+        let world_id = world_id.unwrap_or_default();
+
+        sqlx::query!(
+            "
+            DELETE FROM world_backup_schedules
+            WHERE profile_path = $1 and world_id = $2
+            ",
+            instance,
+            world_id
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+}
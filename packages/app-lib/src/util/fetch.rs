@@ -262,6 +262,10 @@ pub async fn write(
         io::create_dir_all(parent).await?;
     }
 
+    // Profile files may be hard links into the content store, so they are
+    // replaced instead of truncated, which would change every linked copy
+    let _ = tokio::fs::remove_file(path).await;
+
     let mut file = File::create(path)
         .await
         .map_err(|e| IOError::with_path(e, path))?;
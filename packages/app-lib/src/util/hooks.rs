@@ -0,0 +1,159 @@
+//! Variables passed to the pre-launch, wrapper and post-exit hooks
+//!
+//! Every hook is run with these environment variables set, and `$NAME` or
+//! `${NAME}` tokens in the hook command are replaced with their values:
+//!
+//! | Variable            | Value                                          |
+//! |---------------------|------------------------------------------------|
+//! | `INST_NAME`         | Name of the profile                            |
+//! | `INST_ID`           | Path of the profile, relative to the profiles  |
+//! |                     | directory                                      |
+//! | `INST_DIR`          | Full path of the profile directory             |
+//! | `INST_MC_VERSION`   | Minecraft version of the profile               |
+//! | `INST_JAVA`         | Path of the Java executable the game runs with |
+//! | `INST_PROCESS_UUID` | UUID of the game process                       |
+//! | `INST_EXIT_CODE`    | Exit code of the game, only set for post-exit  |
+//! |                     | hooks. Always `0`, as they only run after the  |
+//! |                     | game exits successfully                        |
+//!
+//! Unknown tokens are left untouched, so commands can still refer to other
+//! environment variables through a shell.
+use crate::state::Profile;
+use std::path::PathBuf;
+use tokio::process::Command;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct HookContext {
+    pub instance_name: String,
+    pub instance_id: String,
+    pub instance_dir: PathBuf,
+    pub game_version: String,
+    pub java_path: String,
+    pub process_uuid: Uuid,
+    pub exit_code: Option<i32>,
+}
+
+impl HookContext {
+    pub fn new(
+        profile: &Profile,
+        instance_dir: PathBuf,
+        java_path: String,
+        process_uuid: Uuid,
+    ) -> Self {
+        Self {
+            instance_name: profile.name.clone(),
+            instance_id: profile.path.clone(),
+            instance_dir,
+            game_version: profile.game_version.clone(),
+            java_path,
+            process_uuid,
+            exit_code: None,
+        }
+    }
+
+    pub fn variables(&self) -> Vec<(&'static str, String)> {
+        let mut variables = vec![
+            ("INST_NAME", self.instance_name.clone()),
+            ("INST_ID", self.instance_id.clone()),
+            ("INST_DIR", self.instance_dir.to_string_lossy().to_string()),
+            ("INST_MC_VERSION", self.game_version.clone()),
+            ("INST_JAVA", self.java_path.clone()),
+            ("INST_PROCESS_UUID", self.process_uuid.to_string()),
+        ];
+        if let Some(exit_code) = self.exit_code {
+            variables.push(("INST_EXIT_CODE", exit_code.to_string()));
+        }
+        variables
+    }
+
+    /// Splits a hook command line into a command with the variables
+    /// substituted and set in its environment, running in the profile
+    /// directory. Returns `None` for an empty command.
+    pub fn command(
+        &self,
+        hook: &str,
+        hook_name: &str,
+    ) -> crate::Result<Option<Command>> {
+        let variables = self.variables();
+        let mut args = shlex::split(hook)
+            .ok_or_else(|| {
+                crate::ErrorKind::LauncherError(format!(
+                    "Invalid {hook_name} command: {hook}",
+                ))
+            })?
+            .into_iter()
+            .map(|arg| substitute(&arg, &variables));
+
+        let Some(program) = args.next() else {
+            return Ok(None);
+        };
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .envs(variables)
+            .current_dir(&self.instance_dir);
+        Ok(Some(command))
+    }
+}
+
+/// Replaces `$NAME` and `${NAME}` tokens of known variables in `arg`
+pub fn substitute(arg: &str, variables: &[(&str, String)]) -> String {
+    let mut result = String::with_capacity(arg.len());
+    let mut rest = arg;
+
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+
+        let (name, token_len) = if let Some(braced) = rest.strip_prefix('{')
+            && let Some(end) = braced.find('}')
+        {
+            (&braced[..end], end + 2)
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (&rest[..end], end)
+        };
+
+        match variables.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => result.push_str(value),
+            None => {
+                result.push('$');
+                result.push_str(&rest[..token_len]);
+            }
+        }
+        rest = &rest[token_len..];
+    }
+    result.push_str(rest);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute() {
+        let variables = [
+            ("INST_NAME", "My Pack".to_string()),
+            ("INST_EXIT_CODE", "0".to_string()),
+        ];
+
+        assert_eq!(
+            substitute("--name=$INST_NAME", &variables),
+            "--name=My Pack"
+        );
+        assert_eq!(
+            substitute("${INST_NAME}_backup.zip", &variables),
+            "My Pack_backup.zip"
+        );
+        assert_eq!(substitute("exit:$INST_EXIT_CODE!", &variables), "exit:0!");
+        assert_eq!(substitute("$HOME/$UNKNOWN", &variables), "$HOME/$UNKNOWN");
+        assert_eq!(substitute("${UNCLOSED", &variables), "${UNCLOSED");
+        assert_eq!(substitute("cost $", &variables), "cost $");
+    }
+}
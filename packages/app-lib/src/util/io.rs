@@ -36,6 +36,19 @@ impl IOError {
         }
     }
 
+    /// Converts an error of a directory walk, keeping the path it failed at
+    pub fn from_walkdir(error: async_walkdir::Error) -> Self {
+        let path = error.path().map(|x| x.to_string_lossy().to_string());
+        let source = error.into_io().unwrap_or_else(|| {
+            std::io::Error::other("failed to walk directory")
+        });
+
+        match path {
+            Some(path) => Self::IOPathError { source, path },
+            None => Self::IOError(source),
+        }
+    }
+
     pub fn kind(&self) -> ErrorKind {
         match self {
             IOError::IOPathError { source, .. } => source.kind(),
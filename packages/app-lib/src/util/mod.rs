@@ -1,5 +1,6 @@
 //! Theseus utility functions
 pub mod fetch;
+pub mod hooks;
 pub mod io;
 pub mod jre;
 pub mod network;
@@ -7,3 +8,4 @@ pub mod platform;
 pub mod protocol_version;
 pub mod rpc;
 pub mod server_ping;
+pub(crate) mod snapshot_store;
@@ -0,0 +1,293 @@
+//! Content-addressed store for snapshots
+//!
+//! Objects live at `<root>/objects/<first two hash characters>/<sha1>` and
+//! are shared by every snapshot referencing them. Each snapshot is a JSON
+//! manifest at `<root>/snapshots/<id>.json`. Objects no manifest references
+//! anymore are removed by garbage collection.
+
+use crate::api::worlds::find_available_name;
+use crate::util::io::{self, IOError};
+use crate::{ErrorKind, Result};
+use async_walkdir::WalkDir;
+use chrono::{DateTime, Local, Utc};
+use futures::StreamExt;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// A snapshot manifest kept in a [`SnapshotStore`]
+pub(crate) trait SnapshotManifest: Serialize + DeserializeOwned {
+    fn created(&self) -> DateTime<Utc>;
+
+    /// The hashes of the objects the snapshot references
+    fn objects(&self) -> impl Iterator<Item = &str>;
+}
+
+pub(crate) struct SnapshotStore {
+    root: PathBuf,
+    // What a snapshot is called in errors and logs
+    kind: &'static str,
+}
+
+impl SnapshotStore {
+    pub fn new(root: PathBuf, kind: &'static str) -> Self {
+        Self { root, kind }
+    }
+
+    pub fn objects_dir(&self) -> PathBuf {
+        self.root.join("objects")
+    }
+
+    pub fn snapshots_dir(&self) -> PathBuf {
+        self.root.join("snapshots")
+    }
+
+    pub fn object_path(&self, hash: &str) -> PathBuf {
+        self.objects_dir().join(&hash[..2]).join(hash)
+    }
+
+    pub fn manifest_path(&self, id: &str) -> PathBuf {
+        self.snapshots_dir().join(format!("{id}.json"))
+    }
+
+    /// Finds a free snapshot ID made of the current time and `name`
+    pub async fn new_id(&self, name: &str) -> Result<String> {
+        io::create_dir_all(self.snapshots_dir()).await?;
+
+        let name_base =
+            format!("{}_{name}", Local::now().format("%Y-%m-%d_%H-%M-%S"));
+        let file_name =
+            find_available_name(&self.snapshots_dir(), &name_base, ".json");
+        Ok(file_name.trim_end_matches(".json").to_string())
+    }
+
+    pub async fn read_manifest<M: SnapshotManifest>(
+        &self,
+        id: &str,
+    ) -> Result<M> {
+        let path = self.manifest_path(id);
+        if !path.is_file() {
+            return Err(ErrorKind::InputError(format!(
+                "Unknown {} {id}",
+                self.kind
+            ))
+            .into());
+        }
+        Ok(serde_json::from_slice(&io::read(&path).await?)?)
+    }
+
+    /// Reads the manifests of all snapshots, newest first, skipping those
+    /// that cannot be read
+    pub async fn read_manifests<M: SnapshotManifest>(&self) -> Result<Vec<M>> {
+        self.read_all_manifests(false).await
+    }
+
+    // Unless `strict`, unreadable manifests are skipped instead of failing
+    async fn read_all_manifests<M: SnapshotManifest>(
+        &self,
+        strict: bool,
+    ) -> Result<Vec<M>> {
+        let dir = self.snapshots_dir();
+        let mut manifests = Vec::new();
+        if !dir.exists() {
+            return Ok(manifests);
+        }
+
+        let mut entries = io::read_dir(&dir).await?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &dir))?
+        {
+            let path = entry.path();
+            if path.extension().is_none_or(|x| x != "json") {
+                continue;
+            }
+            match serde_json::from_slice::<M>(&io::read(&path).await?) {
+                Ok(manifest) => manifests.push(manifest),
+                Err(err) if strict => {
+                    return Err(ErrorKind::OtherError(format!(
+                        "Unreadable {} {}: {err}",
+                        self.kind,
+                        path.display()
+                    ))
+                    .into());
+                }
+                Err(err) => tracing::warn!(
+                    "Skipping unreadable {} {}: {err}",
+                    self.kind,
+                    path.display()
+                ),
+            }
+        }
+
+        manifests.sort_by_key(|x| std::cmp::Reverse(x.created()));
+        Ok(manifests)
+    }
+
+    pub async fn write_manifest<M: SnapshotManifest>(
+        &self,
+        id: &str,
+        manifest: &M,
+    ) -> Result<()> {
+        io::write(self.manifest_path(id), serde_json::to_vec(manifest)?)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_manifest(&self, id: &str) -> Result<()> {
+        io::remove_file(self.manifest_path(id)).await?;
+        Ok(())
+    }
+
+    /// Returns the hash of the first object that is missing from the store
+    pub fn find_missing<'a>(
+        &self,
+        mut hashes: impl Iterator<Item = &'a str>,
+    ) -> Option<&'a str> {
+        hashes.find(|hash| !self.object_path(hash).exists())
+    }
+
+    /// Writes an object to the store, returning its hash and the amount of
+    /// bytes that were actually added
+    pub async fn write_object(&self, data: &[u8]) -> Result<(String, u64)> {
+        let hash = sha1_smol::Sha1::from(data).hexdigest();
+        let path = self.object_path(&hash);
+        if path.exists() {
+            return Ok((hash, 0));
+        }
+
+        if let Some(parent) = path.parent() {
+            io::create_dir_all(parent).await?;
+        }
+        io::write(&path, data).await?;
+        Ok((hash, data.len() as u64))
+    }
+
+    /// Adds a file to the store by hard linking it, so keeping it costs no
+    /// space while the original exists. Only suitable for files the launcher
+    /// replaces rather than edits in place. Returns its hash and the amount
+    /// of bytes that were copied where hard links are not supported
+    pub async fn link_object(&self, file: &Path) -> Result<(String, u64)> {
+        let data = io::read(file).await?;
+        let hash = sha1_smol::Sha1::from(&data).hexdigest();
+        let path = self.object_path(&hash);
+        if path.exists() {
+            return Ok((hash, 0));
+        }
+
+        if let Some(parent) = path.parent() {
+            io::create_dir_all(parent).await?;
+        }
+        if let Err(err) = tokio::fs::hard_link(file, &path).await {
+            tracing::debug!(
+                "Could not hard link {} to {}, copying instead: {err}",
+                file.display(),
+                path.display()
+            );
+            io::write(&path, &data).await?;
+            return Ok((hash, data.len() as u64));
+        }
+        Ok((hash, 0))
+    }
+
+    pub async fn read_object(&self, hash: &str) -> Result<Vec<u8>> {
+        Ok(io::read(self.object_path(hash)).await?)
+    }
+
+    /// Removes every object that is not referenced by any snapshot,
+    /// returning the bytes freed. Fails without removing anything if a
+    /// manifest cannot be read, as its objects would be lost otherwise
+    pub async fn collect_garbage<M: SnapshotManifest>(&self) -> Result<u64> {
+        let manifests = self.read_all_manifests::<M>(true).await?;
+        let referenced = manifests
+            .iter()
+            .flat_map(|x| x.objects())
+            .collect::<HashSet<_>>();
+
+        let objects_dir = self.objects_dir();
+        if !objects_dir.exists() {
+            return Ok(0);
+        }
+
+        let mut freed = 0;
+        let mut walker = WalkDir::new(&objects_dir);
+        while let Some(entry) = walker.next().await {
+            let entry = entry.map_err(IOError::from_walkdir)?;
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if referenced.contains(file_name.as_str()) {
+                continue;
+            }
+
+            freed += io::metadata(entry.path()).await?.len();
+            io::remove_file(entry.path()).await?;
+        }
+
+        Ok(freed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize)]
+    struct TestManifest {
+        created: DateTime<Utc>,
+        objects: Vec<String>,
+    }
+
+    impl SnapshotManifest for TestManifest {
+        fn created(&self) -> DateTime<Utc> {
+            self.created
+        }
+
+        fn objects(&self) -> impl Iterator<Item = &str> {
+            self.objects.iter().map(|x| &**x)
+        }
+    }
+
+    #[tokio::test]
+    async fn garbage_collection_keeps_objects_of_unreadable_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(dir.path().to_path_buf(), "snapshot");
+
+        io::create_dir_all(store.snapshots_dir()).await.unwrap();
+        let (kept, _) = store.write_object(b"kept").await.unwrap();
+        let (orphaned, _) = store.write_object(b"orphaned").await.unwrap();
+        store
+            .write_manifest(
+                "a",
+                &TestManifest {
+                    created: Utc::now(),
+                    objects: vec![kept.clone()],
+                },
+            )
+            .await
+            .unwrap();
+
+        // A truncated manifest may reference any object
+        io::write(store.manifest_path("b"), b"{\"created\":")
+            .await
+            .unwrap();
+        assert!(store.collect_garbage::<TestManifest>().await.is_err());
+        assert!(store.object_path(&orphaned).exists());
+        assert_eq!(
+            store.read_manifests::<TestManifest>().await.unwrap().len(),
+            1
+        );
+
+        store.remove_manifest("b").await.unwrap();
+        assert_eq!(
+            store.collect_garbage::<TestManifest>().await.unwrap(),
+            b"orphaned".len() as u64
+        );
+        assert!(store.object_path(&kept).exists());
+        assert!(!store.object_path(&orphaned).exists());
+    }
+}